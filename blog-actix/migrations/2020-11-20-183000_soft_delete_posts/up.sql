@@ -0,0 +1,13 @@
+/**
+    SOFT DELETING POSTS
+
+    Rather than removing a post we set deleted_at to the time it was deleted
+
+    The column is nullable and a NULL means the post has not been deleted,
+    so every existing row stays visible after running this migration
+
+    After running diesel migration the posts table in schema.rs gains
+        deleted_at -> Nullable<Timestamp>
+**/
+
+ALTER TABLE posts ADD COLUMN deleted_at TIMESTAMP
@@ -0,0 +1,13 @@
+/**
+    VERSIONING POSTS
+
+    Every post gets a version number which goes up by one each time the post is updated,
+    an update has to say which version it was based on and is refused if the post has moved on since
+
+    The column is NOT NULL with a DEFAULT of 1 so the posts that already exist start out at version 1
+
+    After running diesel migration the posts table in schema.rs gains
+        version -> Integer
+**/
+
+ALTER TABLE posts ADD COLUMN version INTEGER NOT NULL DEFAULT 1
@@ -0,0 +1,16 @@
+/**
+    WHEN A POST WAS CREATED
+
+    Every post records the time it was created so the newest posts can be listed first
+
+    SQLite won't add a column whose default is CURRENT_TIMESTAMP to a table that already exists,
+    so the column gets a fixed default and the posts already in the table are then stamped with the current time
+    New posts are given the current time by create_post and create_posts when they are inserted
+
+    After running diesel migration the posts table in schema.rs gains
+        created_at -> Timestamp
+**/
+
+ALTER TABLE posts ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+
+UPDATE posts SET created_at = CURRENT_TIMESTAMP
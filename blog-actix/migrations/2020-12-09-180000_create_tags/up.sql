@@ -0,0 +1,24 @@
+/**
+    TAGGING POSTS
+
+    A tag is just a name, which is unique so each tag is stored once however many posts use it
+
+    post_tags joins the two, one row for each tag on a post
+    The primary key over both columns means a post can't have the same tag twice
+
+    After running diesel migration schema.rs gains the tags and post_tags tables
+    along with a joinable! from post_tags to each of posts and tags
+**/
+
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY NOT NULL,
+    name VARCHAR NOT NULL
+);
+
+CREATE UNIQUE INDEX tag_name_unique_idx ON tags (name);
+
+CREATE TABLE post_tags (
+    post_id INTEGER NOT NULL REFERENCES posts (id),
+    tag_id INTEGER NOT NULL REFERENCES tags (id),
+    PRIMARY KEY (post_id, tag_id)
+)
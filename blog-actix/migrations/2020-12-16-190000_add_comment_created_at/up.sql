@@ -0,0 +1,17 @@
+/**
+    WHEN A COMMENT WAS WRITTEN
+
+    Comments record the time they were written like posts do,
+    so a user's posts and comments can be put in one list in time order
+
+    The column gets a fixed default for the same reason as posts.created_at,
+    and the comments already in the table are stamped with the current time
+    New comments are given the current time by create_comment
+
+    After running diesel migration the comments table in schema.rs gains
+        created_at -> Timestamp
+**/
+
+ALTER TABLE comments ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00';
+
+UPDATE comments SET created_at = CURRENT_TIMESTAMP
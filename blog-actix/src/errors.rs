@@ -23,6 +23,19 @@
 
     OperationCanceled is related to a actix_web error having to do with an async operation
 
+    InvalidInput is for requests that are well formed JSON but that we refuse to act on,
+    e.g. an empty list of posts to bulk create, and it carries a message explaining why
+
+    ServiceUnavailable is for when we can't reach the database at all,
+    either no connection could be taken from the pool or a query on it failed,
+    and it is turned into a 503 so load balancers know to stop sending us traffic
+
+    Conflict is for a write based on data which has changed since it was read,
+    e.g. updating a post with an old version number, and it is turned into a 409
+
+    RateLimited is for a client which has made more write requests than the rate_limit middleware allows,
+    and it is turned into a 429 Too Many Requests
+
     DEBUG AND DISPLAY TRAITS
 
     Automatically implemented the Debug trait with the derive attribute on our struct
@@ -113,6 +126,10 @@ pub enum AppError {
     RecordNotFound,
     DatabaseError(diesel::result::Error),
     OperationCanceled,
+    InvalidInput(String),
+    ServiceUnavailable(String),
+    Conflict(String),
+    RateLimited,
 }
 
 #[derive(Debug, Serialize)]
@@ -127,6 +144,10 @@ impl fmt::Display for AppError {
             AppError::RecordNotFound => write!(f, "This record does not exist"),
             AppError::DatabaseError(e) => write!(f, "Database error: {:?}", e),
             AppError::OperationCanceled => write!(f, "The running operation was canceled"),
+            AppError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
+            AppError::ServiceUnavailable(msg) => write!(f, "Service unavailable: {}", msg),
+            AppError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            AppError::RateLimited => write!(f, "Too many requests, try again later"),
         }
     }
 }
@@ -156,6 +177,10 @@ impl actix_web::ResponseError for AppError {
         let mut builder = match self {
             AppError::RecordAlreadyExists => HttpResponse::BadRequest(),
             AppError::RecordNotFound => HttpResponse::NotFound(),
+            AppError::InvalidInput(_) => HttpResponse::BadRequest(),
+            AppError::ServiceUnavailable(_) => HttpResponse::ServiceUnavailable(),
+            AppError::Conflict(_) => HttpResponse::Conflict(),
+            AppError::RateLimited => HttpResponse::TooManyRequests(),
             _ => HttpResponse::InternalServerError(),
         };
         builder.json(ErrorResponse { err })
@@ -30,6 +30,30 @@
     The Pool type is just an Arc around a struct that manages connections
     so calling clone on the pool is the same as calling clone on an Arc
 
+    RUNNING MIGRATIONS AT STARTUP
+
+    A database that is missing a migration used to start up just fine
+    and then fail on the first query with an error about a missing table or column
+
+    The embed_migrations! macro from diesel_migrations reads the migrations directory at compile time
+    and generates an embedded_migrations module with the SQL of every migration built into the binary
+
+    Before binding the server, run takes a connection from the pool and calls embedded_migrations::run_with_output,
+    which applies any migrations that haven't been run yet and prints the name of each one it runs
+    Diesel keeps track of which have been run in the __diesel_schema_migrations table,
+    the same table the diesel CLI uses, so databases set up with diesel migration run carry on as they were
+
+    If the pool can't be created, no connection can be made, or a migration fails, run returns an io::Error saying so
+    and the server never starts, rather than starting in a state where every request fails
+
+    RATE LIMITING WRITES
+
+    The RateLimit middleware from the rate_limit module allows each client IP address
+    WRITES_PER_MINUTE requests that create or change something, anything more gets a 429
+
+    It is created once outside of the closure and cloned into each App,
+    so all of the workers share the same counters
+
     FnOnce TRAIT
 
     We are only guaranteed that it is okay to call the function once
@@ -39,6 +63,8 @@
 #[macro_use]
 extern crate diesel;
 #[macro_use]
+extern crate diesel_migrations;
+#[macro_use]
 extern crate serde_derive;
 
 use actix_web::{middleware, App, HttpServer};
@@ -47,8 +73,13 @@ use diesel::r2d2::{self, ConnectionManager};
 
 type Pool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
 
+embed_migrations!();
+
+const WRITES_PER_MINUTE: u32 = 30;
+
 mod errors;
 mod models;
+mod rate_limit;
 mod routes;
 mod schema;
 
@@ -65,19 +96,39 @@ impl Blog {
         let manager = ConnectionManager::<SqliteConnection>::new(database_url);
         let pool = r2d2::Pool::builder()
             .build(manager)
-            .expect("Failed to create pool.");
+            .map_err(|e| {
+                std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to create pool: {}", e))
+            })?;
+
+        run_migrations(&pool)?;
+
+        let rate_limit = rate_limit::RateLimit::per_minute(WRITES_PER_MINUTE);
 
         println!("Starting http server: 127.0.0.1:{}", self.port);
 
         HttpServer::new(move || {
             App::new()
                 .data(pool.clone())
+                .wrap(rate_limit.clone())
                 .wrap(middleware::Logger::default())
                 .configure(routes::users::configure)
                 .configure(routes::posts::configure)
                 .configure(routes::comments::configure)
+                .configure(routes::tags::configure)
+                .configure(routes::health::configure)
         })
         .bind(("127.0.0.1", self.port))?
         .run()
     }
-}
\ No newline at end of file
+}
+
+fn run_migrations(pool: &Pool) -> std::io::Result<()> {
+    use std::io::{Error, ErrorKind};
+
+    let conn = pool
+        .get()
+        .map_err(|e| Error::new(ErrorKind::Other, format!("Failed to connect to the database: {}", e)))?;
+
+    embedded_migrations::run_with_output(&conn, &mut std::io::stdout())
+        .map_err(|e| Error::new(ErrorKind::Other, format!("Failed to run database migrations: {}", e)))
+}
@@ -161,6 +161,23 @@
    prevents writing out a tuple with each column explicitly listed
    Depending on the struct you are serializing to you may or may not be able to use this shorthand
 
+   FETCHING A SINGLE POST OR COMMENT
+
+   find_post and find_comment look a row up by primary key with find,
+   which gives us a NotFound error, and therefore a 404, if it does not exist
+
+   These back the paths we hand out in the Location header when a post or comment is created
+
+   CREATING POSTS IN BULK
+
+   create_posts runs every insert inside a single transaction
+   so if any one of them fails the ? operator returns the error from the closure
+   and the whole batch is rolled back, leaving no partially created posts behind
+
+   As with create_post we cannot get the ids back from the inserts,
+   but since the transaction guarantees our rows are the newest ones
+   we can fetch the last n posts and reverse them to match the input order
+
    PUBLISH A POST
 
    The create_post method uses the database default for the published column and
@@ -176,6 +193,14 @@
    We are only updating a single column so we pass one expression to the set method,
    but if you want to update multiple columns you can pass a tuple to set instead
 
+   PUBLISHING ALL OF A USER'S DRAFTS
+
+   publish_user_drafts flips published on every unpublished post of a user with a single update,
+   the filtered table picks out the drafts and execute returns how many rows were changed
+
+   The user is looked up first inside the same transaction so an unknown user is a RecordNotFound
+   rather than a count of zero, and soft deleted drafts are left alone as nobody can see them anyway
+
    RETRIEVING POSTS
 
    Implementing two different ways of retrieving posts
@@ -199,7 +224,11 @@
    The argument to select is a tuple with two elements both of which are tuples representing the columns we want to fetch
    We then tell load the type to coerce these columns into
 
-   user_posts takes the user_id as input and retrieves a vector of posts or an empty set if the user does not exist
+   user_posts takes the user_id as input and retrieves a vector of posts
+
+   It looks the user up with find_user first, so a user that does not exist is a RecordNotFound, i.e. a 404,
+   while a user who simply hasn't written anything gets an empty vector, i.e. a 200 with []
+   Without that check both cases would come back as an empty list and a typo in the id would look like a quiet user
 
    COMMENT MODEL
 
@@ -210,6 +239,22 @@
    The returned result should contain the user who made the comment, along with the comment
    The post is unnecessary as it is known 
 
+   PAGINATING THE COMMENTS ON A POST
+
+   As threads grow fetching every comment at once gets expensive,
+   so post_comments takes a page number starting at 1 and a page size
+   and uses limit and offset to fetch just that slice, oldest comments first
+
+   The offset is the number of comments on all the earlier pages, i.e. (page - 1) * per_page
+   page comes straight from the query string, so page_offset works it out with checked_mul
+   and a page so large the offset doesn't fit in an i64 is an InvalidInput rather than an overflow
+   A page below 1 is an InvalidInput too, the route already makes a missing or smaller page 1
+
+   A separate count query gives the total number of comments on the post,
+   not just the ones on this page, so a client can work out how many pages there are
+
+   The page and the numbers describing it are returned together as a CommentPage
+
    FETCHING ALL COMMENTS BY A USER
 
    Notable information to include would be the post the comment is on,
@@ -228,6 +273,15 @@
    Using the select method narrows down which columns from the posts table we need to construct our PostWithComment struct
    and then load a tuple (Comment and PostWithComment) which results in getting the return type we want
 
+   user_comments_with_posts gives the same comments in a flatter shape for showing them in context,
+   each one a CommentWithPost of the comment alongside the id and title of its post
+
+   Deriving Queryable on a struct with a Comment in it works as the fields are built from the columns in order,
+   so selecting (comments::all_columns, posts::id, posts::title) lines up with comment, post_id, and post_title
+
+   A comment on a soft deleted post is left out, the post can't be fetched any more
+   so there is no context left to show for it, and the newest comments come first
+
    REVISING THE POST FETCHING FUNCTIONS
 
    By changing the result form of
@@ -258,6 +312,241 @@
 
    The last zip(comments) takes Vec<(Post, User)> and Vec<Vec<(Comment, User)>>
    and puts them together into a single vector of our desired return type
+
+   FETCHING A POST WITH ITS COMMENTS
+
+   post_with_comments returns a post and all of its comments in one go
+   so a client rendering a post page does not need a second request for the comments
+
+   The post is fetched first so a missing post turns into RecordNotFound
+   before we bother looking for comments
+
+   belonging_to works on a single post just as well as on a vector of them
+   and the comments come back with their authors the same way post_comments returns them
+
+   PostWithComments only exists to be serialized into {"post": {...}, "comments": [...]}
+   so it derives Serialize but none of the Diesel traits
+
+   COUNTING COMMENTS
+
+   posts_with_counts returns the same published posts as all_posts,
+   but instead of the comments themselves only the number of comments on each post
+
+   The counting is done by the database in a single query rather than a query per post:
+   posts are left joined with comments, grouped by the post id, and count is applied to the comment ids
+
+   Diesel 1.4 won't let us select the count function next to the post columns,
+   it has no way to know they are fine to mix once we group by the post id,
+   so the count is written as a small piece of raw SQL with sql::<BigInt> instead
+
+   A left join keeps posts which have no comments at all, the comment columns are just NULL for them,
+   and count ignores NULLs so those posts get a count of 0
+
+   The include_deleted flag is turned into a SQL expression with into_sql
+   so the query stays the same type whether or not it is set,
+   as group_by can't be added to a boxed query
+
+   Each row comes back as a (Post, i64) tuple which we turn into a PostWithCount for serializing
+
+   POSTS BY SEVERAL AUTHORS
+
+   posts_by_authors returns the published posts written by any of the given users, newest first,
+   which is what a feed of the people someone follows needs
+
+   eq_any turns the slice of ids into an IN (...) clause, so it is still a single query however many authors there are
+   Like search_posts each post comes with its author, so the feed can say who wrote what
+
+   The route makes sure the list isn't empty before calling this,
+   an empty IN () would just match nothing
+
+   A USER'S ACTIVITY
+
+   user_activity gathers up everything a user has written, their posts and their comments, into one list
+
+   Each row is wrapped in the Activity enum so the two kinds can live in the same vector
+   The serde tag attribute serializes a variant as the inner struct's fields plus a type field,
+   e.g. {"type":"post","id":1,...} or {"type":"comment","id":3,...}
+
+   Posts and comments both record when they were written in created_at,
+   so the two lists are merged into one newest first, going by id within each kind
+   CURRENT_TIMESTAMP only goes down to the second, so when a post and a comment share a created_at
+   the sort, which is stable, keeps the post ahead of the comment
+   Soft deleted posts are left out just like in user_posts
+
+   A user with no posts or comments simply gets an empty list
+
+   A USER'S SUMMARY
+
+   count_user_posts and count_user_comments use count which turns the query into a SELECT COUNT(*),
+   and get_result gives back the single i64 that comes out of it
+
+   user_summary checks the user exists first so an unknown id is a RecordNotFound rather than two zeros,
+   and then puts the user and both counts into a UserSummary
+   Soft deleted posts aren't counted, the same as they are left out of the user's activity
+
+   SEARCHING POSTS
+
+   search_posts finds the published posts whose title or body contains the search term
+   using LIKE with the term wrapped in % wildcards, which sqlite matches case insensitively for ASCII
+
+   SQLite has no notion of relevance without the FTS extension,
+   so we use a simple rule instead, a match in the title ranks above a match only in the body
+
+   That is done with two queries, first the title matches and then the body matches
+   which leave out anything the title query already found, and the results are concatenated
+   Within each group the newest posts come first just like all_posts
+
+   The term goes through escape_like like the prefix of search_users, see below,
+   so a % or _ in it only matches a literal % or _ rather than acting as a wildcard
+
+   SEARCHING USERS
+
+   search_users finds the users whose username starts with a prefix, for autocompleting a mention,
+   in alphabetical order and at most limit of them, keeping the limit within reason is left to the route
+
+   The prefix goes to LIKE with a % after it, so like the post search it ignores case for ASCII
+   Unlike the post search a % or _ typed by the user should only match itself,
+   so escape_like puts a backslash in front of them, and in front of any backslash,
+   and escape('\\') tells SQLite that backslash is the escape character for this LIKE
+
+   SEVERAL USERS AT ONCE
+
+   find_users_by_ids loads the users with any of the given ids in one query with eq_any, ordered by id,
+   so a client resolving the authors of a page of posts doesn't need a request per author
+
+   An id with no user is simply not in the result rather than a RecordNotFound,
+   the caller can tell which ones are missing by comparing the ids
+
+   THE MOST RECENT POSTS
+
+   recent_posts gives the newest published posts of every user with their authors, for a homepage feed
+
+   Posts are ordered by created_at, newest first, and then by id so posts created within the same second
+   still come out newest first, as sqlite stores CURRENT_TIMESTAMP to the second
+   limit turns into a LIMIT on the query so only as many rows as asked for are loaded,
+   keeping the limit within reason is left to the route
+
+   Each post is wrapped in a PostWithAuthor, which serializes to {"post":...,"author":...}
+   rather than the two element array a (Post, User) tuple gives
+
+   SOFT DELETING POSTS
+
+   Posts are never removed from the table, soft_delete_post sets deleted_at instead
+   so the post and its comments are still around if we ever need them
+
+   diesel::dsl::now is the SQL CURRENT_TIMESTAMP,
+   and as deleted_at is a nullable column we call nullable() on it so the types line up
+
+   Only posts which are not already deleted are updated so deleting twice keeps the original timestamp
+   Then we fetch the post by id which gives us a NotFound if there never was such a post
+
+   Diesel has no chrono support turned on in this crate so the timestamp comes back as the String sqlite stores
+
+   all_posts and user_posts leave out deleted posts unless include_deleted is true
+
+   A filter can only be added conditionally to a query that has been boxed with into_boxed,
+   as otherwise every extra filter changes the type of the query
+
+   UPDATING A POST
+
+   update_post changes the title and body of a post, but only if the caller saw the latest version of it
+
+   The version the caller expects is part of the WHERE clause of the update along with the id,
+   and the same update sets version to version + 1, so two clients who both read version 3
+   can't both write: whichever update runs second no longer matches any row
+
+   execute returns the number of rows that were changed
+   Zero rows means either there is no such post or its version has moved on,
+   so we look the post up to tell the two apart, find_post gives RecordNotFound for the first
+   and otherwise it is a Conflict which tells the caller the version they need to fetch
+
+   All of this happens in one transaction so the post can't change between the update and the check
+
+   PATCHING A POST
+
+   patch_post changes only the fields of a post that are given, e.g. just the title
+
+   PostPatch derives AsChangeset which lets a whole struct be passed to set,
+   each field becomes a column = value in the UPDATE and table_name says which table the columns belong to
+
+   The fields are Options and treat_none_as_null = "false" means a None field is left out of the UPDATE
+   rather than setting the column to NULL, which is also the default but spelled out as it's the whole point here
+
+   A patch is not checked against a version like update_post is, but it still bumps the version,
+   so a client holding an older copy of the post finds out on its next PUT
+   The version change is paired with the patch in a tuple, which set accepts as one changeset
+
+   The post is fetched after the update which gives RecordNotFound for a post that doesn't exist
+   A patch with no fields at all is turned away by the route before it gets here
+
+   REASSIGNING A POST
+
+   reassign_post hands a post over to another author by changing its user_id
+
+   Both the post and the new author are looked up first inside a transaction,
+   so a missing post or a missing user is a RecordNotFound before anything is written
+   and neither can disappear between the checks and the update
+
+   The post keeps its comments, its version and whether it is published, only the author changes
+
+   TRANSFERRING ALL OF A USER'S POSTS
+
+   transfer_posts is reassign_post for every post of a user at once, for merging two accounts
+
+   Both users are looked up first inside the transaction so either one missing is a RecordNotFound
+   and nothing is moved, then a single update sets the user_id of every post of from to to
+   That includes drafts and deleted posts, they all belong to the account being merged
+
+   execute gives back the number of rows it changed which is the number of posts transferred
+
+   PAGING THROUGH POSTS WITH A CURSOR
+
+   posts_after pages through the published posts newest first by id,
+   the cursor is the id of the last post the client has seen and the page is the posts with a smaller id
+
+   Unlike limit and offset the page doesn't shift when posts are created while paging,
+   a new post has a larger id than the cursor so it never pushes an already seen post onto the next page
+   Without a cursor the page starts from the newest post
+
+   One more row than the limit is loaded to find out whether there is anything after this page,
+   if there is, next_cursor is the id of the last post on the page, otherwise it is None
+   and the client knows it has reached the end without asking for an empty page
+
+   SIGNING UP
+
+   create_user_with_post creates a user and their first post in one transaction, for onboarding
+
+   It reuses create_user and create_post, their own transactions become savepoints inside the outer one,
+   so if either insert fails, e.g. the username is already taken and the unique index turns that into
+   RecordAlreadyExists, the ? operator hands the error back from the closure and neither row is kept
+
+   TAGGING POSTS
+
+   A post can have any number of tags, stored once each in the tags table and linked to posts through post_tags
+
+   tag_post trims each name and lowercases it so Rust and rust are the same tag, blank names are dropped
+   insert_or_ignore_into is INSERT OR IGNORE in SQLite, so a tag that already exists is left alone
+   rather than tripping the unique index, and the same goes for a post that already has the tag
+   The tags are then looked up by name to get their ids, all of them in one query with eq_any
+
+   create_post_with_tags creates the post and tags it in one transaction, so a post never ends up half tagged
+   create_posts takes the tags of each post along with its title and body and does the same for the whole batch
+
+   COUNTING TAGS
+
+   tag_counts lists every tag with the number of published posts using it, the most used first
+   and tags used equally often by name
+
+   tags are joined to post_tags and then to posts in one query, grouped by the tag,
+   and the count is raw SQL for the same reason as in posts_with_counts
+   As the joins are inner joins a tag whose posts are all drafts or deleted doesn't show up at all
+
+   CHECKING THE DATABASE
+
+   check_database runs a trivial SELECT 1 through diesel::sql_query
+   which lets the readiness endpoint confirm that a query actually makes it to the database and back
+
+   execute returns the number of rows affected which we don't care about here so we throw it away
  *
 ***/
 
@@ -265,7 +554,9 @@ use crate::errors::AppError;
 use crate::schema::{
    users,
    posts,
-   comments
+   comments,
+   tags,
+   post_tags
 };
 use diesel::prelude::*;
 
@@ -285,6 +576,17 @@ pub struct Post {
    pub title: String,
    pub body: String,
    pub published: bool,
+   pub deleted_at: Option<String>,
+   pub version: i32,
+   pub created_at: String,
+}
+
+#[derive(AsChangeset, Deserialize, Debug)]
+#[table_name = "posts"]
+#[changeset_options(treat_none_as_null = "false")]
+pub struct PostPatch {
+   pub title: Option<String>,
+   pub body: Option<String>,
 }
 
 #[derive(Queryable, Associations, Identifiable, Serialize, Debug)]
@@ -295,6 +597,7 @@ pub struct Comment {
    pub user_id: i32,
    pub post_id: i32,
    pub body: String,
+   pub created_at: String,
 }
 
 #[derive(Queryable, Serialize, Debug)]
@@ -304,6 +607,80 @@ pub struct PostWithComment {
    pub published: bool,
 }
 
+#[derive(Queryable, Serialize, Debug)]
+pub struct CommentWithPost {
+   pub comment: Comment,
+   pub post_id: i32,
+   pub post_title: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PostWithComments {
+   pub post: Post,
+   pub comments: Vec<(Comment, User)>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CommentPage {
+   pub comments: Vec<(Comment, User)>,
+   pub total: i64,
+   pub page: i64,
+   pub per_page: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PostPage {
+   pub posts: Vec<PostWithAuthor>,
+   pub next_cursor: Option<i32>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Activity {
+   Post(Post),
+   Comment(Comment),
+}
+
+impl Activity {
+   pub fn created_at(&self) -> &str {
+      match self {
+         Activity::Post(post) => &post.created_at,
+         Activity::Comment(comment) => &comment.created_at,
+      }
+   }
+}
+
+#[derive(Serialize, Debug)]
+pub struct PostWithCount {
+   pub post: Post,
+   pub comment_count: i64,
+}
+
+#[derive(Queryable, Serialize, Debug)]
+pub struct PostWithAuthor {
+   pub post: Post,
+   pub author: User,
+}
+
+#[derive(Queryable, Identifiable, Serialize, Debug, PartialEq)]
+pub struct Tag {
+   pub id: i32,
+   pub name: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TagWithCount {
+   pub tag: Tag,
+   pub post_count: i64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct UserSummary {
+   pub user: User,
+   pub post_count: i64,
+   pub comment_count: i64,
+}
+
 pub enum UserKey<'a> {
    Username(&'a str),
    ID(i32),
@@ -351,6 +728,7 @@ pub fn create_post(
             posts::user_id.eq(user.id),
             posts::title.eq(title),
             posts::body.eq(body),
+            posts::created_at.eq(diesel::dsl::now),
          ))
          .execute(conn)?;
 
@@ -362,6 +740,132 @@ pub fn create_post(
    })
 }
 
+pub fn find_post(conn: &SqliteConnection, post_id: i32) -> Result<Post> {
+   posts::table
+      .find(post_id)
+      .select(posts::all_columns)
+      .first::<Post>(conn)
+      .map_err(Into::into)
+}
+
+pub fn create_post_with_tags(
+   conn: &SqliteConnection,
+   user: &User,
+   title: &str,
+   body: &str,
+   tags: &[String]
+) -> Result<Post> {
+   conn.transaction(|| {
+      let post = create_post(conn, user, title, body)?;
+      tag_post(conn, post.id, tags)?;
+
+      Ok(post)
+   })
+}
+
+pub fn create_user_with_post(
+   conn: &SqliteConnection,
+   username: &str,
+   title: &str,
+   body: &str
+) -> Result<(User, Post)> {
+   conn.transaction(|| {
+      let user = create_user(conn, username)?;
+      let post = create_post(conn, &user, title, body)?;
+
+      Ok((user, post))
+   })
+}
+
+pub fn create_posts(
+   conn: &SqliteConnection,
+   user: &User,
+   new_posts: Vec<(String, String, Vec<String>)>
+) -> Result<Vec<Post>> {
+   conn.transaction(|| {
+      let count = new_posts.len() as i64;
+
+      for (title, body, _) in new_posts.iter() {
+         diesel::insert_into(posts::table)
+            .values((
+               posts::user_id.eq(user.id),
+               posts::title.eq(title),
+               posts::body.eq(body),
+               posts::created_at.eq(diesel::dsl::now),
+            ))
+            .execute(conn)?;
+      }
+
+      let mut created = posts::table
+         .order(posts::id.desc())
+         .limit(count)
+         .select(posts::all_columns)
+         .load::<Post>(conn)?;
+
+      created.reverse();
+
+      for (post, (_, _, tags)) in created.iter().zip(new_posts.iter()) {
+         tag_post(conn, post.id, tags)?;
+      }
+
+      Ok(created)
+   })
+}
+
+pub fn tag_post(conn: &SqliteConnection, post_id: i32, names: &[String]) -> Result<Vec<Tag>> {
+   let mut names: Vec<String> = names
+      .iter()
+      .map(|name| name.trim().to_lowercase())
+      .filter(|name| !name.is_empty())
+      .collect();
+   names.sort();
+   names.dedup();
+
+   if names.is_empty() {
+      return Ok(vec![]);
+   }
+
+   conn.transaction(|| {
+      for name in names.iter() {
+         diesel::insert_or_ignore_into(tags::table)
+            .values(tags::name.eq(name))
+            .execute(conn)?;
+      }
+
+      let found = tags::table
+         .filter(tags::name.eq_any(&names))
+         .order(tags::name)
+         .load::<Tag>(conn)?;
+
+      for tag in found.iter() {
+         diesel::insert_or_ignore_into(post_tags::table)
+            .values((post_tags::post_id.eq(post_id), post_tags::tag_id.eq(tag.id)))
+            .execute(conn)?;
+      }
+
+      Ok(found)
+   })
+}
+
+pub fn tag_counts(conn: &SqliteConnection) -> Result<Vec<TagWithCount>> {
+   use diesel::dsl::sql;
+   use diesel::sql_types::BigInt;
+
+   let rows = tags::table
+      .inner_join(post_tags::table.inner_join(posts::table))
+      .filter(posts::published.eq(true))
+      .filter(posts::deleted_at.is_null())
+      .group_by(tags::id)
+      .order((sql::<BigInt>("COUNT(posts.id)").desc(), tags::name.asc()))
+      .select((tags::all_columns, sql::<BigInt>("COUNT(posts.id)")))
+      .load::<(Tag, i64)>(conn)?;
+
+   Ok(rows
+      .into_iter()
+      .map(|(tag, post_count)| TagWithCount { tag, post_count })
+      .collect())
+}
+
 pub fn publish_post(conn: &SqliteConnection, post_id: i32) -> Result<Post> {
    conn.transaction(|| {
       diesel::update(posts::table.filter(posts::id.eq(post_id)))
@@ -376,12 +880,313 @@ pub fn publish_post(conn: &SqliteConnection, post_id: i32) -> Result<Post> {
    })
 }
 
-pub fn all_posts(conn: &SqliteConnection) -> Result<Vec<((Post, User), Vec<(Comment, User)>)>> {
-   let query = posts::table
+pub fn publish_user_drafts(conn: &SqliteConnection, user_id: i32) -> Result<usize> {
+   conn.transaction(|| {
+      find_user(conn, UserKey::ID(user_id))?;
+
+      diesel::update(
+         posts::table
+            .filter(posts::user_id.eq(user_id))
+            .filter(posts::published.eq(false))
+            .filter(posts::deleted_at.is_null()),
+      )
+      .set(posts::published.eq(true))
+      .execute(conn)
+      .map_err(Into::into)
+   })
+}
+
+pub fn update_post(
+   conn: &SqliteConnection,
+   post_id: i32,
+   expected_version: i32,
+   title: &str,
+   body: &str,
+) -> Result<Post> {
+   conn.transaction(|| {
+      let updated = diesel::update(
+         posts::table
+            .filter(posts::id.eq(post_id))
+            .filter(posts::version.eq(expected_version))
+      )
+      .set((
+         posts::title.eq(title),
+         posts::body.eq(body),
+         posts::version.eq(posts::version + 1),
+      ))
+      .execute(conn)?;
+
+      let post = find_post(conn, post_id)?;
+
+      if updated == 0 {
+         return Err(AppError::Conflict(format!(
+            "post {} is at version {}, not {}",
+            post_id, post.version, expected_version
+         )));
+      }
+
+      Ok(post)
+   })
+}
+
+pub fn patch_post(conn: &SqliteConnection, post_id: i32, patch: &PostPatch) -> Result<Post> {
+   conn.transaction(|| {
+      diesel::update(posts::table.filter(posts::id.eq(post_id)))
+         .set((patch, posts::version.eq(posts::version + 1)))
+         .execute(conn)?;
+
+      find_post(conn, post_id)
+   })
+}
+
+pub fn reassign_post(conn: &SqliteConnection, post_id: i32, new_user_id: i32) -> Result<Post> {
+   conn.transaction(|| {
+      find_post(conn, post_id)?;
+      find_user(conn, UserKey::ID(new_user_id))?;
+
+      diesel::update(posts::table.filter(posts::id.eq(post_id)))
+         .set(posts::user_id.eq(new_user_id))
+         .execute(conn)?;
+
+      find_post(conn, post_id)
+   })
+}
+
+pub fn transfer_posts(conn: &SqliteConnection, from: i32, to: i32) -> Result<usize> {
+   conn.transaction(|| {
+      find_user(conn, UserKey::ID(from))?;
+      find_user(conn, UserKey::ID(to))?;
+
+      diesel::update(posts::table.filter(posts::user_id.eq(from)))
+         .set(posts::user_id.eq(to))
+         .execute(conn)
+         .map_err(Into::into)
+   })
+}
+
+pub fn post_with_comments(conn: &SqliteConnection, post_id: i32) -> Result<PostWithComments> {
+   let post = posts::table
+      .find(post_id)
+      .select(posts::all_columns)
+      .first::<Post>(conn)?;
+
+   let comments = Comment::belonging_to(&post)
+      .inner_join(users::table)
+      .select((comments::all_columns, (users::id, users::username)))
+      .load::<(Comment, User)>(conn)?;
+
+   Ok(PostWithComments { post, comments })
+}
+
+pub fn posts_with_counts(conn: &SqliteConnection, include_deleted: bool) -> Result<Vec<PostWithCount>> {
+   use diesel::dsl::sql;
+   use diesel::sql_types::{BigInt, Bool};
+
+   let rows = posts::table
+      .left_join(comments::table)
+      .filter(posts::published.eq(true))
+      .filter(posts::deleted_at.is_null().or(include_deleted.into_sql::<Bool>()))
+      .group_by(posts::id)
+      .order(posts::id.desc())
+      .select((posts::all_columns, sql::<BigInt>("COUNT(comments.id)")))
+      .load::<(Post, i64)>(conn)?;
+
+   Ok(rows
+      .into_iter()
+      .map(|(post, comment_count)| PostWithCount { post, comment_count })
+      .collect())
+}
+
+pub fn posts_by_authors(
+   conn: &SqliteConnection,
+   author_ids: &[i32],
+   include_deleted: bool
+) -> Result<Vec<(Post, User)>> {
+   let mut query = posts::table
+      .filter(posts::user_id.eq_any(author_ids))
+      .filter(posts::published.eq(true))
+      .order(posts::id.desc())
+      .inner_join(users::table)
+      .select((posts::all_columns, (users::id, users::username)))
+      .into_boxed();
+
+   if !include_deleted {
+      query = query.filter(posts::deleted_at.is_null());
+   }
+
+   query.load::<(Post, User)>(conn).map_err(Into::into)
+}
+
+pub fn user_activity(conn: &SqliteConnection, user_id: i32) -> Result<Vec<Activity>> {
+   let posts = posts::table
+      .filter(posts::user_id.eq(user_id))
+      .filter(posts::deleted_at.is_null())
+      .order((posts::created_at.desc(), posts::id.desc()))
+      .select(posts::all_columns)
+      .load::<Post>(conn)?;
+
+   let comments = comments::table
+      .filter(comments::user_id.eq(user_id))
+      .order((comments::created_at.desc(), comments::id.desc()))
+      .select(comments::all_columns)
+      .load::<Comment>(conn)?;
+
+   let mut activity: Vec<Activity> = posts
+      .into_iter()
+      .map(Activity::Post)
+      .chain(comments.into_iter().map(Activity::Comment))
+      .collect();
+
+   activity.sort_by(|a, b| b.created_at().cmp(a.created_at()));
+
+   Ok(activity)
+}
+
+pub fn count_user_posts(conn: &SqliteConnection, user_id: i32) -> Result<i64> {
+   posts::table
+      .filter(posts::user_id.eq(user_id))
+      .filter(posts::deleted_at.is_null())
+      .count()
+      .get_result(conn)
+      .map_err(Into::into)
+}
+
+pub fn count_user_comments(conn: &SqliteConnection, user_id: i32) -> Result<i64> {
+   comments::table
+      .filter(comments::user_id.eq(user_id))
+      .count()
+      .get_result(conn)
+      .map_err(Into::into)
+}
+
+pub fn user_summary(conn: &SqliteConnection, user_id: i32) -> Result<UserSummary> {
+   let user = find_user(conn, UserKey::ID(user_id))?;
+   let post_count = count_user_posts(conn, user_id)?;
+   let comment_count = count_user_comments(conn, user_id)?;
+
+   Ok(UserSummary { user, post_count, comment_count })
+}
+
+pub fn search_posts(conn: &SqliteConnection, term: &str) -> Result<Vec<(Post, User)>> {
+   let pattern = format!("%{}%", escape_like(term));
+
+   let title_matches = posts::table
+      .filter(posts::published.eq(true))
+      .filter(posts::deleted_at.is_null())
+      .filter(posts::title.like(&pattern).escape('\\'))
+      .order(posts::id.desc())
+      .inner_join(users::table)
+      .select((posts::all_columns, (users::id, users::username)))
+      .load::<(Post, User)>(conn)?;
+
+   let body_matches = posts::table
+      .filter(posts::published.eq(true))
+      .filter(posts::deleted_at.is_null())
+      .filter(posts::body.like(&pattern).escape('\\'))
+      .filter(posts::title.not_like(&pattern).escape('\\'))
+      .order(posts::id.desc())
+      .inner_join(users::table)
+      .select((posts::all_columns, (users::id, users::username)))
+      .load::<(Post, User)>(conn)?;
+
+   Ok(title_matches.into_iter().chain(body_matches).collect())
+}
+
+pub fn search_users(conn: &SqliteConnection, prefix: &str, limit: i64) -> Result<Vec<User>> {
+   let pattern = format!("{}%", escape_like(prefix));
+
+   users::table
+      .filter(users::username.like(pattern).escape('\\'))
+      .order(users::username)
+      .limit(limit)
+      .select((users::id, users::username))
+      .load::<User>(conn)
+      .map_err(Into::into)
+}
+
+pub fn find_users_by_ids(conn: &SqliteConnection, ids: &[i32]) -> Result<Vec<User>> {
+   users::table
+      .filter(users::id.eq_any(ids))
+      .order(users::id)
+      .select((users::id, users::username))
+      .load::<User>(conn)
+      .map_err(Into::into)
+}
+
+fn escape_like(s: &str) -> String {
+   s.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+pub fn recent_posts(conn: &SqliteConnection, limit: i64) -> Result<Vec<PostWithAuthor>> {
+   posts::table
+      .filter(posts::published.eq(true))
+      .filter(posts::deleted_at.is_null())
+      .order((posts::created_at.desc(), posts::id.desc()))
+      .limit(limit)
+      .inner_join(users::table)
+      .select((posts::all_columns, (users::id, users::username)))
+      .load::<PostWithAuthor>(conn)
+      .map_err(Into::into)
+}
+
+pub fn posts_after(conn: &SqliteConnection, cursor: Option<i32>, limit: i64) -> Result<PostPage> {
+   let mut query = posts::table
+      .filter(posts::published.eq(true))
+      .filter(posts::deleted_at.is_null())
+      .order(posts::id.desc())
+      .limit(limit + 1)
+      .inner_join(users::table)
+      .select((posts::all_columns, (users::id, users::username)))
+      .into_boxed();
+
+   if let Some(cursor) = cursor {
+      query = query.filter(posts::id.lt(cursor));
+   }
+
+   let mut posts = query.load::<PostWithAuthor>(conn)?;
+
+   let next_cursor = if posts.len() as i64 > limit {
+      posts.truncate(limit as usize);
+      posts.last().map(|p| p.post.id)
+   } else {
+      None
+   };
+
+   Ok(PostPage { posts, next_cursor })
+}
+
+pub fn soft_delete_post(conn: &SqliteConnection, post_id: i32) -> Result<Post> {
+   conn.transaction(|| {
+      diesel::update(
+         posts::table
+            .filter(posts::id.eq(post_id))
+            .filter(posts::deleted_at.is_null())
+      )
+      .set(posts::deleted_at.eq(diesel::dsl::now.nullable()))
+      .execute(conn)?;
+
+      posts::table
+         .find(post_id)
+         .select(posts::all_columns)
+         .first(conn)
+         .map_err(Into::into)
+   })
+}
+
+pub fn all_posts(
+   conn: &SqliteConnection,
+   include_deleted: bool
+) -> Result<Vec<((Post, User), Vec<(Comment, User)>)>> {
+   let mut query = posts::table
       .order(posts::id.desc())
       .filter(posts::published.eq(true))
       .inner_join(users::table)
-      .select((posts::all_columns, (users::id, users::username)));
+      .select((posts::all_columns, (users::id, users::username)))
+      .into_boxed();
+
+   if !include_deleted {
+      query = query.filter(posts::deleted_at.is_null());
+   }
 
    let posts_with_user = query.load::<(Post, User)>(conn)?;
 
@@ -398,13 +1203,22 @@ pub fn all_posts(conn: &SqliteConnection) -> Result<Vec<((Post, User), Vec<(Comm
 
 pub fn user_posts(
    conn: &SqliteConnection,
-   user_id: i32
+   user_id: i32,
+   include_deleted: bool
 ) -> Result<Vec<(Post, Vec<(Comment, User)>)>> {
-   let posts = posts::table
+   find_user(conn, UserKey::ID(user_id))?;
+
+   let mut query = posts::table
       .filter(posts::user_id.eq(user_id))
       .order(posts::id.desc())
       .select(posts::all_columns)
-      .load::<Post>(conn)?;
+      .into_boxed();
+
+   if !include_deleted {
+      query = query.filter(posts::deleted_at.is_null());
+   }
+
+   let posts = query.load::<Post>(conn)?;
 
    let comments = Comment::belonging_to(&posts)
       .inner_join(users::table)
@@ -427,6 +1241,7 @@ pub fn create_comment(
             comments::user_id.eq(user_id),
             comments::post_id.eq(post_id),
             comments::body.eq(body),
+            comments::created_at.eq(diesel::dsl::now),
          ))
          .execute(conn)?;
 
@@ -438,13 +1253,52 @@ pub fn create_comment(
    })
 }
 
-pub fn post_comments(conn: &SqliteConnection, post_id: i32) -> Result<Vec<(Comment, User)>> {
+pub fn find_comment(conn: &SqliteConnection, comment_id: i32) -> Result<Comment> {
    comments::table
+      .find(comment_id)
+      .select(comments::all_columns)
+      .first::<Comment>(conn)
+      .map_err(Into::into)
+}
+
+pub fn post_comments(
+   conn: &SqliteConnection,
+   post_id: i32,
+   page: i64,
+   per_page: i64
+) -> Result<CommentPage> {
+   let total = comments::table
       .filter(comments::post_id.eq(post_id))
+      .count()
+      .get_result::<i64>(conn)?;
+
+   let offset = page_offset(page, per_page)?;
+
+   let comments = comments::table
+      .filter(comments::post_id.eq(post_id))
+      .order(comments::id.asc())
+      .limit(per_page)
+      .offset(offset)
       .inner_join(users::table)
       .select((comments::all_columns, (users::id, users::username)))
-      .load::<(Comment, User)>(conn)
-      .map_err(Into::into)
+      .load::<(Comment, User)>(conn)?;
+
+   Ok(CommentPage {
+      comments,
+      total,
+      page,
+      per_page,
+   })
+}
+
+fn page_offset(page: i64, per_page: i64) -> Result<i64> {
+   if page < 1 {
+      return Err(AppError::InvalidInput(format!("page {} is before the first page", page)));
+   }
+
+   (page - 1)
+      .checked_mul(per_page)
+      .ok_or_else(|| AppError::InvalidInput(format!("page {} is too large", page)))
 }
 
 pub fn user_comments(
@@ -460,4 +1314,676 @@ pub fn user_comments(
       ))
       .load::<(Comment, PostWithComment)>(conn)
       .map_err(Into::into)
-}
\ No newline at end of file
+}
+
+pub fn user_comments_with_posts(conn: &SqliteConnection, user_id: i32) -> Result<Vec<CommentWithPost>> {
+   comments::table
+      .filter(comments::user_id.eq(user_id))
+      .inner_join(posts::table)
+      .filter(posts::deleted_at.is_null())
+      .order(comments::id.desc())
+      .select((comments::all_columns, posts::id, posts::title))
+      .load::<CommentWithPost>(conn)
+      .map_err(Into::into)
+}
+
+pub fn check_database(conn: &SqliteConnection) -> Result<()> {
+   diesel::sql_query("SELECT 1")
+      .execute(conn)
+      .map(|_| ())
+      .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+
+   fn test_connection() -> SqliteConnection {
+      let conn = SqliteConnection::establish(":memory:").unwrap();
+      crate::embedded_migrations::run(&conn).unwrap();
+      conn
+   }
+
+   #[test]
+   fn posts_by_authors_only_returns_the_listed_authors() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let sarah = create_user(&conn, "sarah").unwrap();
+      let tom = create_user(&conn, "tom").unwrap();
+
+      for user in &[&ruben, &sarah, &tom] {
+         let post = create_post(&conn, user, "Hello", "from me").unwrap();
+         publish_post(&conn, post.id).unwrap();
+      }
+      create_post(&conn, &ruben, "Draft", "not published yet").unwrap();
+
+      let feed = posts_by_authors(&conn, &[ruben.id, sarah.id], false).unwrap();
+      let authors: Vec<&str> = feed.iter().map(|(_, user)| user.username.as_str()).collect();
+
+      assert_eq!(authors, vec!["sarah", "ruben"]);
+   }
+
+   #[test]
+   fn recent_posts_are_newest_first_and_limited() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let sarah = create_user(&conn, "sarah").unwrap();
+
+      for (user, title) in &[(&ruben, "First"), (&sarah, "Second"), (&ruben, "Third"), (&sarah, "Fourth")] {
+         let post = create_post(&conn, user, title, "Hello").unwrap();
+         publish_post(&conn, post.id).unwrap();
+      }
+      create_post(&conn, &ruben, "Draft", "not published yet").unwrap();
+
+      diesel::update(posts::table.filter(posts::title.eq("Fourth")))
+         .set(posts::created_at.eq("2020-01-01 00:00:00"))
+         .execute(&conn)
+         .unwrap();
+
+      let recent = recent_posts(&conn, 3).unwrap();
+      let titles: Vec<&str> = recent.iter().map(|p| p.post.title.as_str()).collect();
+
+      assert_eq!(titles, vec!["Third", "Second", "First"]);
+      assert_eq!(recent[0].author, ruben);
+      assert_eq!(recent_posts(&conn, 10).unwrap().len(), 4);
+   }
+
+   #[test]
+   fn posts_after_pages_without_overlap_or_gaps() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+
+      let mut ids = Vec::new();
+      for title in &["One", "Two", "Three", "Four", "Five"] {
+         let post = create_post(&conn, &ruben, title, "Hello").unwrap();
+         publish_post(&conn, post.id).unwrap();
+         ids.push(post.id);
+      }
+      create_post(&conn, &ruben, "Draft", "not published yet").unwrap();
+      ids.reverse();
+
+      let ids_of = |page: &PostPage| -> Vec<i32> { page.posts.iter().map(|p| p.post.id).collect() };
+
+      let first = posts_after(&conn, None, 3).unwrap();
+      assert_eq!(ids_of(&first), ids[..3].to_vec());
+      assert_eq!(first.next_cursor, Some(ids[2]));
+
+      let newer = create_post(&conn, &ruben, "Six", "Hello").unwrap();
+      publish_post(&conn, newer.id).unwrap();
+
+      let second = posts_after(&conn, first.next_cursor, 3).unwrap();
+      assert_eq!(ids_of(&second), ids[3..].to_vec());
+      assert_eq!(second.next_cursor, None);
+   }
+
+   #[test]
+   fn patch_post_only_changes_the_given_fields() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let post = create_post(&conn, &ruben, "Hello", "Hello to all").unwrap();
+
+      let patch = PostPatch {
+         title: Some("Hi".to_owned()),
+         body: None,
+      };
+      let patched = patch_post(&conn, post.id, &patch).unwrap();
+
+      assert_eq!(patched.title, "Hi");
+      assert_eq!(patched.body, "Hello to all");
+      assert_eq!(patched.version, post.version + 1);
+
+      match patch_post(&conn, post.id + 1, &patch) {
+         Err(AppError::RecordNotFound) => {}
+         other => panic!("expected RecordNotFound, got {:?}", other),
+      }
+   }
+
+   #[test]
+   fn reassign_post_changes_the_author() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let sarah = create_user(&conn, "sarah").unwrap();
+      let post = create_post(&conn, &ruben, "Hello", "Hello to all").unwrap();
+
+      let reassigned = reassign_post(&conn, post.id, sarah.id).unwrap();
+
+      assert_eq!(reassigned.user_id, sarah.id);
+      assert_eq!(reassigned.title, "Hello");
+      assert_eq!(find_post(&conn, post.id).unwrap().user_id, sarah.id);
+
+      match reassign_post(&conn, post.id + 1, sarah.id) {
+         Err(AppError::RecordNotFound) => {}
+         other => panic!("expected RecordNotFound, got {:?}", other),
+      }
+
+      match reassign_post(&conn, post.id, sarah.id + 1) {
+         Err(AppError::RecordNotFound) => {}
+         other => panic!("expected RecordNotFound, got {:?}", other),
+      }
+      assert_eq!(find_post(&conn, post.id).unwrap().user_id, sarah.id);
+   }
+
+   #[test]
+   fn transfer_posts_moves_every_post() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let sarah = create_user(&conn, "sarah").unwrap();
+      create_post(&conn, &ruben, "Hello", "Hello to all").unwrap();
+      create_post(&conn, &ruben, "Again", "Hello again").unwrap();
+      create_post(&conn, &sarah, "Mine", "Already hers").unwrap();
+
+      assert_eq!(transfer_posts(&conn, ruben.id, sarah.id).unwrap(), 2);
+      assert!(user_posts(&conn, ruben.id, true).unwrap().is_empty());
+      assert_eq!(user_posts(&conn, sarah.id, true).unwrap().len(), 3);
+
+      match transfer_posts(&conn, sarah.id, sarah.id + 1) {
+         Err(AppError::RecordNotFound) => {}
+         other => panic!("expected RecordNotFound, got {:?}", other),
+      }
+      assert_eq!(user_posts(&conn, sarah.id, true).unwrap().len(), 3);
+   }
+
+   #[test]
+   fn create_user_with_post_rolls_back_on_a_taken_username() {
+      let conn = test_connection();
+      let (ruben, post) = create_user_with_post(&conn, "ruben", "Hello", "My first post").unwrap();
+      assert_eq!(post.user_id, ruben.id);
+
+      match create_user_with_post(&conn, "ruben", "Again", "Should not exist") {
+         Err(AppError::RecordAlreadyExists) => {}
+         other => panic!("expected RecordAlreadyExists, got {:?}", other),
+      }
+
+      assert_eq!(users::table.count().get_result::<i64>(&conn).unwrap(), 1);
+      assert_eq!(posts::table.count().get_result::<i64>(&conn).unwrap(), 1);
+   }
+
+   #[test]
+   fn soft_deleted_posts_leave_the_lists_but_stay_in_the_table() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let kept = create_post(&conn, &ruben, "Kept", "Still here").unwrap();
+      let gone = create_post(&conn, &ruben, "Gone", "Deleted").unwrap();
+      publish_post(&conn, kept.id).unwrap();
+      publish_post(&conn, gone.id).unwrap();
+
+      let deleted = soft_delete_post(&conn, gone.id).unwrap();
+      assert!(deleted.deleted_at.is_some());
+
+      let user_titles = |include_deleted: bool| -> Vec<String> {
+         user_posts(&conn, ruben.id, include_deleted)
+            .unwrap()
+            .into_iter()
+            .map(|(post, _)| post.title)
+            .collect()
+      };
+      let all_titles = |include_deleted: bool| -> Vec<String> {
+         all_posts(&conn, include_deleted)
+            .unwrap()
+            .into_iter()
+            .map(|((post, _), _)| post.title)
+            .collect()
+      };
+
+      assert_eq!(user_titles(false), vec!["Kept"]);
+      assert_eq!(all_titles(false), vec!["Kept"]);
+      assert_eq!(user_titles(true), vec!["Gone", "Kept"]);
+      assert_eq!(all_titles(true), vec!["Gone", "Kept"]);
+
+      assert_eq!(find_post(&conn, gone.id).unwrap().deleted_at, deleted.deleted_at);
+      assert_eq!(posts::table.count().get_result::<i64>(&conn).unwrap(), 2);
+   }
+
+   #[test]
+   fn post_with_comments_nests_every_comment() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let sarah = create_user(&conn, "sarah").unwrap();
+      let post = create_post(&conn, &ruben, "Hello", "Hello to all").unwrap();
+      let other = create_post(&conn, &ruben, "Other", "Another post").unwrap();
+
+      let empty = post_with_comments(&conn, post.id).unwrap();
+      assert_eq!(empty.post.id, post.id);
+      assert!(empty.comments.is_empty());
+
+      create_comment(&conn, sarah.id, post.id, "First").unwrap();
+      create_comment(&conn, ruben.id, post.id, "Second").unwrap();
+      create_comment(&conn, sarah.id, other.id, "Elsewhere").unwrap();
+
+      let full = post_with_comments(&conn, post.id).unwrap();
+      let comments: Vec<(&str, &str)> = full
+         .comments
+         .iter()
+         .map(|(comment, author)| (comment.body.as_str(), author.username.as_str()))
+         .collect();
+
+      assert_eq!(full.post.id, post.id);
+      assert_eq!(comments, vec![("First", "sarah"), ("Second", "ruben")]);
+
+      match post_with_comments(&conn, other.id + 1) {
+         Err(AppError::RecordNotFound) => {}
+         other => panic!("expected RecordNotFound, got {:?}", other),
+      }
+   }
+
+   #[test]
+   fn search_posts_ranks_title_matches_first() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+
+      for (title, body) in &[
+         ("Rust tips", "About tooling"),
+         ("Cooking", "I love rust and pasta"),
+         ("Rust news", "Mentions rust in the body too"),
+         ("Unrelated", "Nothing to see"),
+      ] {
+         let post = create_post(&conn, &ruben, title, body).unwrap();
+         publish_post(&conn, post.id).unwrap();
+      }
+      create_post(&conn, &ruben, "Rust draft", "not published yet").unwrap();
+
+      let titles: Vec<String> = search_posts(&conn, "rust")
+         .unwrap()
+         .into_iter()
+         .map(|(post, _)| post.title)
+         .collect();
+
+      assert_eq!(titles, vec!["Rust news", "Rust tips", "Cooking"]);
+   }
+
+   #[test]
+   fn search_posts_matches_wildcards_literally() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+
+      for (title, body) in &[("100% rust", "Really"), ("1000 rust", "Tips"), ("snake_case", "Names"), ("snakes", "Case")] {
+         let post = create_post(&conn, &ruben, title, body).unwrap();
+         publish_post(&conn, post.id).unwrap();
+      }
+
+      let titles = |term: &str| -> Vec<String> {
+         search_posts(&conn, term)
+            .unwrap()
+            .into_iter()
+            .map(|(post, _)| post.title)
+            .collect()
+      };
+
+      assert_eq!(titles("100%"), vec!["100% rust"]);
+      assert_eq!(titles("snake_"), vec!["snake_case"]);
+   }
+
+   #[test]
+   fn user_activity_mixes_posts_and_comments_newest_first() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let sarah = create_user(&conn, "sarah").unwrap();
+
+      let older = create_post(&conn, &ruben, "Older", "Hello").unwrap();
+      let newer = create_post(&conn, &ruben, "Newer", "Hello").unwrap();
+      let comment = create_comment(&conn, ruben.id, older.id, "Replying to myself").unwrap();
+      create_comment(&conn, sarah.id, older.id, "Not ruben").unwrap();
+
+      diesel::update(posts::table.filter(posts::id.eq(older.id)))
+         .set(posts::created_at.eq("2020-01-01 00:00:00"))
+         .execute(&conn)
+         .unwrap();
+      diesel::update(comments::table.filter(comments::id.eq(comment.id)))
+         .set(comments::created_at.eq("2020-01-02 00:00:00"))
+         .execute(&conn)
+         .unwrap();
+      diesel::update(posts::table.filter(posts::id.eq(newer.id)))
+         .set(posts::created_at.eq("2020-01-03 00:00:00"))
+         .execute(&conn)
+         .unwrap();
+
+      let activity: Vec<(&str, i32)> = user_activity(&conn, ruben.id)
+         .unwrap()
+         .iter()
+         .map(|item| match item {
+            Activity::Post(post) => ("post", post.id),
+            Activity::Comment(comment) => ("comment", comment.id),
+         })
+         .collect();
+
+      assert_eq!(activity, vec![("post", newer.id), ("comment", comment.id), ("post", older.id)]);
+
+      let quiet = create_user(&conn, "quiet").unwrap();
+      assert!(user_activity(&conn, quiet.id).unwrap().is_empty());
+   }
+
+   #[test]
+   fn posts_with_counts_counts_the_comments_of_published_posts() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+
+      for (title, comments) in &[("Quiet", 0), ("One", 1), ("Busy", 3)] {
+         let post = create_post(&conn, &ruben, title, "Hello").unwrap();
+         publish_post(&conn, post.id).unwrap();
+
+         for _ in 0..*comments {
+            create_comment(&conn, ruben.id, post.id, "Nice").unwrap();
+         }
+      }
+
+      let draft = create_post(&conn, &ruben, "Draft", "not published yet").unwrap();
+      create_comment(&conn, ruben.id, draft.id, "Early").unwrap();
+
+      let counts: Vec<(String, i64)> = posts_with_counts(&conn, false)
+         .unwrap()
+         .into_iter()
+         .map(|p| (p.post.title, p.comment_count))
+         .collect();
+
+      assert_eq!(
+         counts,
+         vec![("Busy".to_string(), 3), ("One".to_string(), 1), ("Quiet".to_string(), 0)]
+      );
+   }
+
+   #[test]
+   fn update_post_with_a_stale_version_is_a_conflict() {
+      use actix_web::http::StatusCode;
+      use actix_web::ResponseError;
+
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let post = create_post(&conn, &ruben, "Hello", "First draft").unwrap();
+
+      let updated = update_post(&conn, post.id, post.version, "Hello", "Second draft").unwrap();
+      assert_eq!(updated.version, post.version + 1);
+
+      match update_post(&conn, post.id, post.version, "Hello", "Lost update") {
+         Err(err @ AppError::Conflict(_)) => {
+            assert_eq!(err.error_response().status(), StatusCode::CONFLICT);
+         }
+         other => panic!("expected Conflict, got {:?}", other),
+      }
+
+      let unchanged = find_post(&conn, post.id).unwrap();
+      assert_eq!(unchanged.body, "Second draft");
+      assert_eq!(unchanged.version, updated.version);
+   }
+
+   #[test]
+   fn create_posts_inserts_nothing_when_one_post_fails() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+
+      diesel::sql_query(
+         "CREATE TRIGGER reject_bad_title BEFORE INSERT ON posts WHEN NEW.title = 'bad' \
+          BEGIN SELECT RAISE(ABORT, 'bad title'); END",
+      )
+      .execute(&conn)
+      .unwrap();
+
+      let batch = vec![
+         ("good".to_string(), "First".to_string(), vec!["rust".to_string()]),
+         ("bad".to_string(), "Second".to_string(), vec![]),
+         ("also good".to_string(), "Third".to_string(), vec![]),
+      ];
+
+      assert!(create_posts(&conn, &ruben, batch).is_err());
+
+      let posts: i64 = posts::table.count().get_result(&conn).unwrap();
+      let tags: i64 = tags::table.count().get_result(&conn).unwrap();
+      assert_eq!((posts, tags), (0, 0));
+   }
+
+   #[test]
+   fn tag_post_normalizes_and_reuses_tags() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let first = create_post_with_tags(&conn, &ruben, "Hello", "one", &["Rust".to_owned(), " web ".to_owned()]).unwrap();
+      let second = create_post(&conn, &ruben, "Again", "two").unwrap();
+
+      let tags = tag_post(&conn, second.id, &["rust".to_owned(), "RUST".to_owned(), "".to_owned()]).unwrap();
+      let names: Vec<&str> = tags.iter().map(|t| t.name.as_str()).collect();
+      assert_eq!(names, vec!["rust"]);
+
+      let all = tags::table.order(tags::name).load::<Tag>(&conn).unwrap();
+      let names: Vec<&str> = all.iter().map(|t| t.name.as_str()).collect();
+      assert_eq!(names, vec!["rust", "web"]);
+
+      tag_post(&conn, first.id, &["rust".to_owned()]).unwrap();
+      let links: i64 = post_tags::table.count().get_result(&conn).unwrap();
+      assert_eq!(links, 3);
+   }
+
+   #[test]
+   fn tag_counts_only_count_published_posts() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let tagged = |title: &str, tags: &[&str], publish: bool| {
+         let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+         let post = create_post_with_tags(&conn, &ruben, title, "body", &tags).unwrap();
+         if publish {
+            publish_post(&conn, post.id).unwrap();
+         }
+         post
+      };
+
+      tagged("One", &["rust", "web"], true);
+      tagged("Two", &["rust"], true);
+      tagged("Three", &["diesel", "web"], true);
+      tagged("Draft", &["rust", "drafts"], false);
+      let gone = tagged("Gone", &["diesel"], true);
+      soft_delete_post(&conn, gone.id).unwrap();
+
+      let counts: Vec<(String, i64)> = tag_counts(&conn)
+         .unwrap()
+         .into_iter()
+         .map(|c| (c.tag.name, c.post_count))
+         .collect();
+
+      assert_eq!(
+         counts,
+         vec![("rust".to_owned(), 2), ("web".to_owned(), 2), ("diesel".to_owned(), 1)]
+      );
+   }
+
+   #[test]
+   fn search_users_matches_the_prefix_up_to_the_limit() {
+      let conn = test_connection();
+      for name in &["sarah", "rust_fan", "rusty", "ruben", "100%real", "100 percent"] {
+         create_user(&conn, name).unwrap();
+      }
+
+      let names = |prefix: &str, limit: i64| -> Vec<String> {
+         search_users(&conn, prefix, limit)
+            .unwrap()
+            .into_iter()
+            .map(|u| u.username)
+            .collect()
+      };
+
+      assert_eq!(names("ru", 10), vec!["ruben", "rust_fan", "rusty"]);
+      assert_eq!(names("ru", 2), vec!["ruben", "rust_fan"]);
+      assert_eq!(names("rust_", 10), vec!["rust_fan"]);
+      assert_eq!(names("100%", 10), vec!["100%real"]);
+      assert!(names("x", 10).is_empty());
+   }
+
+   #[test]
+   fn find_users_by_ids_skips_the_missing_ones() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      create_user(&conn, "sarah").unwrap();
+      let tom = create_user(&conn, "tom").unwrap();
+
+      let users = find_users_by_ids(&conn, &[tom.id, 999, ruben.id]).unwrap();
+
+      assert_eq!(users, vec![ruben, tom]);
+      assert!(find_users_by_ids(&conn, &[999]).unwrap().is_empty());
+   }
+
+   #[test]
+   fn page_offset_skips_the_earlier_pages() {
+      assert_eq!(page_offset(1, 20).unwrap(), 0);
+      assert_eq!(page_offset(2, 20).unwrap(), 20);
+      assert_eq!(page_offset(5, 3).unwrap(), 12);
+
+      for page in &[0, std::i64::MAX] {
+         match page_offset(*page, 20) {
+            Err(AppError::InvalidInput(_)) => {}
+            other => panic!("expected InvalidInput for page {}, got {:?}", page, other),
+         }
+      }
+   }
+
+   #[test]
+   fn post_comments_pages_and_counts_every_comment() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let post = create_post(&conn, &ruben, "Hello", "Hello to all").unwrap();
+      let other = create_post(&conn, &ruben, "Other", "Not this one").unwrap();
+
+      for i in 1..=5 {
+         create_comment(&conn, ruben.id, post.id, &format!("comment {}", i)).unwrap();
+      }
+      create_comment(&conn, ruben.id, other.id, "elsewhere").unwrap();
+
+      let bodies = |page: i64| -> Vec<String> {
+         post_comments(&conn, post.id, page, 2)
+            .unwrap()
+            .comments
+            .into_iter()
+            .map(|(c, _)| c.body)
+            .collect()
+      };
+
+      assert_eq!(bodies(1), vec!["comment 1", "comment 2"]);
+      assert_eq!(bodies(3), vec!["comment 5"]);
+      assert!(bodies(4).is_empty());
+
+      let page = post_comments(&conn, post.id, 2, 2).unwrap();
+      assert_eq!(page.total, 5);
+      assert_eq!((page.page, page.per_page), (2, 2));
+
+      match post_comments(&conn, post.id, std::i64::MAX, 20) {
+         Err(AppError::InvalidInput(_)) => {}
+         other => panic!("expected InvalidInput, got {:?}", other),
+      }
+   }
+
+   #[test]
+   fn user_posts_for_an_unknown_user_is_not_found() {
+      let conn = test_connection();
+
+      match user_posts(&conn, 1, false) {
+         Err(AppError::RecordNotFound) => {}
+         other => panic!("expected RecordNotFound, got {:?}", other),
+      }
+   }
+
+   #[test]
+   fn user_posts_for_a_user_without_posts_is_empty() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+
+      assert!(user_posts(&conn, ruben.id, false).unwrap().is_empty());
+   }
+
+   #[test]
+   fn user_posts_returns_the_users_posts() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let sarah = create_user(&conn, "sarah").unwrap();
+      create_post(&conn, &ruben, "First", "one").unwrap();
+      create_post(&conn, &ruben, "Second", "two").unwrap();
+      create_post(&conn, &sarah, "Other", "three").unwrap();
+
+      let titles: Vec<String> = user_posts(&conn, ruben.id, false)
+         .unwrap()
+         .into_iter()
+         .map(|(post, _)| post.title)
+         .collect();
+
+      assert_eq!(titles, vec!["Second", "First"]);
+   }
+
+   #[test]
+   fn user_summary_counts_posts_and_comments() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let sarah = create_user(&conn, "sarah").unwrap();
+      let first = create_post(&conn, &ruben, "First", "one").unwrap();
+      let second = create_post(&conn, &ruben, "Second", "two").unwrap();
+      create_post(&conn, &sarah, "Other", "three").unwrap();
+      create_comment(&conn, ruben.id, first.id, "me again").unwrap();
+      create_comment(&conn, sarah.id, first.id, "nice").unwrap();
+      create_comment(&conn, sarah.id, second.id, "nicer").unwrap();
+
+      let summary = user_summary(&conn, ruben.id).unwrap();
+      assert_eq!(summary.user, ruben);
+      assert_eq!(summary.post_count, 2);
+      assert_eq!(summary.comment_count, 1);
+
+      let summary = user_summary(&conn, sarah.id).unwrap();
+      assert_eq!(summary.post_count, 1);
+      assert_eq!(summary.comment_count, 2);
+   }
+
+   #[test]
+   fn user_summary_for_an_unknown_user_is_not_found() {
+      let conn = test_connection();
+
+      match user_summary(&conn, 1) {
+         Err(AppError::RecordNotFound) => {}
+         other => panic!("expected RecordNotFound, got {:?}", other),
+      }
+   }
+
+   #[test]
+   fn publish_user_drafts_only_publishes_that_users_drafts() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let sarah = create_user(&conn, "sarah").unwrap();
+      let published = create_post(&conn, &ruben, "Published", "one").unwrap();
+      publish_post(&conn, published.id).unwrap();
+      let first_draft = create_post(&conn, &ruben, "Draft", "two").unwrap();
+      let second_draft = create_post(&conn, &ruben, "Another draft", "three").unwrap();
+      let other_draft = create_post(&conn, &sarah, "Not mine", "four").unwrap();
+
+      assert_eq!(publish_user_drafts(&conn, ruben.id).unwrap(), 2);
+
+      assert!(find_post(&conn, first_draft.id).unwrap().published);
+      assert!(find_post(&conn, second_draft.id).unwrap().published);
+      assert!(!find_post(&conn, other_draft.id).unwrap().published);
+      assert_eq!(publish_user_drafts(&conn, ruben.id).unwrap(), 0);
+
+      match publish_user_drafts(&conn, sarah.id + 1) {
+         Err(AppError::RecordNotFound) => {}
+         other => panic!("expected RecordNotFound, got {:?}", other),
+      }
+   }
+
+   #[test]
+   fn user_comments_with_posts_includes_the_post_title() {
+      let conn = test_connection();
+      let ruben = create_user(&conn, "ruben").unwrap();
+      let sarah = create_user(&conn, "sarah").unwrap();
+      let hello = create_post(&conn, &ruben, "Hello", "one").unwrap();
+      let news = create_post(&conn, &ruben, "News", "two").unwrap();
+      let gone = create_post(&conn, &ruben, "Gone", "three").unwrap();
+      create_comment(&conn, sarah.id, hello.id, "hi").unwrap();
+      create_comment(&conn, sarah.id, news.id, "cool").unwrap();
+      create_comment(&conn, sarah.id, gone.id, "bye").unwrap();
+      create_comment(&conn, ruben.id, news.id, "thanks").unwrap();
+      soft_delete_post(&conn, gone.id).unwrap();
+
+      let comments: Vec<(String, i32, String)> = user_comments_with_posts(&conn, sarah.id)
+         .unwrap()
+         .into_iter()
+         .map(|c| (c.comment.body, c.post_id, c.post_title))
+         .collect();
+
+      assert_eq!(
+         comments,
+         vec![
+            ("cool".to_owned(), news.id, "News".to_owned()),
+            ("hi".to_owned(), hello.id, "Hello".to_owned()),
+         ]
+      );
+   }
+}
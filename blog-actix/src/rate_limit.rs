@@ -0,0 +1,203 @@
+/***
+ *
+    RATE LIMIT MODULE
+
+    Anyone can create users, posts, and comments, so nothing stops a script from creating thousands of them
+
+    RateLimit is a middleware which counts the write requests made from each client IP address
+    and turns any over the limit into a 429 Too Many Requests, via the RateLimited variant of AppError
+
+    Only writes are counted, GET, HEAD, and OPTIONS requests go straight through,
+    so reading the blog is never limited however often it is done
+
+    FIXED WINDOWS
+
+    The counters live in a HashMap from IpAddr to a count and the Instant its window started
+
+    The first write from an address starts a window and each write after that adds one to the count,
+    once the window is older than its length the next write starts a new window with a count of one
+
+    A write that would take the count over the maximum is refused without being counted,
+    and it never reaches the handler so nothing is written to the database
+
+    This is simpler than a sliding window and allows a burst of up to twice the limit across the edge of two windows,
+    which is fine for keeping spam down
+
+    The map is never cleaned up, an address takes a few bytes and a blog sees a small number of them
+
+    SHARING THE COUNTERS
+
+    The closure passed to HttpServer::new is called once per worker,
+    so a RateLimit created inside it would give each worker its own counters
+    and a client could get the limit once per worker
+
+    Instead run creates one RateLimit before the server and clones it into each App,
+    the counters are behind an Arc<Mutex<...>> so every clone sees the same map
+
+    TRANSFORM AND SERVICE
+
+    A middleware in actix web is two types,
+    one implementing Transform which is what wrap takes and is called to wrap each service,
+    and the one it returns implementing Service which sees every request on its way to that service
+
+    The client address comes from peer_addr, the address of the connection itself,
+    rather than connection_info().remote() which trusts the X-Forwarded-For header a client can set to anything
+
+    A request with no peer address, which is only the case for requests built in tests, is counted against 0.0.0.0
+
+ *
+***/
+
+use crate::errors::AppError;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::Method;
+use actix_web::Error;
+use futures::future::{ok, Either, FutureResult};
+use futures::Poll;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Clone)]
+pub struct RateLimit {
+    max_requests: u32,
+    window: Duration,
+    counters: Arc<Mutex<HashMap<IpAddr, (u32, Instant)>>>,
+}
+
+impl RateLimit {
+    pub fn new(max_requests: u32, window: Duration) -> Self {
+        RateLimit {
+            max_requests,
+            window,
+            counters: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn per_minute(max_requests: u32) -> Self {
+        RateLimit::new(max_requests, Duration::from_secs(60))
+    }
+
+    fn check(&self, ip: IpAddr, now: Instant) -> Result<(), AppError> {
+        let mut counters = self.counters.lock().unwrap();
+        let (count, window_start) = counters.entry(ip).or_insert((0, now));
+
+        if now.duration_since(*window_start) >= self.window {
+            *count = 0;
+            *window_start = now;
+        }
+
+        if *count >= self.max_requests {
+            return Err(AppError::RateLimited);
+        }
+
+        *count += 1;
+        Ok(())
+    }
+}
+
+fn is_write(method: &Method) -> bool {
+    !(method == Method::GET || method == Method::HEAD || method == Method::OPTIONS)
+}
+
+impl<S, B> Transform<S> for RateLimit
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RateLimitMiddleware<S>;
+    type Future = FutureResult<Self::Transform, Self::InitError>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RateLimitMiddleware {
+            service,
+            limit: self.clone(),
+        })
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: S,
+    limit: RateLimit,
+}
+
+impl<S, B> Service for RateLimitMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Either<S::Future, FutureResult<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self) -> Poll<(), Self::Error> {
+        self.service.poll_ready()
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        if !is_write(req.method()) {
+            return Either::A(self.service.call(req));
+        }
+
+        let ip = req
+            .peer_addr()
+            .map(|addr| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+        match self.limit.check(ip, Instant::now()) {
+            Ok(()) => Either::A(self.service.call(req)),
+            Err(e) => Either::B(ok(req.error_response(e))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::test::{self, TestRequest};
+    use actix_web::{web, App, HttpResponse};
+
+    #[test]
+    fn writes_over_the_limit_are_refused() {
+        let mut app = test::init_service(
+            App::new()
+                .wrap(RateLimit::per_minute(2))
+                .route("/posts", web::post().to(|| HttpResponse::Created().finish()))
+                .route("/posts", web::get().to(|| HttpResponse::Ok().finish())),
+        );
+
+        for _ in 0..2 {
+            let req = TestRequest::post().uri("/posts").to_request();
+            let resp = test::call_service(&mut app, req);
+            assert_eq!(resp.status(), StatusCode::CREATED);
+        }
+
+        let req = TestRequest::post().uri("/posts").to_request();
+        let resp = test::call_service(&mut app, req);
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let req = TestRequest::get().uri("/posts").to_request();
+        let resp = test::call_service(&mut app, req);
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn counters_are_per_address_and_reset_after_the_window() {
+        let limit = RateLimit::new(1, Duration::from_secs(60));
+        let start = Instant::now();
+        let first: IpAddr = "10.0.0.1".parse().unwrap();
+        let second: IpAddr = "10.0.0.2".parse().unwrap();
+
+        assert!(limit.check(first, start).is_ok());
+        assert!(limit.check(first, start).is_err());
+        assert!(limit.check(second, start).is_ok());
+        assert!(limit.check(first, start + Duration::from_secs(60)).is_ok());
+    }
+}
@@ -44,15 +44,48 @@
     Then we chain the call with the invocation of map_err
     which operates only on the error variant
 
+    CREATED RESPONSES
+
+    convert_created is the variant of convert used by the handlers that create a resource
+
+    Instead of a 200 it responds with 201 Created
+    and sets the Location header to the path of the new resource
+
+    Only the handler knows what that path looks like,
+    so it passes a closure which is given a reference to the created value
+    and returns the path, e.g. |user| format!("/users/{}", user.id)
+
+    The closure is FnOnce because we only need to call it a single time
+
+    LISTS OF IDS
+
+    Some queries take several ids as one comma separated string, e.g. /posts?authors=1,2 or /users?ids=1,2,3
+    parse_ids turns that into a Vec<i32>, allowing spaces around each id
+
+    An empty list or anything that isn't an i32 is an InvalidInput, i.e. a 400,
+    and what names the kind of id in the message, e.g. "x" is not a valid author id
+
+    TESTING THE ROUTES
+
+    The tests give the handlers a pool from test_pool, a single in-memory SQLite connection
+    with the embedded migrations run on it, so every request in a test sees the same database
+    A pool of more than one connection would give each connection its own empty :memory: database
+
+    actix_web::test::init_service builds the App the same way run does and call_service sends it a request,
+    which lets a test look at the status and headers and not only at the JSON
+
  *
 ***/
 
 use crate::errors::AppError;
+use actix_web::http::header;
 use actix_web::HttpResponse;
 
 pub(super) mod users;
 pub(super) mod posts;
 pub(super) mod comments;
+pub(super) mod tags;
+pub(super) mod health;
 
 fn convert<T, E>(res: Result<T,E>) -> Result<HttpResponse, AppError>
 where
@@ -61,4 +94,77 @@ where
 {
    res.map(|d| HttpResponse::Ok().json(d))
       .map_err(Into::into)
-}
\ No newline at end of file
+}
+
+fn convert_created<T, E, F>(res: Result<T,E>, location: F) -> Result<HttpResponse, AppError>
+where
+   T: serde::Serialize,
+   AppError: From<E>,
+   F: FnOnce(&T) -> String,
+{
+   res.map(|d| {
+         HttpResponse::Created()
+            .header(header::LOCATION, location(&d))
+            .json(d)
+      })
+      .map_err(Into::into)
+}
+
+fn parse_ids(list: &str, what: &str) -> Result<Vec<i32>, AppError> {
+   if list.trim().is_empty() {
+      return Err(AppError::InvalidInput(format!("at least one {} id is required", what)));
+   }
+
+   list.split(',')
+      .map(|id| {
+         id.trim()
+            .parse::<i32>()
+            .map_err(|_| AppError::InvalidInput(format!("{:?} is not a valid {} id", id.trim(), what)))
+      })
+      .collect()
+}
+
+#[cfg(test)]
+mod tests {
+   use super::*;
+   use crate::Pool;
+   use actix_web::{test, App};
+   use diesel::prelude::*;
+   use diesel::r2d2::{self, ConnectionManager};
+
+   pub(super) fn test_pool() -> Pool {
+      let manager = ConnectionManager::<SqliteConnection>::new(":memory:");
+      let pool = r2d2::Pool::builder().max_size(1).build(manager).unwrap();
+      crate::embedded_migrations::run(&pool.get().unwrap()).unwrap();
+      pool
+   }
+
+   #[test]
+   fn creating_responds_201_with_the_location() {
+      let mut app = test::init_service(
+         App::new()
+            .data(test_pool())
+            .configure(users::configure)
+            .configure(posts::configure)
+            .configure(comments::configure)
+      );
+
+      let cases = vec![
+         ("/users", serde_json::json!({ "username": "ruben" }), "/users/1"),
+         ("/users/1/posts", serde_json::json!({ "title": "Hello", "body": "Hello to all" }), "/posts/1"),
+         ("/posts/1/comments", serde_json::json!({ "user_id": 1, "body": "Nice" }), "/comments/1"),
+      ];
+
+      for (uri, body, location) in cases {
+         let req = test::TestRequest::post().uri(uri).set_json(&body).to_request();
+         let resp = test::call_service(&mut app, req);
+
+         assert_eq!(resp.status(), actix_web::http::StatusCode::CREATED, "{}", uri);
+         assert_eq!(resp.headers().get(header::LOCATION).unwrap(), location);
+
+         let req = test::TestRequest::get().uri(location).to_request();
+         let resp = test::call_service(&mut app, req);
+         assert!(resp.status().is_success(), "{} can't be fetched", location);
+      }
+   }
+}
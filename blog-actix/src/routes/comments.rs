@@ -10,27 +10,77 @@
         CommentInput {}
 
     The add_comment function creates the comment given whatever post id is passed
+    and responds with 201 Created and a Location header of /comments/{id}, served by get_comment
 
     And can build on the previous functions from our models to create a simple handler for getting all
     comments for a particular post, and fetching all of the comments from a user
 
+    PAGING THROUGH COMMENTS
+
+    The comments for a post come back a page at a time
+    with ?page=2&per_page=10 picking which page and how big it is
+
+    Both are optional, a missing page means the first page
+    and a missing per_page means DEFAULT_PER_PAGE
+
+    Nothing stops a client asking for a huge page
+    so per_page is clamped between 1 and MAX_PER_PAGE and page is at least 1
+
+    The response holds the comments along with the total number of comments on the post
+
+    A USER'S COMMENTS WITH THEIR POSTS
+
+    The comments of a user come with a small summary of each post nested in them,
+    with ?with_posts=true each entry is instead {"comment":{...},"post_id":1,"post_title":"..."}
+    which is easier to show as a list of comments with the title of the post next to each one
+
+    Comments on deleted posts are left out of this form, see user_comments_with_posts in the models
+    The two shapes are variants of the untagged CommentList enum, the same way PostList works for posts
+
     TEST COMMENT API
 
     see users comments: curl -s -H 'Content-Type: application/json' http://localhost:8998/users/2/comments
+    see users comments with post titles: curl -s -H 'Content-Type: application/json' 'http://localhost:8998/users/2/comments?with_posts=true'
     see post coments: curl -s -H 'Content-Type: application/json' http://localhost:8998/posts/1/comments
+    see a page of post comments: curl -s -H 'Content-Type: application/json' 'http://localhost:8998/posts/1/comments?page=2&per_page=5'
+
+    see a single comment: curl -s -H 'Content-Type: application/json' http://localhost:8998/comments/1
 
     comment on a post: curl -s -H 'Content-Type: application/json' -X POST http://localhost:8998/posts/1/comments -d 
         '{"user_id":2, "body":"Hi Ruben, this is your friend Sarah"}'
+        (add -i to see the 201 Created status and the location: /comments/1 header)
 
 ***/
 
 use crate::errors::AppError;
-use crate::routes::convert;
+use crate::routes::{convert, convert_created};
 use crate::{models, Pool};
 use actix_web::{web, HttpResponse};
 use diesel::prelude::*;
 use futures::Future;
 
+const DEFAULT_PER_PAGE: i64 = 20;
+const MAX_PER_PAGE: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+struct PageQuery {
+    page: Option<i64>,
+    per_page: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserCommentsQuery {
+    #[serde(default)]
+    with_posts: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum CommentList {
+    WithPostSummary(Vec<(models::Comment, models::PostWithComment)>),
+    WithPosts(Vec<models::CommentWithPost>),
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct CommentInput {
     user_id: i32,
@@ -50,35 +100,61 @@ fn add_comment(
 
         models::create_comment(conn, user_id, post_id.into_inner(), body.as_str())
     })
+    .then(|res| convert_created(res, |comment: &models::Comment| format!("/comments/{}", comment.id)))
+}
+
+fn get_comment(
+    comment_id: web::Path<i32>,
+    pool: web::Data<Pool>
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn: &SqliteConnection = &pool.get().unwrap();
+
+        models::find_comment(conn, comment_id.into_inner())
+    })
     .then(convert)
 }
 
 fn post_comments(
     post_id: web::Path<i32>,
+    query: web::Query<PageQuery>,
     pool: web::Data<Pool>
 ) -> impl Future<Item = HttpResponse, Error = AppError> {
     web::block(move || {
         let conn: &SqliteConnection = &pool.get().unwrap();
-
-        models::post_comments(conn, post_id.into_inner())
+        let page = query.page.unwrap_or(1).max(1);
+        let per_page = query
+            .per_page
+            .unwrap_or(DEFAULT_PER_PAGE)
+            .max(1)
+            .min(MAX_PER_PAGE);
+
+        models::post_comments(conn, post_id.into_inner(), page, per_page)
     })
     .then(convert)
 }
 
 fn user_comments(
     user_id: web::Path<i32>,
+    query: web::Query<UserCommentsQuery>,
     pool: web::Data<Pool>
 ) -> impl Future<Item = HttpResponse, Error = AppError> {
     web::block(move || {
         let conn: &SqliteConnection = &pool.get().unwrap();
+        let user_id = user_id.into_inner();
 
-        models::user_comments(conn, user_id.into_inner())
+        if query.with_posts {
+            models::user_comments_with_posts(conn, user_id).map(CommentList::WithPosts)
+        } else {
+            models::user_comments(conn, user_id).map(CommentList::WithPostSummary)
+        }
     })
     .then(convert)
 }
 
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg.service(web::resource("/users/{id}/comments").route(web::get().to_async(user_comments)))
+        .service(web::resource("/comments/{id}").route(web::get().to_async(get_comment)))
         .service(
             web::resource("/posts/{id}/comments")
                 .route(web::post().to_async(add_comment))
@@ -0,0 +1,110 @@
+/*** 
+ *
+    ROUTES FOR HEALTH CHECKS
+
+    READINESS
+
+    A health check that never touches the database would happily report that we are fine
+    while every other request fails because the database is gone
+
+    The ready handler instead takes a connection out of the pool and runs a trivial query on it
+    so a 200 means we really are able to serve requests
+
+    Just like every other handler the database work happens inside web::block
+    as Diesel is synchronous
+
+    Both ways this can fail, the pool not handing out a connection and the query failing,
+    are mapped to AppError::ServiceUnavailable which responds with a 503 and the usual JSON error body
+
+    This is also why we don't unwrap the result of pool.get() like the other handlers do,
+    a readiness check that panics instead of answering isn't much use
+
+    The test for the 503 builds its pool with build_unchecked, which doesn't try to connect up front,
+    pointed at a database in a directory that doesn't exist, so pool.get() gives up after the short timeout
+
+    CONFIGURING THE ROUTES
+
+    Define 1 route:
+        - GET /ready which calls ready
+
+    EXAMPLES TO TEST WITH CURL
+
+    curl -i http://localhost:8998/ready
+        (HTTP/1.1 200 OK with {"status":"ready"})
+        (HTTP/1.1 503 Service Unavailable with {"err":"Service unavailable: ..."} if the database is unreachable)
+
+ *
+***/
+
+use crate::errors::AppError;
+use crate::routes::convert;
+use crate::{models, Pool};
+use actix_web::{web, HttpResponse};
+use futures::Future;
+
+#[derive(Debug, Serialize)]
+struct Readiness {
+    status: &'static str,
+}
+
+fn ready(pool: web::Data<Pool>) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn = &pool
+            .get()
+            .map_err(|e| AppError::ServiceUnavailable(e.to_string()))?;
+
+        models::check_database(conn)
+            .map_err(|e| AppError::ServiceUnavailable(e.to_string()))?;
+
+        Ok(Readiness { status: "ready" })
+    })
+    .then(convert)
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/ready").route(web::get().to_async(ready)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::tests::test_pool;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+    use diesel::prelude::*;
+    use diesel::r2d2::{self, ConnectionManager};
+    use std::time::Duration;
+
+    fn ready_status(pool: Pool) -> (StatusCode, serde_json::Value) {
+        let mut app = test::init_service(App::new().data(pool).configure(configure));
+
+        let req = test::TestRequest::get().uri("/ready").to_request();
+        let resp = test::call_service(&mut app, req);
+        let status = resp.status();
+        let body = serde_json::from_slice(&test::read_body(resp)).unwrap();
+
+        (status, body)
+    }
+
+    #[test]
+    fn ready_when_the_database_answers() {
+        let (status, body) = ready_status(test_pool());
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, serde_json::json!({ "status": "ready" }));
+    }
+
+    #[test]
+    fn unavailable_when_the_pool_has_no_connection() {
+        let manager = ConnectionManager::<SqliteConnection>::new("/does-not-exist/blog.db");
+        let pool = r2d2::Pool::builder()
+            .max_size(1)
+            .connection_timeout(Duration::from_millis(200))
+            .build_unchecked(manager);
+
+        let (status, body) = ready_status(pool);
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(body["err"].as_str().unwrap().starts_with("Service unavailable: "));
+    }
+}
@@ -18,14 +18,150 @@
 
     This way enables handling all of the different errors without having a mess of conditionals
 
+    Creating a post responds with 201 Created and a Location header of /posts/{id}
+    which is served by get_post
+
+    EXPANDING THE AUTHOR
+
+    With ?expand=author the created post comes back with its author inlined, {"post":{...},"author":{...}},
+    the same shape as a PostWithAuthor in the feeds, so the client doesn't have to fetch the user separately
+    The handler already looked the user up to create the post so this costs no extra query
+
+    Without expand the response is the bare post as before, and any other value of expand is a 400
+    Either way the two shapes are variants of the untagged CreatedPost enum so the closure has one return type
+
+    expand is parsed into an Expand before web::block is called, so a bad value is turned away
+    without the post ever being created, the handler then hands back a future that has already failed,
+    future::err wrapped in Either::A, in place of the blocking call in Either::B
+
+    TAGGING A POST
+
+    The input can also have a list of tags, e.g. {"title":"...", "body":"...", "tags":["rust","web"]},
+    which is optional thanks to the serde default attribute, so a post without tags is sent the same as before
+    The post and its tags are created together by models::create_post_with_tags
+
+    Posts created in bulk can be tagged the same way, each post with its own list
+
+    FETCHING A POST WITH ITS COMMENTS
+
+    GET /posts/{id}/full responds with the post and its comments nested in one object
+    and a 404 if there is no such post
+
+    CREATING POSTS IN BULK
+
+    The bulk route accepts a JSON array of PostInput instead of a single one
+    and hands all of them to models::create_posts which inserts them in one transaction
+
+    An empty array is rejected with a 400 as there is nothing sensible to create
+
+    Like a single post it responds with 201 Created, the body being the array of created posts,
+    but as there is no one URL for several posts the Location header is /users/{id}/posts,
+    the list the new posts now belong to
+
+    If any post in the batch fails to insert, none of the posts are kept
+    e.g. sending a body that is not a string in the middle of the array never reaches the database
+    and a failure inside the transaction rolls back the posts inserted before it
+
     PUBLISHING A POST
 
     Simply need a post_id in the url path for processing a post publish
 
+    POST /users/{id}/posts/publish-all publishes every draft of a user at once
+    and responds with how many were published, e.g. {"published":3}, or a 404 for an unknown user
+
+    UPDATING A POST
+
+    A PUT to /posts/{id} replaces the title and body of a post
+    The body also has to include the version of the post the change was made against,
+    which is the version field of the post as it was fetched
+
+    If someone else updated the post in the meantime the version won't match
+    and the response is a 409 Conflict rather than quietly overwriting their change,
+    the client should fetch the post again and redo its edit on top of the latest version
+
+    PATCHING A POST
+
+    A PATCH to /posts/{id} changes only the fields in the body, e.g. {"title":"New title"} keeps the body as it was
+    The body is deserialized straight into models::PostPatch, a missing field is None and left alone
+    A body with neither field is a 400 as there is nothing to change, and an unknown post is a 404
+
+    No version is needed, but the version still goes up so anyone about to PUT an older copy gets a 409
+
+    REASSIGNING A POST
+
+    POST /posts/{id}/reassign with {"new_user_id":2} moves the post to another author
+    and responds with the updated post
+
+    An unknown post or an unknown new author is a 404 and the post is left as it was
+
+    SEARCHING POSTS
+
+    GET /posts/search?q=term returns the published posts mentioning the term along with their authors,
+    posts with the term in the title come before those that only have it in the body
+
+    An empty search term is rejected with a 400
+
+    The search resource is registered before /posts/{id},
+    otherwise search would be taken as the id of a post and fail to parse as an i32
+
+    THE MOST RECENT POSTS
+
+    GET /posts/recent?limit=10 returns the newest published posts of all users with their authors, for a homepage feed
+    Each entry is {"post":...,"author":...}
+
+    A missing limit means DEFAULT_RECENT_LIMIT and the limit is clamped between 1 and MAX_RECENT_LIMIT,
+    so asking for a million posts gives MAX_RECENT_LIMIT of them rather than loading the whole table
+
+    Like search, the recent resource is registered before /posts/{id}
+
+    DELETING A POST
+
+    A DELETE to /posts/{id} soft deletes the post, the row stays in the table with deleted_at set
+    and the response is the post as it is now
+
+    A FEED OF SEVERAL AUTHORS
+
+    GET /posts?authors=1,2,3 returns the published posts written by any of the users with those ids,
+    newest first and each with its author, for a feed of the people someone follows
+
+    The ids come in as one comma separated string in ListQuery and parse_ids turns it into a Vec<i32>,
+    an empty list or anything that isn't a number is a 400 rather than an empty feed
+    Spaces around the ids are allowed, so authors=1, 2 works too
+
+    include_deleted works the same as for the other lists, while with_counts is ignored when authors is given
+
+    PAGING THROUGH POSTS
+
+    GET /posts?limit=20 gives the newest published posts with their authors along with a next_cursor,
+    {"posts":[...],"next_cursor":42}, and GET /posts?after=42&limit=20 gives the page after that one
+    next_cursor is null on the last page
+
+    The cursor is the id of the last post on the previous page, see models::posts_after,
+    so posts created while a client is paging don't make it see a post twice or skip one
+    like limit and offset would
+
+    Giving either after or limit asks for a page, a missing limit means DEFAULT_PAGE_LIMIT
+    and it is clamped between 1 and MAX_PAGE_LIMIT, while include_deleted and with_counts are ignored
+    authors still takes precedence over both
+
     FETCHING POSTS
 
     Can fetch posts either given a user_id or just fetch them all
 
+    The posts of a user that doesn't exist are a 404, a user without any posts gets an empty list
+
+    Deleted posts are left out of both lists unless ?include_deleted=true is in the query string,
+    which the web::Query extractor deserializes into ListQuery
+    The serde default attribute makes a missing parameter false
+
+    Fetching a single post by id still returns it after it has been deleted
+
+    With ?with_counts=true the list of all posts has the number of comments on each post
+    instead of the comments themselves, which is all a list view usually needs
+
+    The two kinds of list are wrapped in the PostList enum so the closure passed to web::block
+    has a single return type, and the serde untagged attribute serializes each variant as just its contents
+
     ROUTE CONFIGURATION
 
     The path /users/{id}/posts accepts both a POST and a GET request
@@ -37,46 +173,244 @@
     create a post: curl -s -H 'Content-Type: application/json' -X POST http://localhost:8998/users/1/posts -d 
         '{"title":"Ruben says hello", "body":"Hello to all"}'
 
+    create a tagged post: curl -s -H 'Content-Type: application/json' -X POST http://localhost:8998/users/1/posts -d
+        '{"title":"Diesel tips", "body":"Joins", "tags":["rust", "diesel"]}'
+
+    create posts in bulk: curl -s -H 'Content-Type: application/json' -X POST http://localhost:8998/users/1/posts/bulk -d
+        '[{"title":"First", "body":"One"}, {"title":"Second", "body":"Two"}]'
+
+    view a post: curl -s -H 'Content-Type: application/json' http://localhost:8998/posts/1
+
+    view a post with its comments: curl -s -H 'Content-Type: application/json' http://localhost:8998/posts/1/full
+
+    create a post with its author: curl -s -H 'Content-Type: application/json' -X POST 'http://localhost:8998/users/1/posts?expand=author' -d
+        '{"title":"Ruben says hello", "body":"Hello to all"}'
+
+    check the created response: curl -i -H 'Content-Type: application/json' -X POST http://localhost:8998/users/1/posts -d
+        '{"title":"Ruben says hello again", "body":"Hello"}'
+        (HTTP/1.1 201 Created with location: /posts/2)
+
     publish a post: curl -s -H 'Content-Type: application/json' -X POST http://localhost:8998/posts/1/publish
 
+    publish all drafts of a user: curl -s -H 'Content-Type: application/json' -X POST http://localhost:8998/users/1/posts/publish-all
+
+    update a post: curl -s -H 'Content-Type: application/json' -X PUT http://localhost:8998/posts/1 -d
+        '{"title":"Ruben says hi", "body":"Hi to all", "version":1}'
+        (sending the same request again gives a 409 as the post is now at version 2)
+    patch a post: curl -s -H 'Content-Type: application/json' -X PATCH http://localhost:8998/posts/1 -d '{"title":"Ruben says hey"}'
+
+    reassign a post: curl -s -H 'Content-Type: application/json' -X POST http://localhost:8998/posts/1/reassign -d '{"new_user_id":2}'
+
+    the most recent posts: curl -s -H 'Content-Type: application/json' 'http://localhost:8998/posts/recent?limit=10'
+
+    search posts: curl -s -H 'Content-Type: application/json' 'http://localhost:8998/posts/search?q=hello'
+
+    delete a post: curl -s -H 'Content-Type: application/json' -X DELETE http://localhost:8998/posts/1
+
     list all posts: curl -s -H 'Content-Type: application/json' http://localhost:8998/posts
 
+    list all posts with comment counts: curl -s -H 'Content-Type: application/json' http://localhost:8998/posts?with_counts=true
+
+    page through the posts: curl -s -H 'Content-Type: application/json' 'http://localhost:8998/posts?after=42&limit=20'
+
+    list the posts of some authors: curl -s -H 'Content-Type: application/json' 'http://localhost:8998/posts?authors=1,2'
+    list all posts including deleted ones: curl -s -H 'Content-Type: application/json' http://localhost:8998/posts?include_deleted=true
+
     view posts: curl -s -H 'Content-Type: application/json' http://localhost:8998/users/1/posts
 
 
 ***/
 
 use crate::errors::AppError;
-use crate::routes::convert;
+use crate::routes::{convert, convert_created, parse_ids};
 use crate::{models, Pool};
 use actix_web::{web, HttpResponse};
 use diesel::prelude::*;
+use futures::future::{self, Either};
 use futures::Future;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct PostInput {
     title: String,
     body: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExpandQuery {
+    expand: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expand {
+    Nothing,
+    Author,
+}
+
+impl Expand {
+    fn parse(expand: Option<&str>) -> Result<Self, AppError> {
+        match expand {
+            None => Ok(Expand::Nothing),
+            Some("author") => Ok(Expand::Author),
+            Some(other) => Err(AppError::InvalidInput(format!("can't expand {:?}, only author", other))),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum CreatedPost {
+    Bare(models::Post),
+    WithAuthor(models::PostWithAuthor),
+}
+
+impl CreatedPost {
+    fn new(post: models::Post, author: models::User, expand: Expand) -> Self {
+        match expand {
+            Expand::Nothing => CreatedPost::Bare(post),
+            Expand::Author => CreatedPost::WithAuthor(models::PostWithAuthor { post, author }),
+        }
+    }
+
+    fn id(&self) -> i32 {
+        match self {
+            CreatedPost::Bare(post) => post.id,
+            CreatedPost::WithAuthor(p) => p.post.id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdatePostInput {
+    title: String,
+    body: String,
+    version: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReassignInput {
+    new_user_id: i32,
+}
+
+#[derive(Debug, Serialize)]
+struct PublishedCount {
+    published: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchQuery {
+    q: String,
+}
+
+const DEFAULT_RECENT_LIMIT: i64 = 10;
+const MAX_RECENT_LIMIT: i64 = 50;
+
+#[derive(Debug, Deserialize)]
+struct RecentQuery {
+    limit: Option<i64>,
+}
+
+const DEFAULT_PAGE_LIMIT: i64 = 20;
+const MAX_PAGE_LIMIT: i64 = 100;
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    #[serde(default)]
+    include_deleted: bool,
+    #[serde(default)]
+    with_counts: bool,
+    authors: Option<String>,
+    after: Option<i32>,
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum PostList {
+    WithComments(Vec<((models::Post, models::User), Vec<(models::Comment, models::User)>)>),
+    WithCounts(Vec<models::PostWithCount>),
+    ByAuthors(Vec<(models::Post, models::User)>),
+    Page(models::PostPage),
 }
 
 fn add_post(
     user_id: web::Path<i32>,
     post: web::Json<PostInput>,
+    query: web::Query<ExpandQuery>,
+    pool: web::Data<Pool>
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    let expand = match Expand::parse(query.expand.as_deref()) {
+        Ok(expand) => expand,
+        Err(e) => return Either::A(future::err(e)),
+    };
+
+    Either::B(
+        web::block(move || {
+            let conn: &SqliteConnection = &pool.get().unwrap();
+            let key = models::UserKey::ID(user_id.into_inner());
+
+            models::find_user(conn, key).and_then(|user| {
+                let post = post.into_inner();
+                let title = post.title;
+                let body = post.body;
+
+                let created = models::create_post_with_tags(conn, &user, title.as_str(), body.as_str(), &post.tags)?;
+                Ok(CreatedPost::new(created, user, expand))
+            })
+        })
+        .then(|res| convert_created(res, |post: &CreatedPost| format!("/posts/{}", post.id())))
+    )
+}
+
+fn get_post(
+    post_id: web::Path<i32>,
+    pool: web::Data<Pool>
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn: &SqliteConnection = &pool.get().unwrap();
+
+        models::find_post(conn, post_id.into_inner())
+    })
+    .then(convert)
+}
+
+fn get_full_post(
+    post_id: web::Path<i32>,
     pool: web::Data<Pool>
 ) -> impl Future<Item = HttpResponse, Error = AppError> {
     web::block(move || {
         let conn: &SqliteConnection = &pool.get().unwrap();
-        let key = models::UserKey::ID(user_id.into_inner());
+
+        models::post_with_comments(conn, post_id.into_inner())
+    })
+    .then(convert)
+}
+
+fn bulk_add_posts(
+    user_id: web::Path<i32>,
+    posts: web::Json<Vec<PostInput>>,
+    pool: web::Data<Pool>
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    let user_id = user_id.into_inner();
+
+    web::block(move || {
+        let conn: &SqliteConnection = &pool.get().unwrap();
+        let posts = posts.into_inner();
+
+        if posts.is_empty() {
+            return Err(AppError::InvalidInput("at least one post is required".to_owned()));
+        }
+
+        let key = models::UserKey::ID(user_id);
 
         models::find_user(conn, key).and_then(|user| {
-            let post = post.into_inner();
-            let title = post.title;
-            let body = post.body;
+            let posts = posts.into_iter().map(|p| (p.title, p.body, p.tags)).collect();
 
-            models::create_post(conn, &user, title.as_str(), body.as_str())
+            models::create_posts(conn, &user, posts)
         })
     })
-    .then(convert)
+    .then(move |res| convert_created(res, |_: &Vec<models::Post>| format!("/users/{}/posts", user_id)))
 }
 
 fn publish_post(
@@ -91,23 +425,147 @@ fn publish_post(
     .then(convert)
 }
 
+fn publish_user_drafts(
+    user_id: web::Path<i32>,
+    pool: web::Data<Pool>
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn: &SqliteConnection = &pool.get().unwrap();
+
+        models::publish_user_drafts(conn, user_id.into_inner())
+            .map(|published| PublishedCount { published })
+    })
+    .then(convert)
+}
+
+fn update_post(
+    post_id: web::Path<i32>,
+    post: web::Json<UpdatePostInput>,
+    pool: web::Data<Pool>
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn: &SqliteConnection = &pool.get().unwrap();
+        let post = post.into_inner();
+
+        models::update_post(conn, post_id.into_inner(), post.version, &post.title, &post.body)
+    })
+    .then(convert)
+}
+
+fn patch_post(
+    post_id: web::Path<i32>,
+    patch: web::Json<models::PostPatch>,
+    pool: web::Data<Pool>
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn: &SqliteConnection = &pool.get().unwrap();
+        let patch = patch.into_inner();
+
+        if patch.title.is_none() && patch.body.is_none() {
+            return Err(AppError::InvalidInput("a title or body to change is required".to_owned()));
+        }
+
+        models::patch_post(conn, post_id.into_inner(), &patch)
+    })
+    .then(convert)
+}
+
+fn reassign_post(
+    post_id: web::Path<i32>,
+    input: web::Json<ReassignInput>,
+    pool: web::Data<Pool>
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn: &SqliteConnection = &pool.get().unwrap();
+
+        models::reassign_post(conn, post_id.into_inner(), input.new_user_id)
+    })
+    .then(convert)
+}
+
+fn search_posts(
+    query: web::Query<SearchQuery>,
+    pool: web::Data<Pool>
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn: &SqliteConnection = &pool.get().unwrap();
+        let term = query.into_inner().q;
+
+        if term.trim().is_empty() {
+            return Err(AppError::InvalidInput("a search term is required".to_owned()));
+        }
+
+        models::search_posts(conn, term.trim())
+    })
+    .then(convert)
+}
+
+fn recent_posts(
+    query: web::Query<RecentQuery>,
+    pool: web::Data<Pool>
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn: &SqliteConnection = &pool.get().unwrap();
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_RECENT_LIMIT)
+            .max(1)
+            .min(MAX_RECENT_LIMIT);
+
+        models::recent_posts(conn, limit)
+    })
+    .then(convert)
+}
+
+fn delete_post(
+    post_id: web::Path<i32>,
+    pool: web::Data<Pool>
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn: &SqliteConnection = &pool.get().unwrap();
+
+        models::soft_delete_post(conn, post_id.into_inner())
+    })
+    .then(convert)
+}
+
 fn user_posts(
     user_id: web::Path<i32>,
+    query: web::Query<ListQuery>,
     pool: web::Data<Pool>
 ) -> impl Future<Item = HttpResponse, Error = AppError> {
     web::block(move || {
         let conn: &SqliteConnection = &pool.get().unwrap();
 
-        models::user_posts(conn, user_id.into_inner())
+        models::user_posts(conn, user_id.into_inner(), query.include_deleted)
     })
     .then(convert)
 }
 
-fn all_posts(pool: web::Data<Pool>) -> impl Future<Item = HttpResponse, Error = AppError> {
+fn all_posts(
+    query: web::Query<ListQuery>,
+    pool: web::Data<Pool>
+) -> impl Future<Item = HttpResponse, Error = AppError> {
     web::block(move || {
         let conn: &SqliteConnection = &pool.get().unwrap();
 
-        models::all_posts(conn)
+        if let Some(authors) = &query.authors {
+            let ids = parse_ids(authors, "author")?;
+
+            models::posts_by_authors(conn, &ids, query.include_deleted).map(PostList::ByAuthors)
+        } else if query.after.is_some() || query.limit.is_some() {
+            let limit = query
+                .limit
+                .unwrap_or(DEFAULT_PAGE_LIMIT)
+                .max(1)
+                .min(MAX_PAGE_LIMIT);
+
+            models::posts_after(conn, query.after, limit).map(PostList::Page)
+        } else if query.with_counts {
+            models::posts_with_counts(conn, query.include_deleted).map(PostList::WithCounts)
+        } else {
+            models::all_posts(conn, query.include_deleted).map(PostList::WithComments)
+        }
     })
     .then(convert)
 }
@@ -118,6 +576,77 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             .route(web::post().to_async(add_post))
             .route(web::get().to_async(user_posts))
     )
+    .service(web::resource("/users/{id}/posts/bulk").route(web::post().to_async(bulk_add_posts)))
+    .service(web::resource("/users/{id}/posts/publish-all").route(web::post().to_async(publish_user_drafts)))
     .service(web::resource("/posts").route(web::get().to_async(all_posts)))
-    .service(web::resource("/posts/{id}/publish").route(web::post().to_async(publish_post)));
-}
\ No newline at end of file
+    .service(web::resource("/posts/search").route(web::get().to_async(search_posts)))
+    .service(web::resource("/posts/recent").route(web::get().to_async(recent_posts)))
+    .service(
+        web::resource("/posts/{id}")
+            .route(web::get().to_async(get_post))
+            .route(web::put().to_async(update_post))
+            .route(web::patch().to_async(patch_post))
+            .route(web::delete().to_async(delete_post))
+    )
+    .service(web::resource("/posts/{id}/full").route(web::get().to_async(get_full_post)))
+    .service(web::resource("/posts/{id}/publish").route(web::post().to_async(publish_post)))
+    .service(web::resource("/posts/{id}/reassign").route(web::post().to_async(reassign_post)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post() -> models::Post {
+        models::Post {
+            id: 7,
+            user_id: 1,
+            title: "Hello".to_owned(),
+            body: "Hello to all".to_owned(),
+            published: false,
+            deleted_at: None,
+            version: 1,
+            created_at: "2020-12-01 10:00:00".to_owned(),
+        }
+    }
+
+    fn ruben() -> models::User {
+        models::User {
+            id: 1,
+            username: "ruben".to_owned(),
+        }
+    }
+
+    #[test]
+    fn created_post_is_bare_without_expand() {
+        let created = CreatedPost::new(post(), ruben(), Expand::Nothing);
+        let json = serde_json::to_value(&created).unwrap();
+
+        assert_eq!(created.id(), 7);
+        assert_eq!(json["id"], 7);
+        assert_eq!(json["title"], "Hello");
+        assert!(json.get("author").is_none());
+    }
+
+    #[test]
+    fn created_post_inlines_the_author_when_expanded() {
+        let created = CreatedPost::new(post(), ruben(), Expand::Author);
+        let json = serde_json::to_value(&created).unwrap();
+
+        assert_eq!(created.id(), 7);
+        assert_eq!(json["post"]["id"], 7);
+        assert_eq!(json["author"], serde_json::json!({ "id": 1, "username": "ruben" }));
+
+    }
+
+    #[test]
+    fn expand_only_accepts_author() {
+        assert_eq!(Expand::parse(None).unwrap(), Expand::Nothing);
+        assert_eq!(Expand::parse(Some("author")).unwrap(), Expand::Author);
+
+        match Expand::parse(Some("comments")) {
+            Err(AppError::InvalidInput(_)) => {}
+            other => panic!("expected InvalidInput, got {:?}", other),
+        }
+    }
+}
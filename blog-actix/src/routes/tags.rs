@@ -0,0 +1,41 @@
+/*** 
+ *
+    ROUTES FOR TAGS
+
+    Posts are tagged when they are created, see the posts routes,
+    so the only thing left to do with tags on their own is list them
+
+    LISTING THE TAGS
+
+    GET /tags returns every tag used by a published post along with how many published posts use it,
+    the most popular tag first, e.g. [{"tag":{"id":1,"name":"rust"},"post_count":2}, ...]
+
+    Drafts and deleted posts don't count, see tag_counts in the models
+
+    TEST TAG API
+
+    list the tags: curl -s -H 'Content-Type: application/json' http://localhost:8998/tags
+
+***/
+
+use crate::errors::AppError;
+use crate::routes::convert;
+use crate::{models, Pool};
+use actix_web::{web, HttpResponse};
+use diesel::prelude::*;
+use futures::Future;
+
+fn all_tags(
+    pool: web::Data<Pool>
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn: &SqliteConnection = &pool.get().unwrap();
+
+        models::tag_counts(conn)
+    })
+    .then(convert)
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource("/tags").route(web::get().to_async(all_tags)));
+}
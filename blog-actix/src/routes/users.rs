@@ -50,6 +50,9 @@
     Finally, we can use our convert function to turn the result of the call to
     models::create_user into the response we desire
 
+    The response is a 201 Created with a Location header of /users/{id}
+    which we build from the id of the user we just created with convert_created
+
     FIND A USER
 
     find_user implements the lookup by username
@@ -61,16 +64,69 @@
     This is basically the same as find_user except we expect an i32 in the path instead of a string
     and we create the other variant of the UserKey enum
 
+    A USER'S ACTIVITY
+
+    user_activity returns the posts and comments written by the user with the given id in a single list, newest first,
+    with a type field on each entry saying which one it is
+
+    A USER'S SUMMARY
+
+    user_summary returns the user along with how many posts and comments they have written,
+    {"user":{...},"post_count":2,"comment_count":5}
+
+    The lookup and both counts happen in the one blocking call, and an unknown id is a 404
+
+    SEARCHING USERS
+
+    GET /users/search?prefix=ru&limit=10 returns the users whose username starts with the prefix
+    in alphabetical order, for autocompleting a mention
+
+    A missing limit means DEFAULT_SEARCH_LIMIT and the limit is clamped between 1 and MAX_SEARCH_LIMIT
+    An empty prefix is rejected with a 400 as it would match everyone
+
+    The search resource is registered before /users/{id}, the same way /posts/search is
+
+    SEVERAL USERS BY ID
+
+    GET /users?ids=1,2,3 returns the users with those ids in one go, ordered by id,
+    so a client with a page of posts can resolve all the authors with a single request
+
+    The ids are parsed by parse_ids like the authors of /posts, so an empty or malformed list is a 400
+    Ids that don't belong to anyone are left out of the list rather than failing the whole request
+
+    TRANSFERRING POSTS
+
+    POST /users/{id}/transfer-posts with {"to_user_id": 2} hands every post of user {id} over to user 2,
+    for when two accounts are merged, and responds with how many posts moved, {"transferred":3}
+
+    models::transfer_posts does it in one transaction, so if either user doesn't exist it is a 404
+    and no post has changed hands
+
+    SIGNING UP
+
+    POST /signup with {"username": "Ruben", "post": {"title": "Hello", "body": "My first post"}}
+    creates the user and their first post together and responds with both, {"user":{...},"post":{...}}
+    as a 201 Created with the Location of the new user
+
+    models::create_user_with_post does both inserts in one transaction,
+    so a username that is already taken is a 400 and no post is left behind without its user
+
     CONFIGURING THE ROUTES
 
     The signature of the configure function is specified by Actix web
 
     The only parameter is a mutable reference to a service configuration object
 
-    Define 3 routes:
+    Define 9 routes:
         - POST /users which calls create_user
+        - POST /signup which calls signup
+        - GET /users which calls users_by_ids
         - GET /users/find/{name} which calls find_user
+        - GET /users/search which calls search_users
         - GET /users/{id} which calls get_user
+        - GET /users/{id}/activity which calls user_activity
+        - GET /users/{id}/summary which calls user_summary
+        - POST /users/{id}/transfer-posts which calls transfer_posts
 
     We use *to_async* to specify the handlers here 
     because our handlers return futures
@@ -79,14 +135,22 @@
     EXAMPLES TO TEST WITH CURL
 
     curl -H 'Content-Type: application/json' -X POST http://localhost:8998/users -d '{"username":"Ruben"}'
+    curl -i -H 'Content-Type: application/json' -X POST http://localhost:8998/users -d '{"username":"Sarah"}'
+        (HTTP/1.1 201 Created with location: /users/2)
+    curl -i -H 'Content-Type: application/json' -X POST http://localhost:8998/signup -d '{"username":"Tom","post":{"title":"Hello","body":"My first post"}}'
     curl -H 'Content-Type: application/json' http://localhost:8998/users/find/Ruben
+    curl -H 'Content-Type: application/json' 'http://localhost:8998/users?ids=1,2,3'
+    curl -H 'Content-Type: application/json' 'http://localhost:8998/users/search?prefix=ru&limit=10'
     curl -H 'Content-Type: application/json' http://localhost:8998/users/1
+    curl -H 'Content-Type: application/json' http://localhost:8998/users/1/activity
+    curl -H 'Content-Type: application/json' http://localhost:8998/users/1/summary
+    curl -H 'Content-Type: application/json' -X POST http://localhost:8998/users/1/transfer-posts -d '{"to_user_id":2}'
 
  *
 ***/
 
 use crate::errors::AppError;
-use crate::routes::convert;
+use crate::routes::{convert, convert_created, parse_ids};
 use crate::{models, Pool};
 use actix_web::{web, HttpResponse};
 use futures::Future;
@@ -96,6 +160,48 @@ struct UserInput {
     username: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct SignupInput {
+    username: String,
+    post: FirstPost,
+}
+
+#[derive(Debug, Deserialize)]
+struct FirstPost {
+    title: String,
+    body: String,
+}
+
+#[derive(Debug, Serialize)]
+struct SignedUp {
+    user: models::User,
+    post: models::Post,
+}
+
+const DEFAULT_SEARCH_LIMIT: i64 = 10;
+const MAX_SEARCH_LIMIT: i64 = 50;
+
+#[derive(Debug, Deserialize)]
+struct TransferInput {
+    to_user_id: i32,
+}
+
+#[derive(Debug, Serialize)]
+struct TransferredCount {
+    transferred: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdsQuery {
+    ids: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchQuery {
+    prefix: String,
+    limit: Option<i64>,
+}
+
 fn create_user(
     item: web::Json<UserInput>,
     pool: web::Data<Pool>,
@@ -106,6 +212,33 @@ fn create_user(
 
         models::create_user(conn, username.as_str())
     })
+    .then(|res| convert_created(res, |user: &models::User| format!("/users/{}", user.id)))
+}
+
+fn signup(
+    item: web::Json<SignupInput>,
+    pool: web::Data<Pool>,
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn = &pool.get().unwrap();
+        let SignupInput { username, post } = item.into_inner();
+
+        models::create_user_with_post(conn, &username, &post.title, &post.body)
+            .map(|(user, post)| SignedUp { user, post })
+    })
+    .then(|res| convert_created(res, |signed_up: &SignedUp| format!("/users/{}", signed_up.user.id)))
+}
+
+fn users_by_ids(
+    query: web::Query<IdsQuery>,
+    pool: web::Data<Pool>,
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn = &pool.get().unwrap();
+        let ids = parse_ids(&query.ids, "user")?;
+
+        models::find_users_by_ids(conn, &ids)
+    })
     .then(convert)
 }
 
@@ -123,6 +256,28 @@ fn find_user(
     .then(convert)
 }
 
+fn search_users(
+    query: web::Query<SearchQuery>,
+    pool: web::Data<Pool>,
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn = &pool.get().unwrap();
+
+        if query.prefix.is_empty() {
+            return Err(AppError::InvalidInput("a prefix is required".to_owned()));
+        }
+
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .max(1)
+            .min(MAX_SEARCH_LIMIT);
+
+        models::search_users(conn, &query.prefix, limit)
+    })
+    .then(convert)
+}
+
 fn get_user(
     user_id: web::Path<i32>,
     pool: web::Data<Pool>,
@@ -137,8 +292,55 @@ fn get_user(
     .then(convert)
 }
 
+fn user_activity(
+    user_id: web::Path<i32>,
+    pool: web::Data<Pool>,
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn = &pool.get().unwrap();
+
+        models::user_activity(conn, user_id.into_inner())
+    })
+    .then(convert)
+}
+
+fn user_summary(
+    user_id: web::Path<i32>,
+    pool: web::Data<Pool>,
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn = &pool.get().unwrap();
+
+        models::user_summary(conn, user_id.into_inner())
+    })
+    .then(convert)
+}
+
+fn transfer_posts(
+    user_id: web::Path<i32>,
+    input: web::Json<TransferInput>,
+    pool: web::Data<Pool>,
+) -> impl Future<Item = HttpResponse, Error = AppError> {
+    web::block(move || {
+        let conn = &pool.get().unwrap();
+
+        models::transfer_posts(conn, user_id.into_inner(), input.to_user_id)
+            .map(|transferred| TransferredCount { transferred })
+    })
+    .then(convert)
+}
+
 pub fn configure(cfg: &mut web::ServiceConfig) {
-    cfg.service(web::resource("/users").route(web::post().to_async(create_user)))
+    cfg.service(
+        web::resource("/users")
+            .route(web::post().to_async(create_user))
+            .route(web::get().to_async(users_by_ids))
+    )
+        .service(web::resource("/signup").route(web::post().to_async(signup)))
         .service(web::resource("/users/find/{name}").route(web::get().to_async(find_user)))
-        .service(web::resource("/users/{id}").route(web::get().to_async(get_user)));
+        .service(web::resource("/users/search").route(web::get().to_async(search_users)))
+        .service(web::resource("/users/{id}").route(web::get().to_async(get_user)))
+        .service(web::resource("/users/{id}/activity").route(web::get().to_async(user_activity)))
+        .service(web::resource("/users/{id}/summary").route(web::get().to_async(user_summary)))
+        .service(web::resource("/users/{id}/transfer-posts").route(web::post().to_async(transfer_posts)));
 }
\ No newline at end of file
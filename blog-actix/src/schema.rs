@@ -4,6 +4,14 @@ table! {
         user_id -> Integer,
         post_id -> Integer,
         body -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    post_tags (post_id, tag_id) {
+        post_id -> Integer,
+        tag_id -> Integer,
     }
 }
 
@@ -14,6 +22,16 @@ table! {
         title -> Text,
         body -> Text,
         published -> Bool,
+        deleted_at -> Nullable<Timestamp>,
+        version -> Integer,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    tags (id) {
+        id -> Integer,
+        name -> Text,
     }
 }
 
@@ -26,10 +44,14 @@ table! {
 
 joinable!(comments -> posts (post_id));
 joinable!(comments -> users (user_id));
+joinable!(post_tags -> posts (post_id));
+joinable!(post_tags -> tags (tag_id));
 joinable!(posts -> users (user_id));
 
 allow_tables_to_appear_in_same_query!(
     comments,
+    post_tags,
     posts,
+    tags,
     users,
 );
@@ -0,0 +1,57 @@
+/***
+ *
+ *
+ *
+    FIELDS THE DERIVE REFUSES
+
+    A binary crate has no doctests, so the cases which must not compile live here as compile_fail examples
+
+    Each one is compiled as its own little crate against builder, and the example passes only if compiling it fails
+    The plain example at the end uses the same setup and has to compile,
+    so a broken setup can't make the compile_fail examples pass by accident
+
+***/
+
+//! A field named `builder`:
+//!
+//! ```compile_fail
+//! use builder::Builder;
+//!
+//! #[derive(Builder)]
+//! struct Pipeline {
+//!     builder: String,
+//!     stages: u8,
+//! }
+//!
+//! fn main() {}
+//! ```
+//!
+//! A field named like a method of the builder:
+//!
+//! ```compile_fail
+//! use builder::Builder;
+//!
+//! #[derive(Builder)]
+//! struct Pipeline {
+//!     build: u8,
+//! }
+//!
+//! fn main() {}
+//! ```
+//!
+//! The same struct with the field renamed:
+//!
+//! ```
+//! use builder::Builder;
+//!
+//! #[derive(Builder)]
+//! struct Pipeline {
+//!     runner: String,
+//!     stages: u8,
+//! }
+//!
+//! fn main() {
+//!     let pipeline = Pipeline::builder().runner("docker").stages(3u8).build();
+//!     assert_eq!((pipeline.runner.as_str(), pipeline.stages), ("docker", 3));
+//! }
+//! ```
@@ -95,6 +95,94 @@
             }
         }
     }
+
+    CLEARING A FIELD
+
+    With #[builder(with_clear)] on the struct, every field that is not required
+    also gets a clear method which resets the field back to None:
+
+        fn retries(mut self) -> Self { ... }
+        fn clear_retries(mut self) -> Self {
+            self.retries = None;
+            self
+        }
+
+    so setting retries and then clearing it means build() uses Default::default, i.e. 0
+
+    TAKING THE BUILDER APART
+
+    With #[builder(expose_parts)] on the struct the builder gets an into_parts method
+    which gives back every field as an Option without building:
+
+        fn into_parts(self) -> (Option<String>, Option<u16>, Option<bool>,)
+
+    so setting host and port but not secure gives (Some(host), Some(port), None)
+
+    VALIDATING FIELDS
+
+    #[builder(validate = "validate_port")] runs validate_port(&port) when building
+    and having a validator makes build() return Result<Listener, Vec<String>>
+
+    Port 0 is rejected, so building with it gives Err with the message from validate_port,
+    while any other port builds just fine
+
+    USING OUR OWN ERROR TYPE
+
+    #[builder(error = "ConfigError")] on Connection makes build() return Result<Connection, ConfigError>
+
+    Leaving out the required host gives ConfigError::Missing(vec!["host"])
+    through the From<Vec<&'static str>> impl instead of panicking,
+    and a bad port gives ConfigError::Invalid through From<Vec<String>>
+    because port also has a validator
+
+    FIELDS NAMED LIKE THE GENERATED METHODS
+
+    A field called build, new, or one of the other methods the builder has is refused with an error on the field:
+        #[derive(Builder)]
+        struct Pipeline {
+            build: u8,
+        }
+    gives: a field named `build` clashes with the `build` method generated on the builder, rename the field
+
+    A field called builder is refused too, as Pipeline::builder().builder("docker") is too easily misread:
+        a field named `builder` is confused with the `builder()` function generated on the struct, rename the field
+
+    so Pipeline calls the field runner instead
+
+    Both of these are compile_fail examples in lib.rs, cargo test runs them as doctests
+    and they pass only if the derive refuses to compile the struct
+
+    NESTED BUILDERS
+
+    Order has two fields marked #[builder(nested)], customer and shipping,
+    and Customer in turn has a nested address, so the whole order is built in one go:
+
+        Order::builder()
+            .id(1u32)
+            .customer(|c| c.name("Ruben").address(|a| a.street("1 Main St").city("Springfield")))
+            .shipping(|a| a.street("2 Side St").city("Shelbyville"))
+            .build()
+
+    Each closure is handed a fresh builder for the field's type and returns it with the fields set,
+    and the setter builds it and stores the result
+
+    ALIASES
+
+    #[builder(alias(size => width, height))] on Window adds a size setter taking a (width, height) tuple,
+    and #[builder(alias(caption => title))] adds caption as a second name for the title setter:
+
+        Window::builder().size((800u32, 600u32)).caption("main").build()
+
+    gives the same Window as setting width, height, and title one at a time
+
+    MERGING BUILDERS
+
+    #[builder(mergeable)] on Theme adds a merge method to its builder,
+    so a builder of defaults can have a builder of overrides layered on top:
+
+        defaults.merge(overrides).build()
+
+    Every field the overrides set wins, and the rest keep the value from the defaults
 ***/
 
 use builder::Builder;
@@ -117,6 +205,107 @@ where
     f: U,
 }
 
+#[derive(Debug, Builder)]
+#[builder(with_clear)]
+struct Settings {
+    name: String,
+    retries: u8,
+}
+
+#[derive(Debug, Builder)]
+#[builder(expose_parts)]
+struct Endpoint {
+    host: String,
+    port: u16,
+    secure: bool,
+}
+
+#[derive(Debug, Builder)]
+struct Listener {
+    #[builder(validate = "validate_port")]
+    port: u16,
+    backlog: u32,
+}
+
+#[derive(Debug, Builder)]
+#[builder(error = "ConfigError")]
+struct Connection {
+    #[builder(required)]
+    host: String,
+    #[builder(validate = "validate_port")]
+    port: u16,
+}
+
+#[derive(Debug, Builder)]
+struct Pipeline {
+    runner: String,
+    stages: u8,
+}
+
+#[derive(Debug, Default, PartialEq, Builder)]
+struct Address {
+    street: String,
+    city: String,
+}
+
+#[derive(Debug, Default, PartialEq, Builder)]
+struct Customer {
+    name: String,
+    #[builder(nested)]
+    address: Address,
+}
+
+#[derive(Debug, Builder)]
+struct Order {
+    id: u32,
+    #[builder(nested)]
+    customer: Customer,
+    #[builder(nested)]
+    shipping: Address,
+}
+
+#[derive(Debug, PartialEq, Builder)]
+#[builder(alias(size => width, height), alias(caption => title))]
+struct Window {
+    title: String,
+    width: u32,
+    height: u32,
+}
+
+#[derive(Debug, PartialEq, Builder)]
+#[builder(mergeable)]
+struct Theme {
+    font: String,
+    size: u8,
+    dark: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum ConfigError {
+    Missing(Vec<&'static str>),
+    Invalid(Vec<String>),
+}
+
+impl From<Vec<&'static str>> for ConfigError {
+    fn from(fields: Vec<&'static str>) -> ConfigError {
+        ConfigError::Missing(fields)
+    }
+}
+
+impl From<Vec<String>> for ConfigError {
+    fn from(messages: Vec<String>) -> ConfigError {
+        ConfigError::Invalid(messages)
+    }
+}
+
+fn validate_port(port: &u16) -> Result<(), String> {
+    if *port == 0 {
+        Err("port must not be 0".to_owned())
+    } else {
+        Ok(())
+    }
+}
+
 fn main() {
     let item: Item<i32, &str> = Item::builder()
         .a(42u32)
@@ -132,4 +321,97 @@ fn main() {
     let item2 = Item::<u32, u64>::builder().b(None).d(X {}).f(99u64).build();
 
     println!("{:#?}", item2);
+
+    let settings = Settings::builder()
+        .name("cleared")
+        .retries(3u8)
+        .clear_retries()
+        .build();
+
+    println!("{:#?}", settings);
+    assert_eq!(settings.retries, 0);
+
+    let parts = Endpoint::builder()
+        .host("localhost")
+        .port(8080u16)
+        .into_parts();
+
+    println!("{:?}", parts);
+    assert_eq!(parts, (Some("localhost".to_owned()), Some(8080), None));
+
+    let listener = Listener::builder().port(8080u16).backlog(128u32).build();
+
+    println!("{:?}", listener);
+    assert_eq!(listener.unwrap().port, 8080);
+
+    let invalid = Listener::builder().port(0u16).build();
+
+    println!("{:?}", invalid);
+    assert_eq!(invalid.unwrap_err(), vec!["port must not be 0".to_owned()]);
+
+    let connection = Connection::builder().host("localhost").port(5432u16).build();
+
+    println!("{:?}", connection);
+    let connection = connection.unwrap();
+    assert_eq!((connection.host.as_str(), connection.port), ("localhost", 5432));
+
+    let missing = Connection::builder().port(5432u16).build();
+
+    println!("{:?}", missing);
+    assert_eq!(missing.unwrap_err(), ConfigError::Missing(vec!["host"]));
+
+    let bad_port = Connection::builder().host("localhost").port(0u16).build();
+
+    println!("{:?}", bad_port);
+    assert_eq!(
+        bad_port.unwrap_err(),
+        ConfigError::Invalid(vec!["port must not be 0".to_owned()])
+    );
+
+    let pipeline = Pipeline::builder().runner("docker").stages(3u8).build();
+
+    println!("{:?}", pipeline);
+    assert_eq!((pipeline.runner.as_str(), pipeline.stages), ("docker", 3));
+
+    let order = Order::builder()
+        .id(1u32)
+        .customer(|c| c.name("Ruben").address(|a| a.street("1 Main St").city("Springfield")))
+        .shipping(|a| a.street("2 Side St").city("Shelbyville"))
+        .build();
+
+    println!("{:#?}", order);
+    assert_eq!(order.id, 1);
+    assert_eq!(
+        order.customer,
+        Customer {
+            name: "Ruben".to_owned(),
+            address: Address {
+                street: "1 Main St".to_owned(),
+                city: "Springfield".to_owned(),
+            },
+        }
+    );
+    assert_eq!(order.shipping.city, "Shelbyville");
+
+    let window = Window::builder().size((800u32, 600u32)).caption("main").build();
+
+    println!("{:?}", window);
+    assert_eq!(
+        window,
+        Window::builder().title("main").width(800u32).height(600u32).build()
+    );
+
+    let defaults = Theme::builder().font("Fira Code").size(12u8).dark(false);
+    let overrides = Theme::builder().size(14u8).dark(true);
+    let theme = defaults.merge(overrides).build();
+
+    println!("{:?}", theme);
+    assert_eq!(
+        theme,
+        Theme {
+            font: "Fira Code".to_owned(),
+            size: 14,
+            dark: true,
+        }
+    );
 }
@@ -486,12 +486,196 @@
     and then defines a build function which consumes the builder
     and constructs an instance of the struct that is being built
 
+    CLEARING FIELDS
+
+    Once a field has been set there is no way to go back to the unset state with the setters alone
+
+    Putting #[builder(with_clear)] on the struct itself generates a clear_field method
+    for every field that is not required, e.g. for a: u32 the following is created:
+
+        fn clear_a(mut self) -> Self {
+            self.a = None;
+            self
+        }
+
+    so that build() falls back to Default::default for that field again
+
+    Required fields do not get a clear method as clearing one would only set up a panic in build()
+
+    As with_clear describes the builder as a whole it is an error to put it on a field,
+    the mirror image of required which is an error on the struct
+
+    EXPOSING THE PARTS
+
+    Putting #[builder(expose_parts)] on the struct generates an into_parts method
+    which consumes the builder and hands back its fields as a tuple of Options in declaration order,
+    e.g. for a: u32 and b: String the following is created:
+
+        fn into_parts(self) -> (Option<u32>, Option<String>,) {
+            (self.a, self.b,)
+        }
+
+    Nothing is defaulted or unwrapped so the caller can tell which fields were actually set,
+    which is handy for merging two builders or for debugging
+
+    The trailing comma inside the tuple keeps a struct with a single field returning a one element tuple
+    rather than just the Option itself
+
+    Like with_clear it only makes sense on the struct
+
+    VALIDATING FIELDS
+
+    A field can name a function to check its value with #[builder(validate = "validate_port")]
+
+    The function is given a reference to the field's value and returns Result<(), String>,
+    e.g. fn validate_port(port: &u16) -> Result<(), String>
+
+    The attribute value is a string literal, so after the = we parse a LitStr
+    and then parse the contents of the string as a syn::Path, which allows paths like checks::validate_port
+
+    As soon as one field has a validator build() becomes fallible and returns Result<T, Vec<String>>
+    Each field is first pulled out of the builder into a local variable the same way as before,
+    then every validator is run against its field and each failure message is collected,
+    so the caller finds out about all of the invalid fields at once rather than just the first
+
+    Structs without any validators keep the infallible build() so existing code does not change
+
+    Now that there is more than one field attribute, whether a field is required
+    is asked of is_required rather than checking that the field has no attributes at all
+
+    FIELDS THAT CLASH WITH GENERATED METHODS
+
+    Each field gets a setter of the same name on the builder, and the builder also has methods of its own:
+    new, build, into_parts with expose_parts, merge with mergeable, and clear_ methods with with_clear
+
+    So a field called build would give the builder two methods called build,
+    and the compiler reports that as a duplicate definition pointing at the derive,
+    which doesn't say anything about which field is to blame
+
+    clashing_method checks every field name against the methods the builder will have
+    and we report a clash as our own error spanned on the field itself
+
+    There is no attribute to rename the generated methods so the only fix is renaming the field
+
+    A field called builder is refused as well
+    Strictly the two never meet, the builder() function lives on the struct while the setter lives on the builder,
+    but Pipeline::builder().builder("docker") reads like a mistake, and a struct which already has a builder method
+    of its own would get the opaque duplicate definition error instead, so we stop it with a clear error on the field
+
+    CUSTOM ERROR TYPES
+
+    Putting #[builder(error = "BuildError")] on the struct makes build() return Result<T, BuildError>
+    so the builder fits in with whatever error enum the application already has
+
+    The value is parsed as a syn::Type the same way the validate attribute parses its path
+
+    With an error type build() no longer panics when a required field is missing,
+    instead it gathers the names of every missing required field and converts them with
+    <BuildError as From<Vec<&'static str>>>::from
+
+    If there are also validators their messages are converted with From<Vec<String>>,
+    so the error type only has to implement that second conversion when a field has a validator
+
+    The conversions are written out with quote_spanned! using the span of the error type,
+    and since LitStr::parse gives the parsed tokens the span of the string literal,
+    a missing From impl is reported on the "BuildError" in the attribute rather than somewhere inside the macro
+
+    Like with_clear and expose_parts it is only valid on the struct
+
+    NESTED BUILDERS
+
+    Putting #[builder(nested)] on a field whose type also derives Builder
+    changes its setter to take a closure which fills in that type's builder instead of a finished value:
+
+        fn address<__Builder_F: FnOnce(AddressBuilder) -> AddressBuilder>(mut self, f: __Builder_F) -> Self {
+            self.address = Some(f(<Address>::builder()).build());
+            self
+        }
+
+    so a struct can be put together in one expression, e.g. .address(|b| b.street("...").city("..."))
+
+    The macro only sees the tokens of the field type, not the type itself,
+    so nested_builder_type works out the name of the other builder the same way we name our own,
+    by adding Builder to the last segment of the path and keeping any generic arguments, so Wrapper<T> gives WrapperBuilder<T>
+
+    That means the field type has to be a path to a struct deriving Builder,
+    a reference or tuple is refused with an error on the type,
+    while a path to a struct that doesn't derive Builder fails to compile on the missing builder
+
+    The nested build() is called straight away and its result stored, so the nested type needs the infallible build(),
+    i.e. no validators and no error attribute
+
+    The nested attribute is only valid on a field
+
+    ALIASES
+
+    Putting #[builder(alias(size => width, height))] on the struct gives the builder a setter called size
+    which sets width and height together from a tuple:
+
+        fn size<__Builder_T0: Into<u32>, __Builder_T1: Into<u32>>(mut self, val: (__Builder_T0, __Builder_T1)) -> Self {
+            self.width = Some(val.0.into());
+            self.height = Some(val.1.into());
+            self
+        }
+
+    The tuple is in the same order as the fields are listed in the attribute,
+    and each element goes through Into like any other setter so the fields can have different types
+    An alias for a single field takes the value itself rather than a one element tuple
+
+    Inside alias(...) we parse the alias name, the => token, and then a comma separated list of field names
+    with Punctuated::parse_separated_nonempty, so an alias has to name at least one field
+
+    The fields keep their own setters, and setting a field again after the alias simply replaces the value
+
+    parse_builder_struct checks every alias:
+    each field it lists has to exist and can only be listed once,
+    and the alias name can't be the same as a field, another alias, or one of the builder's own methods,
+    each mistake is reported on the offending name in the attribute
+
+    Like with_clear and expose_parts it is only valid on the struct
+
+    MERGING BUILDERS
+
+    Putting #[builder(mergeable)] on the struct generates a merge method
+    which layers one builder on top of another, e.g. for a: u32 and b: String the following is created:
+
+        fn merge(mut self, other: Self) -> Self {
+            if other.a.is_some() {
+                self.a = other.a;
+            }
+            if other.b.is_some() {
+                self.b = other.b;
+            }
+            self
+        }
+
+    so every field set in other wins and every field other left unset keeps the value from self,
+    which is handy for a builder of defaults with a builder of overrides merged on top
+
+    Nothing is built or validated while merging, that still happens in build()
+    With mergeable a field called merge would clash with the method, see clashing_method
+
+    Like with_clear and expose_parts it is only valid on the struct
+
+    REQUIRED WITH A VALUE
+
+    Besides the bare #[builder(required)] a field can say #[builder(required = true)] or #[builder(required = false)],
+    which is easier for code that generates the attribute from a flag than leaving the word out
+
+    After the required ident the Parse impl peeks for an = and if there is one it reads the rest as a MetaNameValue,
+    i.e. path = lit, with the ident we already have as the path
+    Anything but a bool literal is an error on the literal, e.g. required = "yes"
+
+    The bool is kept in Required next to the tokens and is_required only counts Required(_, true),
+    so required = false behaves as if the attribute wasn't there and the field falls back to Default
+    On the struct it is still an error whatever the value
+
     
 ***/
     
 extern crate proc_macro;
 use proc_macro::TokenStream;
-use quote::quote;
+use quote::{quote, quote_spanned};
 use std::fmt;
 use syn::parenthesized;
 use syn::parse::Result as SynResult;
@@ -499,7 +683,14 @@ use syn::parse::Result as SynResult;
 type MultiResult<T> = std::result::Result<T, Vec<syn::Error>>;
 
 enum BuilderAttribute {
-    Required(proc_macro2::TokenStream),
+    Required(proc_macro2::TokenStream, bool),
+    WithClear(proc_macro2::TokenStream),
+    ExposeParts(proc_macro2::TokenStream),
+    Validate(syn::Path),
+    Error(Box<syn::Type>),
+    Nested(proc_macro2::TokenStream),
+    Alias(syn::Ident, Vec<syn::Ident>),
+    Mergeable(proc_macro2::TokenStream),
 }
 
 #[derive(Debug, Default)]
@@ -511,6 +702,11 @@ struct BuilderInfo {
     name: syn::Ident,
     generics: syn::Generics,
     fields: Vec<(Option<syn::Ident>, syn::Type, Vec<BuilderAttribute>)>,
+    with_clear: bool,
+    expose_parts: bool,
+    mergeable: bool,
+    error: Option<syn::Type>,
+    aliases: Vec<(syn::Ident, Vec<syn::Ident>)>,
 }
 
 struct BuilderAttributeBody(Vec<BuilderAttribute>);
@@ -562,11 +758,54 @@ impl syn::parse::Parse for BuilderAttribute {
         let name: Ident = input.parse()?;
 
         if name == "required" {
-            Ok(BuilderAttribute::Required(input_tts))
+            if !input.peek(syn::Token![=]) {
+                return Ok(BuilderAttribute::Required(input_tts, true));
+            }
+
+            let meta = syn::MetaNameValue {
+                path: name.into(),
+                eq_token: input.parse()?,
+                lit: input.parse()?,
+            };
+
+            match meta.lit {
+                syn::Lit::Bool(required) => Ok(BuilderAttribute::Required(input_tts, required.value)),
+                lit => Err(syn::Error::new_spanned(lit, "expected `true` or `false`")),
+            }
+        } else if name == "with_clear" {
+            Ok(BuilderAttribute::WithClear(input_tts))
+        } else if name == "expose_parts" {
+            Ok(BuilderAttribute::ExposeParts(input_tts))
+        } else if name == "validate" {
+            input.parse::<syn::Token![=]>()?;
+            let func: syn::LitStr = input.parse()?;
+
+            Ok(BuilderAttribute::Validate(func.parse()?))
+        } else if name == "error" {
+            input.parse::<syn::Token![=]>()?;
+            let ty: syn::LitStr = input.parse()?;
+
+            Ok(BuilderAttribute::Error(Box::new(ty.parse()?)))
+        } else if name == "nested" {
+            Ok(BuilderAttribute::Nested(input_tts))
+        } else if name == "alias" {
+            use syn::punctuated::Punctuated;
+            use syn::token::Comma;
+
+            let inside;
+            parenthesized!(inside in input);
+
+            let alias: Ident = inside.parse()?;
+            inside.parse::<syn::Token![=>]>()?;
+            let fields = Punctuated::<Ident, Comma>::parse_separated_nonempty(&inside)?;
+
+            Ok(BuilderAttribute::Alias(alias, fields.into_iter().collect()))
+        } else if name == "mergeable" {
+            Ok(BuilderAttribute::Mergeable(input_tts))
         } else {
             Err(syn::Error::new(
                 name.span(),
-                "expected `required`",
+                "expected `required`, `with_clear`, `expose_parts`, `validate`, `error`, `nested`, `alias` or `mergeable`",
             ))
         }
     }
@@ -581,8 +820,20 @@ impl From<BuilderInfo> for TokenStream {
 impl BuilderInfo {
     fn generate_builder(self) -> proc_macro2::TokenStream {
         let gen_typ = syn::Ident::new("__Builder_T", proc_macro2::Span::call_site());
+        let gen_fn = syn::Ident::new("__Builder_F", proc_macro2::Span::call_site());
+
+        let setters = self.fields.iter().map(|(n, t, a)| {
+            if is_nested(a) {
+                let nested = nested_builder_type(t).expect("nested field types are checked while parsing");
+
+                return quote! {
+                    fn #n<#gen_fn: FnOnce(#nested) -> #nested>(mut self, f: #gen_fn) -> Self {
+                        self.#n = Some(f(<#t>::builder()).build());
+                        self
+                    }
+                };
+            }
 
-        let setters = self.fields.iter().map(|(n, t, _)| {
             quote! {
                 fn #n<#gen_typ: Into<#t>>(mut self, val: #gen_typ) -> Self {
                     self.#n = Some(val.into());
@@ -591,6 +842,41 @@ impl BuilderInfo {
             }
         });
 
+        let fields = &self.fields;
+        let alias_setters = self.aliases.iter().map(|(alias, targets)| {
+            let types = targets.iter().map(|target| {
+                fields
+                    .iter()
+                    .find(|(n, _, _)| n.as_ref() == Some(target))
+                    .map(|(_, t, _)| t)
+                    .expect("alias fields are checked while parsing")
+            });
+            let gen_typs: Vec<_> = (0..targets.len())
+                .map(|i| syn::Ident::new(&format!("{}{}", gen_typ, i), proc_macro2::Span::call_site()))
+                .collect();
+            let bounds = gen_typs.iter().zip(types).map(|(g, t)| quote! { #g: Into<#t> });
+
+            if let [target] = &targets[..] {
+                let g = &gen_typs[0];
+
+                return quote! {
+                    fn #alias<#(#bounds),*>(mut self, val: #g) -> Self {
+                        self.#target = Some(val.into());
+                        self
+                    }
+                };
+            }
+
+            let indexes = (0..targets.len()).map(syn::Index::from);
+
+            quote! {
+                fn #alias<#(#bounds),*>(mut self, val: (#(#gen_typs),*)) -> Self {
+                    #(self.#targets = Some(val.#indexes.into());)*
+                    self
+                }
+            }
+        });
+
         let builder_fields = self.fields.iter().map(|(n, t, _)| {
             quote! {
                 #n: Option<#t>,
@@ -603,22 +889,186 @@ impl BuilderInfo {
             }
         });
 
+        let with_clear = self.with_clear;
+        let clearers = self
+            .fields
+            .iter()
+            .filter(|(_, _, a)| with_clear && !is_required(a))
+            .map(|(n, _, _)| {
+                let n = n.as_ref().expect("only named fields are supported");
+                let clear_name = syn::Ident::new(&format!("clear_{}", n), n.span());
+
+                quote! {
+                    fn #clear_name(mut self) -> Self {
+                        self.#n = None;
+                        self
+                    }
+                }
+            });
+
+        let into_parts = if self.expose_parts {
+            let part_types = self.fields.iter().map(|(_, t, _)| quote! { Option<#t>, });
+            let part_values = self.fields.iter().map(|(n, _, _)| quote! { self.#n, });
+
+            quote! {
+                fn into_parts(self) -> (#(#part_types)*) {
+                    (#(#part_values)*)
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        let merge = if self.mergeable {
+            let merges = self.fields.iter().map(|(n, _, _)| {
+                quote! {
+                    if other.#n.is_some() {
+                        self.#n = other.#n;
+                    }
+                }
+            });
+
+            quote! {
+                fn merge(mut self, other: Self) -> Self {
+                    #(#merges)*
+                    self
+                }
+            }
+        } else {
+            quote! {}
+        };
+
         let builder_build = self.fields.iter().map(|(n, _t, a)| {
-            if a.is_empty() {
+            if is_required(a) {
+                quote! {
+                    #n: self.#n.unwrap(),
+                }
+            } else {
                 quote! {
                     #n: self.#n.unwrap_or_else(Default::default),
                 }
+            }
+        });
+
+        let builder_locals = self.fields.iter().map(|(n, _t, a)| {
+            if is_required(a) {
+                quote! {
+                    let #n = self.#n.unwrap();
+                }
             } else {
                 quote! {
-                    #n: self.#n.unwrap(),
+                    let #n = self.#n.unwrap_or_else(Default::default);
                 }
             }
         });
 
+        let validators: Vec<_> = self
+            .fields
+            .iter()
+            .flat_map(|(n, _, a)| {
+                a.iter().filter_map(move |attr| match attr {
+                    BuilderAttribute::Validate(func) => Some(quote! {
+                        if let Err(e) = #func(&#n) {
+                            __builder_errors.push(e);
+                        }
+                    }),
+                    _ => None,
+                })
+            })
+            .collect();
+
         let name = self.name;
         let (impl_generics, ty_generics, maybe_where) = self.generics.split_for_impl();
         let builder_name = syn::Ident::new(&format!("{}Builder", name), name.span());
 
+        let build = if let Some(err) = &self.error {
+            use syn::spanned::Spanned;
+
+            let err_span = err.span();
+            let locals = self.fields.iter().map(|(n, _, _)| n);
+            let missing_checks = self
+                .fields
+                .iter()
+                .filter(|(_, _, a)| is_required(a))
+                .map(|(n, _, _)| {
+                    let field_name = n.as_ref().expect("only named fields are supported").to_string();
+
+                    quote! {
+                        if self.#n.is_none() {
+                            __builder_missing.push(#field_name);
+                        }
+                    }
+                });
+            let missing_error = quote_spanned! {err_span=>
+                <#err as From<Vec<&'static str>>>::from(__builder_missing)
+            };
+            let validate = if validators.is_empty() {
+                quote! {}
+            } else {
+                let invalid_error = quote_spanned! {err_span=>
+                    <#err as From<Vec<String>>>::from(__builder_errors)
+                };
+
+                quote! {
+                    let mut __builder_errors: Vec<String> = Vec::new();
+
+                    #(#validators)*
+
+                    if !__builder_errors.is_empty() {
+                        return Err(#invalid_error);
+                    }
+                }
+            };
+
+            quote! {
+                fn build(self) -> Result<#name #ty_generics, #err> {
+                    let mut __builder_missing: Vec<&'static str> = Vec::new();
+
+                    #(#missing_checks)*
+
+                    if !__builder_missing.is_empty() {
+                        return Err(#missing_error);
+                    }
+
+                    #(#builder_locals)*
+
+                    #validate
+
+                    Ok(#name {
+                        #(#locals),*
+                    })
+                }
+            }
+        } else if validators.is_empty() {
+            quote! {
+                fn build(self) -> #name #ty_generics {
+                    #name {
+                        #(#builder_build)*
+                    }
+                }
+            }
+        } else {
+            let locals = self.fields.iter().map(|(n, _, _)| n);
+
+            quote! {
+                fn build(self) -> Result<#name #ty_generics, Vec<String>> {
+                    #(#builder_locals)*
+
+                    let mut __builder_errors: Vec<String> = Vec::new();
+
+                    #(#validators)*
+
+                    if !__builder_errors.is_empty() {
+                        return Err(__builder_errors);
+                    }
+
+                    Ok(#name {
+                        #(#locals),*
+                    })
+                }
+            }
+        };
+
         quote! {
             impl #impl_generics #name #ty_generics #maybe_where {
                 fn builder() -> #builder_name #ty_generics {
@@ -645,11 +1095,15 @@ impl BuilderInfo {
 
                 #(#setters)*
 
-                fn build(self) -> #name #ty_generics {
-                    #name {
-                        #(#builder_build)*
-                    }
-                }
+                #(#alias_setters)*
+
+                #(#clearers)*
+
+                #into_parts
+
+                #merge
+
+                #build
             }
         }
     }
@@ -709,12 +1163,28 @@ fn parse_builder_struct(
     use syn::Fields;
 
     let mut errors = SyntaxErrors::default();
+    let mut with_clear = false;
+    let mut expose_parts = false;
+    let mut mergeable = false;
+    let mut error = None;
+    let mut aliases = Vec::new();
 
     for attr in attributes_from_syn(attrs)? {
         match attr {
-            BuilderAttribute::Required(tts) => {
+            BuilderAttribute::Required(tts, _) => {
                 errors.add(tts, "required is only valid on a field");
             }
+            BuilderAttribute::WithClear(_) => with_clear = true,
+            BuilderAttribute::ExposeParts(_) => expose_parts = true,
+            BuilderAttribute::Validate(func) => {
+                errors.add(func, "validate is only valid on a field");
+            }
+            BuilderAttribute::Error(ty) => error = Some(*ty),
+            BuilderAttribute::Nested(tts) => {
+                errors.add(tts, "nested is only valid on a field");
+            }
+            BuilderAttribute::Alias(alias, targets) => aliases.push((alias, targets)),
+            BuilderAttribute::Mergeable(_) => mergeable = true,
         }
     }
 
@@ -736,13 +1206,84 @@ fn parse_builder_struct(
         .named
         .into_iter()
         .map(|f| match attributes_from_syn(f.attrs) {
-            Ok(attrs) => (f.ident, f.ty, attrs),
+            Ok(attrs) => {
+                let mut field_attrs = Vec::new();
+
+                for attr in attrs {
+                    match attr {
+                        BuilderAttribute::WithClear(tts) => {
+                            errors.add(tts, "with_clear is only valid on a struct");
+                        }
+                        BuilderAttribute::ExposeParts(tts) => {
+                            errors.add(tts, "expose_parts is only valid on a struct");
+                        }
+                        BuilderAttribute::Error(ty) => {
+                            errors.add(ty, "error is only valid on a struct");
+                        }
+                        BuilderAttribute::Alias(alias, _) => {
+                            errors.add(alias, "alias is only valid on a struct");
+                        }
+                        BuilderAttribute::Mergeable(tts) => {
+                            errors.add(tts, "mergeable is only valid on a struct");
+                        }
+                        BuilderAttribute::Nested(_) if nested_builder_type(&f.ty).is_none() => {
+                            errors.add(&f.ty, "nested is only valid on a field whose type is a struct deriving Builder");
+                        }
+                        attr => field_attrs.push(attr),
+                    }
+                }
+
+                (f.ident, f.ty, field_attrs)
+            }
             Err(e) => {
                 errors.extend(e);
                 (f.ident, f.ty, vec![])
             }
         })
-        .collect();
+        .collect::<Vec<_>>();
+
+    for (n, _, _) in fields.iter() {
+        if let Some(n) = n.as_ref().filter(|n| *n == "builder") {
+            errors.add(
+                n,
+                "a field named `builder` is confused with the `builder()` function generated on the struct, rename the field",
+            );
+        } else if let Some(clash) = n
+            .as_ref()
+            .and_then(|n| clashing_method(n, &fields, with_clear, expose_parts, mergeable))
+        {
+            errors.add(
+                n,
+                format!(
+                    "a field named `{0}` clashes with the `{0}` method generated on the builder, rename the field",
+                    clash
+                ),
+            );
+        }
+    }
+
+    for (i, (alias, targets)) in aliases.iter().enumerate() {
+        let alias_name = alias.to_string();
+
+        if fields.iter().any(|(n, _, _)| n.as_ref() == Some(alias)) {
+            errors.add(alias, format!("the alias `{}` has the same name as a field", alias_name));
+        } else if aliases[..i].iter().any(|(other, _)| other == alias) {
+            errors.add(alias, format!("the alias `{}` is defined more than once", alias_name));
+        } else if clashing_method(alias, &fields, with_clear, expose_parts, mergeable).is_some() {
+            errors.add(
+                alias,
+                format!("the alias `{0}` clashes with the `{0}` method generated on the builder", alias_name),
+            );
+        }
+
+        for (j, target) in targets.iter().enumerate() {
+            if !fields.iter().any(|(n, _, _)| n.as_ref() == Some(target)) {
+                errors.add(target, format!("no field named `{}` to alias", target));
+            } else if targets[..j].contains(target) {
+                errors.add(target, format!("`{}` is listed more than once in the alias", target));
+            }
+        }
+    }
 
     errors.finish()?;
 
@@ -750,9 +1291,65 @@ fn parse_builder_struct(
         name,
         generics,
         fields,
+        with_clear,
+        expose_parts,
+        mergeable,
+        error,
+        aliases,
     })
 }
 
+fn clashing_method(
+    name: &syn::Ident,
+    fields: &[(Option<syn::Ident>, syn::Type, Vec<BuilderAttribute>)],
+    with_clear: bool,
+    expose_parts: bool,
+    mergeable: bool,
+) -> Option<String> {
+    let name = name.to_string();
+
+    let clears_another_field = with_clear
+        && fields.iter().any(|(other, _, a)| match other {
+            Some(other) => !is_required(a) && name == format!("clear_{}", other),
+            None => false,
+        });
+
+    if name == "new"
+        || name == "build"
+        || (expose_parts && name == "into_parts")
+        || (mergeable && name == "merge")
+        || clears_another_field
+    {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+fn is_required(attrs: &[BuilderAttribute]) -> bool {
+    attrs
+        .iter()
+        .any(|attr| matches!(attr, BuilderAttribute::Required(_, true)))
+}
+
+fn is_nested(attrs: &[BuilderAttribute]) -> bool {
+    attrs
+        .iter()
+        .any(|attr| matches!(attr, BuilderAttribute::Nested(_)))
+}
+
+fn nested_builder_type(ty: &syn::Type) -> Option<syn::Type> {
+    let mut path = match ty {
+        syn::Type::Path(path) if path.qself.is_none() => path.clone(),
+        _ => return None,
+    };
+
+    let last = path.path.segments.last_mut()?;
+    last.ident = syn::Ident::new(&format!("{}Builder", last.ident), last.ident.span());
+
+    Some(syn::Type::Path(path))
+}
+
 fn attributes_from_syn(attrs: Vec<syn::Attribute>) -> MultiResult<Vec<BuilderAttribute>> {
     use syn::parse2;
 
@@ -779,4 +1376,43 @@ fn attributes_from_syn(attrs: Vec<syn::Attribute>) -> MultiResult<Vec<BuilderAtt
     } else {
         Err(errs)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_body(tokens: &str) -> SynResult<Vec<BuilderAttribute>> {
+        syn::parse_str::<BuilderAttributeBody>(tokens).map(|body| body.0)
+    }
+
+    #[test]
+    fn bare_required_is_required() {
+        let attrs = parse_body("(required)").unwrap();
+
+        assert!(matches!(attrs[..], [BuilderAttribute::Required(_, true)]));
+        assert!(is_required(&attrs));
+    }
+
+    #[test]
+    fn required_takes_a_bool() {
+        let attrs = parse_body("(required = true)").unwrap();
+        assert!(matches!(attrs[..], [BuilderAttribute::Required(_, true)]));
+        assert!(is_required(&attrs));
+
+        let attrs = parse_body("(required = false, nested)").unwrap();
+        assert!(matches!(
+            attrs[..],
+            [BuilderAttribute::Required(_, false), BuilderAttribute::Nested(_)]
+        ));
+        assert!(!is_required(&attrs));
+    }
+
+    #[test]
+    fn required_rejects_anything_but_a_bool() {
+        let err = parse_body("(required = \"yes\")").err().unwrap();
+        assert_eq!(err.to_string(), "expected `true` or `false`");
+
+        assert!(parse_body("(required =)").is_err());
+    }
+}
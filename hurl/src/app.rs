@@ -52,6 +52,7 @@
     Create a validate method to check whether a cmd or url exists
 
     Add a helper (log_level) to turn the quiet and verbose settings into a string log level for use with the logging implementation
+    and log_filter which makes that into the filter the logger is set up with, e.g. hurl=debug for -vvvv
 
     DATA STRUCTURE FOR THE SUBCOMMAND
 
@@ -111,6 +112,31 @@
     Finally use the text value of the separator to get a separator type
     which we then use to construct the appropriate Parameter
 
+    SEPARATORS INSIDE JSON VALUES
+
+    Raw JSON values are full of characters that are also separators, e.g. key:={"a":1} or key:=[1,2,3]
+
+    This works because the earliest separator wins and only the first text segment containing a separator is looked at,
+    so as long as the key has no separator characters in it the := right after the key is found first,
+    and on a tie at the same position the longest separator wins which is why := beats :
+
+    Everything after the chosen separator, colons and equals signs included, ends up in the value untouched
+
+    The one thing that cannot be worked out automatically is a key that contains a separator character,
+    e.g. a:b:=1 is a header named a with the value b:=1
+    Escaping the character in the key with a backslash, a\:b:=1, skips it during the separator search,
+    and the backslash is dropped so the key is a:b
+
+    Because a header like that is almost always a mistake, parse_param prints a warning naming the escaped form
+    when the value of a header has a := or =@ in it, the job of escaped_header_key
+    A plain = is left alone as header values like Cookie:a=b or base64 padding have it all the time
+
+    The tests at the bottom of this file cover objects, arrays, nested colons, and escaped keys
+
+    The indexes in gather_escapes are byte offsets into the string,
+    so they are advanced by the UTF-8 length of each character rather than by one,
+    otherwise any non-ASCII character would cut the end of the parameter off or slice through a character
+
     ADDING CONFIGURATION TO THE APP
 
     Bringing in PathBuf for working with file system paths
@@ -150,17 +176,134 @@
 
     Sessions are unique based on this host value and the configured named
 
+    PRESETS
+
+    --save-preset takes a name and stores the request being made under that name,
+    and --preset takes a name and replays the stored request, see the presets module
+
+    Because a preset can supply the URL, validate accepts a preset in place of a URL or command
+
+    To be stored in a preset the Parameter enum derives Serialize and Deserialize,
+    and the Method enum gains name and from_name to go to and from the subcommand name
+
+    CHOOSING THE HTTP VERSION
+
+    The --http-version option takes one of 1.0, 1.1, or 2 and is parsed into the HttpVersion enum
+
+    The blocking client in reqwest 0.9 only speaks HTTP/1.1 by default
+    and can be told to use HTTP/2 with prior knowledge, i.e. without negotiating an upgrade
+
+    There is no way to make it send HTTP/1.0 requests,
+    so rather than silently sending HTTP/1.1 the parser rejects 1.0 with an error explaining why
+
+    The version actually used is printed in the first line of the response output
+
+    READING PARAMETERS FROM A FILE
+
+    --params-file takes a path to a file with one parameter per line,
+    written exactly as it would be on the command line, e.g. X-API-TOKEN:abc123 or foo:=[1,2,3]
+
+    Each line goes through the same parse_param as the command line arguments,
+    blank lines are skipped, and lines starting with # are comments
+
+    process_params_file merges the parameters from the file with the ones from the command line
+    A parameter on the command line wins over one from the file of the same type with the same key,
+    so the file can hold the defaults and the command line can change a few of them
+
+    The parameters from the file go first and the command line ones after,
+    which keeps the order of each and matters for things like repeated headers
+
+    PATH PARAMETERS
+
+    process_path_params fills in placeholders like :id in the URL from the data parameters,
+    using substitute_path_params in the client module, and keeps the parameters that weren't used
+
+    With more than one URL each of them is filled in from the same parameters,
+    and a parameter is kept for the body or query only if none of the URLs used it
+
+    MORE THAN ONE URL
+
+    Without a method subcommand, hurl example.com/a example.com/b makes a request to each URL in turn
+
+    structopt can only hand us the arguments after the first URL as a list of strings, args,
+    because a URL like example.com:8080/b would happily parse as a header called example.com
+    So args are split up in validate by split_args: anything looks_like_url accepts is another URL
+    and everything else goes through parse_param into parameters, in the order they were given
+
+    looks_like_url accepts:
+        - anything with a transport, e.g. http://example.com/b,
+          as long as there is no parameter separator before the ://
+        - the localhost shortcuts and bare paths, i.e. anything starting with : or /
+        - a host followed by nothing, a path, a query, or a numeric port, e.g. example.com/b or api.example.com:8080
+          where the host is localhost or a dotted name made of letters, digits, dots, and dashes
+
+    So X-Count:5 is still a header as X-Count has no dot, and foo.bar:baz is a header as baz is not a port
+    A parameter always has its separator right after the key which never looks like a host with a path or port after it
+
+    The value of a parameter can be a URL though, e.g. redirect_uri==https://app.example.com/cb or Origin:http://localhost:3000,
+    so the text before :// is checked with has_separator, the same separators parse_param looks for,
+    and only an argument whose scheme has none of them is taken as a URL
+
+    The extra URLs are in extra_urls, every URL gets the same parameters,
+    and the same method which is POST if there is any data and GET otherwise
+
+    Presets, --interactive, and --benchmark only deal with the first URL
+
+    CUSTOM METHODS
+
+    The subcommands only cover the common methods, --method takes any other name, e.g. --method PURGE
+    and is used instead of inferring GET or POST, request_method gives whichever of the two applies
+
+    parse_method hands the name to reqwest::Method::from_bytes which only accepts a valid HTTP token,
+    so a name with a space, a colon, or the like is refused when the arguments are parsed
+    The name is used exactly as given, methods are case sensitive so purge and PURGE are two different methods
+
+    --method stands in for a subcommand so the two can't be given together,
+    and a preset saved with --method stores the name like the name of a subcommand
+
+    OPENAPI OPERATIONS
+
+    --from-openapi with --operation fills in the request from an OpenAPI spec, see the openapi module,
+    and like a preset it can stand in for the URL in validate
+
+    process_openapi then works like process_params_file with the spec as the file:
+    the example body becomes parameters which the command line ones replace by type and key
+
+    The method and URL only come from the spec when no URL was given
+    As the URL is the first positional argument a parameter like id=5 ends up in url,
+    so when looks_like_url says url isn't one it is moved to the front of the parameters instead
+    A method subcommand always has its own URL, so then only the body comes from the spec
+
+    SHELL COMPLETIONS
+
+    --generate-completions bash prints a completion script for the shell and exits without making a request,
+    e.g. hurl --generate-completions bash > /etc/bash_completion.d/hurl
+
+    clap writes the script from the same definition structopt derives for App, so it never falls behind the flags
+    completion_script takes any shell clap knows, bash, zsh, fish, powershell, or elvish,
+    and main checks for it before validate so no URL is needed
+
+    The flag is hidden from --help as it is only needed once, when installing hurl
+
 ***/
 
 use log::{debug, trace};
 use std::convert::TryFrom;
 use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use structopt::clap::Shell;
 use structopt::StructOpt;
 
+use crate::client;
 use crate::config;
+use crate::openapi;
 use crate::errors::{Error, HurlResult};
+use crate::report::Format;
 use crate::session::make_safe_pathname;
 
+const DEFAULT_JSON_INDENT: usize = 2;
+const PARAM_SEPARATORS: [&str; 7] = [":=@", "=@", "==", ":=", "@", "=", ":"];
+
 #[derive(StructOpt, Debug)]
 #[structopt(rename_all = "screaming_snake_case")]
 pub enum Method {
@@ -172,7 +315,7 @@ pub enum Method {
     DELETE(MethodData),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Parameter {
     // :
     Header {
@@ -228,6 +371,12 @@ enum Separator {
     Snail,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpVersion {
+    Http11,
+    Http2,
+}
+
 #[derive(StructOpt, Debug)]
 pub struct MethodData {
     /// The URL to request
@@ -248,10 +397,23 @@ pub struct App {
     #[structopt(short, long)]
     pub quiet: bool,
 
+    /// Only print output when the request fails
+    /// 
+    /// A 2xx or 3xx response, e.g. 304 Not Modified, produces no output at all,
+    /// while a 4xx or 5xx prints the response and exits non-zero.
+    #[structopt(long)]
+    pub silent_success: bool,
+
     /// Verbose mode (-v, -vv, -vvv, etc.)
     #[structopt(short, long, parse(from_occurrences))]
     pub verbose: u8,
 
+    /// Write the log messages to stderr as one JSON object per line
+    /// 
+    /// The level still comes from --verbose, without it nothing is logged.
+    #[structopt(long)]
+    pub log_stderr_json: bool,
+
     /// Form mode
     #[structopt(short, long)]
     pub form: bool,
@@ -280,6 +442,248 @@ pub struct App {
     #[structopt(short, long)]
     pub secure: bool,
 
+    /// Base URL for URLs given as just a path
+    /// 
+    /// If the URL starts with a slash, i.e. /users/1
+    /// it is appended to this, e.g. https://api.example.com/users/1.
+    /// Usually set with base_url in the configuration file.
+    #[structopt(long)]
+    pub base_url: Option<String>,
+
+    /// HTTP version
+    /// 
+    /// One of 1.1 or 2. HTTP/2 is used with prior knowledge
+    /// so the server must support it without an upgrade.
+    /// HTTP/1.0 is not supported by the underlying client.
+    #[structopt(long, parse(try_from_str = parse_http_version))]
+    pub http_version: Option<HttpVersion>,
+
+    /// Disable the localhost shortcut
+    /// 
+    /// By default a URL starting with a colon, e.g. :8080/foo,
+    /// is expanded to http://localhost:8080/foo.
+    /// If this flag is set the URL is parsed as given instead.
+    #[structopt(long)]
+    pub no_localhost_shortcut: bool,
+
+    /// Append query parameters to the URL without encoding them
+    /// 
+    /// Use this when the values are already percent-encoded.
+    /// Nothing is escaped for you, so reserved characters like & or #
+    /// in a value will change the meaning of the URL.
+    #[structopt(long)]
+    pub raw_query: bool,
+
+    /// Print the cookies set by the response in their own section
+    /// 
+    /// This is always on when --verbose is given.
+    #[structopt(long)]
+    pub show_cookies: bool,
+
+    /// The maximum number of bytes of the response body to read
+    /// 
+    /// The request fails once the body grows past this many bytes,
+    /// rather than reading the whole thing into memory.
+    #[structopt(long)]
+    pub max_response_size: Option<u64>,
+
+    /// Print the equivalent curl command before sending the request
+    #[structopt(long)]
+    pub print_curl: bool,
+
+    /// Write the request to this file in the .http format for replaying it in an editor
+    /// 
+    /// The status of the response is added at the end as a comment.
+    #[structopt(long, parse(from_os_str))]
+    pub save_http: Option<PathBuf>,
+
+    /// The maximum number of seconds the whole request may take
+    /// 
+    /// This includes connecting, following redirects, and receiving the response.
+    #[structopt(long)]
+    pub max_time: Option<u64>,
+
+    /// Send every request through this proxy, e.g. http://127.0.0.1:3128
+    /// 
+    /// Used for both http and https URLs. Takes precedence over --use-system-proxy.
+    #[structopt(long)]
+    pub proxy: Option<String>,
+
+    /// Use the proxy configured on the system, e.g. in the HTTP_PROXY and HTTPS_PROXY environment variables
+    #[structopt(long)]
+    pub use_system_proxy: bool,
+
+    /// Retry a request answered with 429 Too Many Requests up to this many times
+    /// 
+    /// Each retry waits as long as the Retry-After header of the response says,
+    /// or a second when there is no such header.
+    #[structopt(long, default_value = "0")]
+    pub retries: u32,
+
+    /// Send this value as the Host header instead of the host from the URL
+    /// 
+    /// The connection is still made to the host in the URL.
+    #[structopt(long)]
+    pub host_header: Option<String>,
+
+    /// Send the request this many times and report latency statistics
+    /// 
+    /// The responses are not printed, only the timings and a count of each status code.
+    #[structopt(long)]
+    pub benchmark: Option<u32>,
+
+    /// Read additional parameters from this file, one per line
+    /// 
+    /// Each line uses the same syntax as the parameters on the command line.
+    /// Lines starting with # are ignored, and a parameter given on the command line
+    /// replaces one from the file with the same type and key.
+    #[structopt(long, parse(from_os_str))]
+    pub params_file: Option<PathBuf>,
+
+    /// Append a line of JSON describing each request to this file
+    /// 
+    /// Each line has the timestamp, URL, method, status, latency in milliseconds,
+    /// and the error if the request failed.
+    #[structopt(long, parse(from_os_str))]
+    pub log_file: Option<PathBuf>,
+
+    /// Don't show the upload progress for file uploads
+    /// 
+    /// Progress is only ever shown on a terminal, and is written to stderr.
+    #[structopt(long)]
+    pub no_progress: bool,
+
+    /// Ask before sending the request and prompt for parameters given as ?
+    /// 
+    /// e.g. password=? asks for the password.
+    /// This is ignored when stdin is not a terminal.
+    #[structopt(long)]
+    pub interactive: bool,
+
+    /// Write the response headers to this file as a JSON object
+    /// 
+    /// Each header name maps to its value, or to an array of values
+    /// when the response has the same header more than once.
+    #[structopt(long, parse(from_os_str))]
+    pub dump_headers: Option<PathBuf>,
+
+    /// Only print the status and headers for 4xx and 5xx responses
+    #[structopt(long)]
+    pub no_body_on_error: bool,
+
+    /// Print at most this many bytes of the body for 4xx and 5xx responses
+    #[structopt(long)]
+    pub error_body_limit: Option<usize>,
+
+    /// Send this as the Accept header, e.g. application/json
+    /// 
+    /// An Accept:... parameter takes precedence over this.
+    #[structopt(long)]
+    pub accept: Option<String>,
+
+    /// Ask for a compressed response and decompress it before printing
+    /// 
+    /// Sends Accept-Encoding: gzip, deflate unless an Accept-Encoding:... parameter is given,
+    /// and reports the size on the wire next to the decoded size.
+    #[structopt(long)]
+    pub compressed: bool,
+
+    /// Print the addresses the host resolves to before making the request
+    /// 
+    /// Also shows the transport and port being used and where the port came from.
+    /// This is always on when --verbose is given.
+    #[structopt(long)]
+    pub trace_connection: bool,
+
+    /// Send the YAML document in this file as the JSON body
+    /// 
+    /// The whole body comes from the file, so it can't be combined
+    /// with data parameters or --form.
+    #[structopt(long, parse(from_os_str))]
+    pub body_yaml: Option<PathBuf>,
+
+    /// Print a JSON array of objects as a table
+    /// 
+    /// Each object is a row and each key a column.
+    /// Any other response is printed as usual.
+    #[structopt(long)]
+    pub table: bool,
+
+    /// Only treat the body as JSON when the Content-Type says it is
+    /// 
+    /// By default a body that looks like JSON is pretty printed
+    /// whatever the Content-Type, e.g. JSON sent as text/plain.
+    #[structopt(long)]
+    pub strict_content_type: bool,
+
+    /// The number of spaces to indent JSON bodies by, 2 if not given
+    /// 
+    /// 0 prints the body on a single line, the same as --compact.
+    #[structopt(long)]
+    pub indent: Option<usize>,
+
+    /// Print JSON bodies on a single line
+    #[structopt(long, conflicts_with = "indent")]
+    pub compact: bool,
+
+    /// Print only the value at this path in a JSON body, e.g. data.items.0.name
+    /// 
+    /// A string is printed without its quotes. Exits non-zero when there is nothing at the path.
+    #[structopt(long, conflicts_with_all = &["format", "table", "output-dir", "diff"])]
+    pub jq_path: Option<String>,
+
+    /// Exit non-zero for 4xx and 5xx responses after printing them in full
+    /// 
+    /// Unlike --silent-success a successful response is still printed.
+    #[structopt(long, conflicts_with_all = &["no-body-on-error", "error-body-limit"])]
+    pub fail_with_body: bool,
+
+    /// Print a one line summary of each request to stderr
+    /// 
+    /// e.g. GET https://example.com/ -> 200 OK (123ms, 4.5 KB).
+    /// This is printed whatever the verbosity.
+    #[structopt(long)]
+    pub summary: bool,
+
+    /// Don't print the summary line even if the config file asks for it
+    #[structopt(long, conflicts_with = "summary")]
+    pub no_summary: bool,
+
+    /// Print a diff of two response bodies instead of the responses
+    /// 
+    /// With two URLs each of them is requested once,
+    /// with a single URL it is requested twice.
+    #[structopt(long)]
+    pub diff: bool,
+
+    /// Save each response body to a file in this directory
+    /// 
+    /// The file is named after the path of the URL, e.g. users_1 for example.com/users/1,
+    /// with a number added when that name is already taken.
+    #[structopt(long, parse(from_os_str))]
+    pub output_dir: Option<PathBuf>,
+
+    /// Print the response as a single json or toml document
+    /// 
+    /// The document has the status, version, headers, and body,
+    /// with a JSON body included as data rather than as text.
+    #[structopt(long, possible_values = &["json", "toml"])]
+    pub format: Option<Format>,
+
+    /// Cache responses in the session and make conditional requests
+    /// 
+    /// A response with an ETag or Last-Modified header is remembered for its URL,
+    /// and the next request sends If-None-Match or If-Modified-Since.
+    /// A 304 Not Modified answer shows the cached body.
+    #[structopt(long, requires = "session")]
+    pub cache: bool,
+
+    /// Use this method instead of a method subcommand, e.g. PURGE or LINK
+    /// 
+    /// Any valid HTTP method name is accepted and sent as it is written.
+    #[structopt(long, parse(try_from_str = parse_method))]
+    pub method: Option<reqwest::Method>,
+
     /// The HTTP Method to use, one of:
     /// HEAD, GET, POST, PUT, PATCH, DELETE.
     #[structopt(subcommand)]
@@ -291,6 +695,9 @@ pub struct App {
 
     /// The parameters for the request if a method subcommand is not specified
     /// 
+    /// Any further URLs can be given here too, e.g. example.com/a example.com/b,
+    /// and each of them is requested in turn with the same parameters.
+    /// 
     /// There are seven types of parameters that can be added to a command-line.
     /// Each type of parameter is distinguished by the unique separator between
     /// the key and value
@@ -322,9 +729,17 @@ pub struct App {
     /// Raw JSON data from file -- key:=@filename
     /// 
     ///   e.g. foo:=@bar.json becomes {"foo": {"bar": "this is from bar.json"}}
-    #[structopt(parse(try_from_str = parse_param))]
+    #[structopt(name = "PARAMETERS")]
+    pub args: Vec<String>,
+
+    /// The parameters parsed out of args by validate
+    #[structopt(skip)]
     pub parameters: Vec<Parameter>,
 
+    /// The URLs after the first one, split out of args by validate
+    #[structopt(skip)]
+    pub extra_urls: Vec<String>,
+
     /// Configuration file
     /// 
     /// A TOML file which is stored by default at HOME/.config/hurl/config
@@ -336,6 +751,7 @@ pub struct App {
     /// auth: string
     /// token: string
     /// secure: bool
+    /// base_url: string
     /// 
     /// Each option has the same meaning as the corresponding configuration option with the same name.
     /// The verbose setting is a number from 0 - meaning no logging - to 5 - meaning maximal log output
@@ -354,16 +770,87 @@ pub struct App {
     /// but do not modify what is stored.
     #[structopt(long)]
     pub read_only: bool,
+
+    /// Add headers to the session alongside any stored values for the same key
+    /// 
+    /// By default a header replaces the value stored in the session.
+    /// With this flag every value is kept and sent as its own header line.
+    #[structopt(long)]
+    pub append_headers: bool,
+
+    /// Save this request as a named preset
+    /// 
+    /// The method, URL, parameters, and authentication are stored
+    /// so the same request can be made again with --preset.
+    #[structopt(long)]
+    pub save_preset: Option<String>,
+
+    /// Make the request stored in the named preset
+    /// 
+    /// A method or URL given on the command line takes precedence over the preset.
+    #[structopt(long)]
+    pub preset: Option<String>,
+
+    /// Fill in the request from an operation in this OpenAPI spec, JSON or YAML
+    /// 
+    /// The operation is picked with --operation. Its method, path, and the example
+    /// of its JSON request body are used, and anything given on the command line wins.
+    #[structopt(long, parse(from_os_str), requires = "operation")]
+    pub from_openapi: Option<PathBuf>,
+
+    /// The operationId of the operation to use from --from-openapi
+    #[structopt(long, requires = "from-openapi")]
+    pub operation: Option<String>,
+
+    /// Print a completion script for this shell and exit
+    #[structopt(long, hidden = true, possible_values = &Shell::variants(), case_insensitive = true)]
+    pub generate_completions: Option<Shell>,
 }
 
 impl App {
     pub fn validate(&mut self) -> HurlResult<()> {
-        if self.cmd.is_none() && self.url.is_none() {
+        let (urls, parameters) = split_args(&self.args)?;
+        self.extra_urls = urls;
+        self.parameters = parameters;
+
+        if self.cmd.is_none() && self.url.is_none() && self.preset.is_none() && self.from_openapi.is_none() {
             return Err(Error::MissingUrlAndCommand);
         }
+
+        if let (Some(method), Some(cmd)) = (&self.method, &self.cmd) {
+            return Err(Error::ConflictingMethod(method.to_string(), cmd.name()));
+        }
+
+        if self.diff && self.extra_urls.len() > 1 {
+            return Err(Error::TooManyUrlsToDiff(self.extra_urls.len() + 1));
+        }
+
+        if self.body_yaml.is_some() {
+            let parameters = match &self.cmd {
+                Some(cmd) => &cmd.data().parameters,
+                None => &self.parameters,
+            };
+
+            if self.form {
+                return Err(Error::ConflictingBody("--form"));
+            }
+
+            if parameters.iter().any(|p| p.is_data()) {
+                return Err(Error::ConflictingBody("data parameters"));
+            }
+        }
+
         Ok(())
     }
 
+    pub fn request_method(&self) -> reqwest::Method {
+        match &self.method {
+            Some(method) => method.clone(),
+            None if self.parameters.iter().any(|p| p.is_data()) => reqwest::Method::POST,
+            None => reqwest::Method::GET,
+        }
+    }
+
     pub fn log_level(&self) -> Option<&'static str> {
         if self.quiet || self.verbose <= 0 {
             return None;
@@ -378,6 +865,22 @@ impl App {
         }
     }
 
+    pub fn json_indent(&self) -> usize {
+        if self.compact {
+            0
+        } else {
+            self.indent.unwrap_or(DEFAULT_JSON_INDENT)
+        }
+    }
+
+    pub fn show_summary(&self) -> bool {
+        self.summary && !self.no_summary
+    }
+
+    pub fn log_filter(&self) -> Option<String> {
+        self.log_level().map(|level| format!("hurl={}", level))
+    }
+
     pub fn process_config_file(&mut self) {
         let config_path = config::config_file(self);
         let config_opt = config::read_config_file(config_path);
@@ -408,7 +911,113 @@ impl App {
             if self.token.is_none() {
                 self.token = config.token.take();
             }
+
+            if self.base_url.is_none() {
+                self.base_url = config.base_url.take();
+            }
+
+            if self.proxy.is_none() {
+                self.proxy = config.proxy.take();
+            }
+
+            if !self.use_system_proxy {
+                if let Some(u) = config.use_system_proxy {
+                    self.use_system_proxy = u;
+                }
+            }
+
+            if !self.summary && !self.no_summary {
+                if let Some(summary) = config.summary {
+                    self.summary = summary;
+                }
+            }
+        }
+    }
+
+    pub fn process_params_file(&mut self) -> HurlResult<()> {
+        let path = match &self.params_file {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let contents = std::fs::read_to_string(path)?;
+        let file_params = parse_params_file(&contents)?;
+        debug!("Read {} parameters from {:?}", file_params.len(), path);
+
+        let parameters = match &mut self.cmd {
+            Some(cmd) => &mut cmd.data_mut().parameters,
+            None => &mut self.parameters,
+        };
+        let cli_params = std::mem::replace(parameters, Vec::new());
+        *parameters = merge_parameters(file_params, cli_params);
+
+        Ok(())
+    }
+
+    pub fn process_openapi(&mut self) -> HurlResult<()> {
+        let (path, operation_id) = match (&self.from_openapi, &self.operation) {
+            (Some(path), Some(operation_id)) => (path, operation_id),
+            _ => return Ok(()),
+        };
+
+        let spec = std::fs::read_to_string(path)?;
+        let request = openapi::find_operation(&spec, operation_id)?;
+        debug!("Using {} {} from the OpenAPI spec", request.method, request.url);
+
+        if let Some(cmd) = &mut self.cmd {
+            let data = cmd.data_mut();
+            let cli_params = std::mem::replace(&mut data.parameters, Vec::new());
+            data.parameters = merge_parameters(request.parameters, cli_params);
+            return Ok(());
+        }
+
+        if let Some(url) = self.url.take() {
+            if looks_like_url(&url) {
+                self.url = Some(url);
+            } else {
+                trace!("Treating {} as a parameter as the URL comes from the spec", url);
+                self.parameters.insert(0, parse_param(&url)?);
+            }
         }
+
+        if self.url.is_none() {
+            self.url = Some(request.url);
+
+            if self.method.is_none() {
+                self.method = Some(parse_method(&request.method)?);
+            }
+        }
+
+        let cli_params = std::mem::replace(&mut self.parameters, Vec::new());
+        self.parameters = merge_parameters(request.parameters, cli_params);
+
+        Ok(())
+    }
+
+    pub fn process_path_params(&mut self) -> HurlResult<()> {
+        let (url, parameters) = match &mut self.cmd {
+            Some(cmd) => {
+                let data = cmd.data_mut();
+                (&mut data.url, &mut data.parameters)
+            }
+            None => match &mut self.url {
+                Some(url) => (url, &mut self.parameters),
+                None => return Ok(()),
+            },
+        };
+
+        let (new_url, mut remaining) = client::substitute_path_params(url, parameters.clone())?;
+        *url = new_url;
+
+        for extra_url in self.extra_urls.iter_mut() {
+            let (new_url, unused) = client::substitute_path_params(extra_url, parameters.clone())?;
+            *extra_url = new_url;
+            remaining.retain(|p| unused.contains(p));
+        }
+
+        *parameters = remaining;
+
+        Ok(())
     }
 
     pub fn host(&self) -> String {
@@ -422,6 +1031,12 @@ impl App {
     }
 }
 
+pub fn completion_script(shell: Shell) -> Vec<u8> {
+    let mut script = Vec::new();
+    App::clap().gen_completions_to("hurl", shell, &mut script);
+    script
+}
+
 impl Method {
     pub fn data(&self) -> &MethodData {
         use Method::*;
@@ -435,6 +1050,50 @@ impl Method {
             DELETE(x) => x,
         }
     }
+
+    pub fn data_mut(&mut self) -> &mut MethodData {
+        use Method::*;
+
+        match self {
+            HEAD(x) => x,
+            GET(x) => x,
+            PUT(x) => x,
+            POST(x) => x,
+            PATCH(x) => x,
+            DELETE(x) => x,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        use Method::*;
+
+        match self {
+            HEAD(_) => "HEAD",
+            GET(_) => "GET",
+            PUT(_) => "PUT",
+            POST(_) => "POST",
+            PATCH(_) => "PATCH",
+            DELETE(_) => "DELETE",
+        }
+    }
+
+    pub fn is_name(name: &str) -> bool {
+        ["HEAD", "GET", "PUT", "POST", "PATCH", "DELETE"].contains(&name)
+    }
+
+    pub fn from_name(name: &str, data: MethodData) -> HurlResult<Method> {
+        use Method::*;
+
+        match name {
+            "HEAD" => Ok(HEAD(data)),
+            "GET" => Ok(GET(data)),
+            "PUT" => Ok(PUT(data)),
+            "POST" => Ok(POST(data)),
+            "PATCH" => Ok(PATCH(data)),
+            "DELETE" => Ok(DELETE(data)),
+            _ => Err(Error::UnknownMethod(name.to_owned())),
+        }
+    }
 }
 
 impl From<&Method> for reqwest::Method {
@@ -482,6 +1141,99 @@ impl Parameter {
             _ => true,
         }
     }
+
+    pub fn key(&self) -> &str {
+        match self {
+            Parameter::Header { key, .. } => key,
+            Parameter::Data { key, .. } => key,
+            Parameter::RawJsonData { key, .. } => key,
+            Parameter::Query { key, .. } => key,
+            Parameter::FormFile { key, .. } => key,
+            Parameter::DataFile { key, .. } => key,
+            Parameter::RawJsonDataFile { key, .. } => key,
+        }
+    }
+
+    fn same_kind_and_key(&self, other: &Parameter) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other) && self.key() == other.key()
+    }
+}
+
+fn split_args(args: &[String]) -> HurlResult<(Vec<String>, Vec<Parameter>)> {
+    let mut urls = Vec::new();
+    let mut parameters = Vec::new();
+
+    for arg in args {
+        if looks_like_url(arg) {
+            trace!("Treating {} as another URL", arg);
+            urls.push(arg.to_owned());
+        } else {
+            parameters.push(parse_param(arg)?);
+        }
+    }
+
+    Ok((urls, parameters))
+}
+
+fn looks_like_url(arg: &str) -> bool {
+    if let Some(scheme_end) = arg.find("://") {
+        return !has_separator(&arg[..scheme_end]);
+    }
+
+    if arg.starts_with(':') || arg.starts_with('/') {
+        return true;
+    }
+
+    let host_end = arg.find(|c| c == '/' || c == ':' || c == '?' || c == '#').unwrap_or(arg.len());
+    let (host, rest) = arg.split_at(host_end);
+
+    let is_host = host == "localhost"
+        || (host.contains('.')
+            && !host.starts_with('.')
+            && host.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-'));
+
+    if !is_host {
+        return false;
+    }
+
+    match rest.chars().next() {
+        None | Some('/') | Some('?') | Some('#') => true,
+        Some(':') => {
+            let port = rest[1..].split('/').next().unwrap_or("");
+            !port.is_empty() && port.chars().all(|c| c.is_ascii_digit())
+        }
+        Some(_) => false,
+    }
+}
+
+fn parse_params_file(contents: &str) -> HurlResult<Vec<Parameter>> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_param)
+        .collect()
+}
+
+fn merge_parameters(file_params: Vec<Parameter>, cli_params: Vec<Parameter>) -> Vec<Parameter> {
+    let mut merged: Vec<Parameter> = file_params
+        .into_iter()
+        .filter(|file_param| !cli_params.iter().any(|p| p.same_kind_and_key(file_param)))
+        .collect();
+    merged.extend(cli_params);
+    merged
+}
+
+fn parse_http_version(src: &str) -> HurlResult<HttpVersion> {
+    match src {
+        "1.1" => Ok(HttpVersion::Http11),
+        "2" | "2.0" => Ok(HttpVersion::Http2),
+        _ => Err(Error::UnsupportedHttpVersion(src.to_owned())),
+    }
+}
+
+pub fn parse_method(src: &str) -> HurlResult<reqwest::Method> {
+    reqwest::Method::from_bytes(src.as_bytes()).map_err(|_| Error::InvalidMethod(src.to_owned()))
 }
 
 fn gather_escapes<'a>(src: &'a str) -> Vec<Token<'a>> {
@@ -503,7 +1255,7 @@ fn gather_escapes<'a>(src: &'a str) -> Vec<Token<'a>> {
         let c = a.unwrap();
 
         if c != '\\' {
-            end += 1;
+            end += c.len_utf8();
             continue;
         }
 
@@ -525,14 +1277,37 @@ fn gather_escapes<'a>(src: &'a str) -> Vec<Token<'a>> {
                 end += 2;
                 start = end;
             }
-            _ => end += 2,
+            _ => end += 1 + c.len_utf8(),
+        }
+    }
+}
+
+fn has_separator(src: &str) -> bool {
+    gather_escapes(src).iter().any(|token| match token {
+        Token::Text(s) => PARAM_SEPARATORS.iter().any(|sep| s.contains(sep)),
+        Token::Escape(_) => false,
+    })
+}
+
+fn escaped_header_key(key: &str, value: &str) -> Option<String> {
+    let n = [":=", "=@"].iter().filter_map(|sep| value.find(sep)).min()?;
+    let full_key = format!("{}:{}", key, &value[..n]);
+
+    let mut escaped = String::new();
+    for c in full_key.chars() {
+        if let '\\' | '=' | '@' | ':' = c {
+            escaped.push('\\');
         }
+        escaped.push(c);
     }
+    escaped.push_str(&value[n..]);
+
+    Some(escaped)
 }
 
 fn parse_param(src: &str) -> HurlResult<Parameter> {
     debug!("Parsing: {}", src);
-    let separators = [":=@", "=@", "==", ":=", "@", "=", ":"];
+    let separators = PARAM_SEPARATORS;
     let tokens = gather_escapes(src);
 
     let mut found = Vec::new();
@@ -571,10 +1346,7 @@ fn parse_param(src: &str) -> HurlResult<Parameter> {
         if i < idx {
             match token {
                 Token::Text(s) => key.push_str(&s),
-                Token::Escape(c) => {
-                    key.push('\\');
-                    key.push(*c);
-                }
+                Token::Escape(c) => key.push(*c),
             }
         } else if i > idx {
             match token {
@@ -608,10 +1380,18 @@ fn parse_param(src: &str) -> HurlResult<Parameter> {
                 key,
                 value,
             }),
-            Separator::Colon => Ok(Parameter::Header {
-                key,
-                value
-            }),
+            Separator::Colon => {
+                if let Some(escaped) = escaped_header_key(&key, &value) {
+                    eprintln!(
+                        "warning: {} is a header named {}, write {} if the colon is part of the key",
+                        src, key, escaped
+                    );
+                }
+                Ok(Parameter::Header {
+                    key,
+                    value
+                })
+            }
             Separator::ColonEqual => Ok(Parameter::RawJsonData {
                 key,
                 value
@@ -632,4 +1412,230 @@ fn parse_param(src: &str) -> HurlResult<Parameter> {
     } else {
         unreachable!();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw_json(key: &str, value: &str) -> Parameter {
+        Parameter::RawJsonData {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        }
+    }
+
+    #[test]
+    fn parse_param_raw_json_object() {
+        let param = parse_param(r#"key:={"a":1}"#).unwrap();
+
+        assert_eq!(param, raw_json("key", r#"{"a":1}"#));
+    }
+
+    #[test]
+    fn parse_param_raw_json_array() {
+        let param = parse_param("key:=[1,2,3]").unwrap();
+
+        assert_eq!(param, raw_json("key", "[1,2,3]"));
+    }
+
+    #[test]
+    fn parse_param_raw_json_nested_colons() {
+        let src = r#"key:={"a":{"b":"c:d","e":"f==g"}}"#;
+        let param = parse_param(src).unwrap();
+
+        assert_eq!(param, raw_json("key", r#"{"a":{"b":"c:d","e":"f==g"}}"#));
+    }
+
+    #[test]
+    fn parse_param_header_value_with_colons() {
+        let param = parse_param("X-Time:12:30:00").unwrap();
+
+        assert_eq!(
+            param,
+            Parameter::Header {
+                key: "X-Time".to_owned(),
+                value: "12:30:00".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_param_key_with_separator_is_a_header() {
+        let param = parse_param("a:b:=1").unwrap();
+
+        assert_eq!(
+            param,
+            Parameter::Header {
+                key: "a".to_owned(),
+                value: "b:=1".to_owned(),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_param_escaped_separator_in_key() {
+        let param = parse_param(r"a\:b:=1").unwrap();
+
+        assert_eq!(param, raw_json("a:b", "1"));
+    }
+
+    #[test]
+    fn parse_param_names_the_escape_for_a_header_that_looks_like_data() {
+        assert_eq!(
+            parse_param("a:b:=1").unwrap(),
+            Parameter::Header { key: "a".to_owned(), value: "b:=1".to_owned() }
+        );
+        assert_eq!(escaped_header_key("a", "b:=1").as_deref(), Some(r"a\:b:=1"));
+        assert_eq!(escaped_header_key("a", "b=@file.json").as_deref(), Some(r"a\:b=@file.json"));
+        assert_eq!(escaped_header_key("Authorization", "Basic dXNlcg=="), None);
+        assert_eq!(escaped_header_key("Cookie", "a=b"), None);
+    }
+
+    #[test]
+    fn parse_param_non_ascii_value() {
+        let param = parse_param(r#"name:="José""#).unwrap();
+
+        assert_eq!(param, raw_json("name", r#""José""#));
+    }
+
+    #[test]
+    fn parse_params_file_with_several_types() {
+        let contents = "\
+# defaults for the staging api
+X-API-TOKEN:abc123
+
+name=hurl
+tags:=[1,2,3]
+page==2
+";
+        let params = parse_params_file(contents).unwrap();
+
+        assert_eq!(
+            params,
+            vec![
+                Parameter::Header {
+                    key: "X-API-TOKEN".to_owned(),
+                    value: "abc123".to_owned(),
+                },
+                Parameter::Data {
+                    key: "name".to_owned(),
+                    value: "hurl".to_owned(),
+                },
+                raw_json("tags", "[1,2,3]"),
+                Parameter::Query {
+                    key: "page".to_owned(),
+                    value: "2".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_params_file_reports_bad_lines() {
+        assert!(parse_params_file("name=hurl\nnot a parameter\n").is_err());
+    }
+
+    #[test]
+    fn merge_parameters_command_line_wins() {
+        let file_params = parse_params_file("name=file\npage==1\nX-Mode:file\n").unwrap();
+        let cli_params = vec![parse_param("name=cli").unwrap(), parse_param("X-Mode==cli").unwrap()];
+
+        let merged = merge_parameters(file_params, cli_params);
+
+        assert_eq!(
+            merged,
+            vec![
+                parse_param("page==1").unwrap(),
+                parse_param("X-Mode:file").unwrap(),
+                parse_param("name=cli").unwrap(),
+                parse_param("X-Mode==cli").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn looks_like_url_tells_urls_from_parameters() {
+        for url in &["http://example.com/a", "example.com/b", "api.example.com:8080", "localhost:3000/x", ":3000/x", "/posts", "example.com?q=1"] {
+            assert!(looks_like_url(url), "{} should be a URL", url);
+        }
+
+        for param in &[
+            "name=hurl",
+            "page==2",
+            "X-Count:5",
+            "foo.bar:baz",
+            "file@data.json",
+            "age:=30",
+            "file.txt@path/to/file",
+            "redirect_uri==https://app.example.com/cb",
+            "Origin:http://localhost:3000",
+            "callback=https://x/y",
+        ] {
+            assert!(!looks_like_url(param), "{} should be a parameter", param);
+        }
+    }
+
+    #[test]
+    fn validate_splits_extra_urls_from_parameters() {
+        let mut app = App::from_iter(&["hurl", "example.com/a", "example.com/b", "page==2", "localhost:3000/c"]);
+        app.validate().unwrap();
+
+        assert_eq!(app.url.as_deref(), Some("example.com/a"));
+        assert_eq!(app.extra_urls, vec!["example.com/b", "localhost:3000/c"]);
+        assert_eq!(app.parameters, vec![parse_param("page==2").unwrap()]);
+    }
+
+    #[test]
+    fn path_params_are_kept_unless_a_url_uses_them() {
+        let mut app = App::from_iter(&["hurl", "example.com/posts/:id", "example.com/users/:id", "page==2"]);
+        app.validate().unwrap();
+        app.parameters.push(parse_param("id=7").unwrap());
+        app.process_path_params().unwrap();
+
+        assert_eq!(app.url.as_deref(), Some("example.com/posts/7"));
+        assert_eq!(app.extra_urls, vec!["example.com/users/7"]);
+        assert_eq!(app.parameters, vec![parse_param("page==2").unwrap()]);
+    }
+
+    #[test]
+    fn method_accepts_any_token_and_rejects_the_rest() {
+        let mut app = App::from_iter(&["hurl", "--method", "LINK", "example.com", "a=1"]);
+        app.validate().unwrap();
+
+        assert_eq!(app.request_method().as_str(), "LINK");
+        assert!(App::from_iter_safe(&["hurl", "--method", "BAD METHOD", "example.com"]).is_err());
+        assert!(App::from_iter_safe(&["hurl", "--method", "", "example.com"]).is_err());
+    }
+
+    #[test]
+    fn method_conflicts_with_a_subcommand() {
+        let mut app = App::from_iter(&["hurl", "--method", "PURGE", "GET", "example.com"]);
+
+        assert!(matches!(app.validate(), Err(Error::ConflictingMethod(_, "GET"))));
+    }
+
+    #[test]
+    fn log_filter_follows_the_verbosity() {
+        let filter = |args: &[&str]| App::from_iter(args).log_filter();
+
+        assert_eq!(filter(&["hurl", "example.com"]), None);
+        assert_eq!(filter(&["hurl", "-v", "example.com"]).as_deref(), Some("hurl=error"));
+        assert_eq!(filter(&["hurl", "-vv", "example.com"]).as_deref(), Some("hurl=warn"));
+        assert_eq!(filter(&["hurl", "-vvv", "example.com"]).as_deref(), Some("hurl=info"));
+        assert_eq!(filter(&["hurl", "-vvvv", "example.com"]).as_deref(), Some("hurl=debug"));
+        assert_eq!(filter(&["hurl", "-vvvvv", "example.com"]).as_deref(), Some("hurl=trace"));
+        assert_eq!(filter(&["hurl", "-vvvvvvv", "example.com"]).as_deref(), Some("hurl=trace"));
+        assert_eq!(filter(&["hurl", "-q", "-vvv", "example.com"]), None);
+    }
+
+    #[test]
+    fn completion_script_for_bash_is_generated() {
+        let app = App::from_iter(&["hurl", "--generate-completions", "bash"]);
+        assert!(matches!(app.generate_completions, Some(Shell::Bash)));
+
+        let script = String::from_utf8(completion_script(Shell::Bash)).unwrap();
+        assert!(!script.is_empty());
+        assert!(script.contains("--generate-completions"));
+    }
+}
@@ -0,0 +1,142 @@
+/***
+ *
+ *
+ *
+    BENCHMARK MODULE
+
+    --benchmark <n> sends the same request n times one after another
+    and reports how long the requests took instead of printing the responses
+
+    RUNNING THE REQUESTS
+
+    The method, URL, and parameters are worked out the same way main does it,
+    a subcommand gives them directly and otherwise the method is --method or inferred from whether there is any data
+
+    Every request goes through the Client built once in main, so after the first request
+    the connection is normally reused and the timings don't include connecting again each time
+
+    Each request is timed with Instant, which is monotonic so it can't go backwards
+    if the system clock changes in the middle of a run
+
+    client::perform returns as soon as the headers are in, so the body is copied into io::sink
+    before the clock is stopped, otherwise a large response would look as fast as an empty one
+    Reading it to the end also lets the connection go back to the pool for the next request
+
+    The bodies are never printed, only the status code is kept,
+    and a request which fails outright, or whose body can't be read, is counted as an error rather than stopping the run
+
+    THE REPORT
+
+    Once all the requests are done the durations are sorted so the percentiles can be read off directly
+
+    percentile uses the nearest rank method: the p-th percentile of n sorted values
+    is the value at rank ceil(p / 100 * n), counting from 1
+    For the 10 values 1..=10 that makes p50 the 5th value and p90 the 9th
+
+    The mean is the total divided by the number of requests,
+    and the counts are kept in a BTreeMap so the status codes come out in order
+
+***/
+
+use crate::app::App;
+use crate::client;
+use crate::errors::HurlResult;
+use crate::session::Session;
+use reqwest::Client;
+use std::collections::BTreeMap;
+use std::io;
+use std::time::{Duration, Instant};
+
+pub fn run(app: &App, http_client: &Client, count: u32, session: &mut Option<Session>) -> HurlResult<()> {
+    let (method, url, parameters) = match &app.cmd {
+        Some(cmd) => {
+            let data = cmd.data();
+            (cmd.into(), data.url.clone(), data.parameters.clone())
+        }
+        None => (
+            app.request_method(),
+            app.url.clone().unwrap_or_default(),
+            app.parameters.clone(),
+        ),
+    };
+
+    let mut durations = Vec::new();
+    let mut statuses: BTreeMap<String, u32> = BTreeMap::new();
+
+    for _ in 0..count {
+        let start = Instant::now();
+        let result = client::perform(app, http_client, method.clone(), session, &url, &parameters)
+            .and_then(|mut resp| {
+                io::copy(&mut resp, &mut io::sink())?;
+                Ok(resp.status())
+            });
+        durations.push(start.elapsed());
+
+        let key = match result {
+            Ok(status) => status.as_u16().to_string(),
+            Err(_) => "error".to_owned(),
+        };
+
+        *statuses.entry(key).or_insert(0) += 1;
+    }
+
+    durations.sort();
+    print_report(&durations, &statuses);
+
+    Ok(())
+}
+
+fn print_report(sorted: &[Duration], statuses: &BTreeMap<String, u32>) {
+    if sorted.is_empty() {
+        println!("No requests were made");
+        return;
+    }
+
+    let total: Duration = sorted.iter().sum();
+
+    println!("Requests: {}", sorted.len());
+    println!("Min:  {:?}", sorted[0]);
+    println!("Max:  {:?}", sorted[sorted.len() - 1]);
+    println!("Mean: {:?}", total / sorted.len() as u32);
+    println!("p50:  {:?}", percentile(sorted, 50.0));
+    println!("p90:  {:?}", percentile(sorted, 90.0));
+    println!("p99:  {:?}", percentile(sorted, 99.0));
+
+    println!("Status codes:");
+    for (status, count) in statuses {
+        println!("  {}: {}", status, count);
+    }
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    let index = rank.max(1).min(sorted.len()) - 1;
+
+    sorted[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(values: &[u64]) -> Vec<Duration> {
+        values.iter().map(|&ms| Duration::from_millis(ms)).collect()
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let sorted = millis(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+
+        assert_eq!(percentile(&sorted, 50.0), Duration::from_millis(5));
+        assert_eq!(percentile(&sorted, 90.0), Duration::from_millis(9));
+        assert_eq!(percentile(&sorted, 99.0), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn percentile_of_single_value() {
+        let sorted = millis(&[42]);
+
+        assert_eq!(percentile(&sorted, 0.0), Duration::from_millis(42));
+        assert_eq!(percentile(&sorted, 99.0), Duration::from_millis(42));
+    }
+}
@@ -38,6 +38,152 @@
     The map_err(From::from) bit is so that
     if an error is returned from reqwest it can be turned into a custom error type
 
+    BUILDING THE CLIENT
+
+    The client itself is created by build_client which is the one place that turns options on the App
+    into settings on a reqwest ClientBuilder, rather than each option patching perform separately
+
+    New client level options only need another step in build_client
+
+    main calls build_client once and hands the same Client to every call of perform and perform_method,
+    so several URLs, the two requests of --diff, and every request of --benchmark share it
+    A reqwest Client keeps a pool of open connections, and with HTTP/1.1 keep-alive
+    a request to a host that was already talked to goes over the connection left open by the last one,
+    saving the TCP and TLS handshakes that building a new Client for each request would repeat
+
+    At the moment the options are the HTTP version requested with --http-version,
+    the overall time limit from --max-time, leaving decompression to hurl for --compressed,
+    and the proxy to send requests through
+
+    USING A PROXY
+
+    --proxy takes the URL of a proxy which is used for every request, whether the URL is http or https
+
+    --use-system-proxy asks reqwest to use the proxy configured on the system instead,
+    e.g. from the HTTP_PROXY and HTTPS_PROXY environment variables,
+    which reqwest 0.9 otherwise ignores
+
+    When both are given --proxy wins, and both can be set in the configuration file as proxy and use_system_proxy
+
+    A proxy URL that doesn't parse makes build_client fail, so hurl stops before sending anything
+
+    LIMITING THE TOTAL TIME
+
+    --max-time bounds the whole operation like curl's option of the same name
+
+    It is passed to the client as its timeout, and as reqwest follows redirects inside a single send
+    the redirects are covered by it too
+
+    perform also records a deadline when it starts, before any files are read,
+    and if the deadline has passed by the time the response comes back Error::ClientTimeout is returned
+    rather than the late response
+
+    Asking for HTTP/2 uses h2_prior_knowledge, and if the server does not speak HTTP/2
+    the resulting reqwest error flows through the usual From conversion into our Error
+
+    RETRYING WHEN RATE LIMITED
+
+    A server that gets too many requests answers 429 Too Many Requests,
+    usually with a Retry-After header saying how long to wait before trying again
+
+    With --retries n perform sends the request up to n more times while the answer is a 429,
+    sleeping for as long as retry_after says in between and printing how long it waits to stderr
+    Any other status, or an error, is returned straight away, and after the last retry the 429 itself is returned
+
+    Retry-After is either a number of seconds, e.g. Retry-After: 120,
+    or an HTTP date, e.g. Retry-After: Wed, 21 Oct 2015 07:28:00 GMT, which the httpdate crate parses
+    A date is turned into the time left until then, a date in the past meaning no wait at all,
+    and a 429 without a usable header waits DEFAULT_RETRY_AFTER
+
+    A RequestBuilder is used up by send, so each attempt sends a copy made with try_clone beforehand
+    A multipart body is read from the files as it is sent and can't be copied, so it is never retried
+
+    With --max-time a retry that would have to wait past the deadline isn't made and the 429 is returned instead
+
+    OVERRIDING THE HOST HEADER
+
+    reqwest fills in the Host header from the URL, which is what virtual hosting on the server uses
+    to decide which site the request is for
+
+    When connecting to a bare IP address behind a load balancer that is not what we want,
+    so --host-header sets the Host header explicitly after the other parameters
+    and the connection still goes to whatever the URL points at
+
+    The TLS server name (SNI) is still taken from the URL as reqwest has no way to set it separately,
+    so for https the certificate has to match the URL rather than the Host header
+
+    TRACING THE CONNECTION
+
+    With --trace-connection, or any level of --verbose, perform prints what connection::trace
+    has to say about the parsed URL to stderr before the request is sent,
+    i.e. the transport, the port, and every address the host resolves to
+
+    CHOOSING THE ACCEPT HEADER
+
+    --accept application/json is a shortcut for the parameter Accept:application/json
+
+    Adding a header to the builder appends rather than replaces,
+    so to let an explicit Accept parameter override the option,
+    accept_header only hands back the value of --accept when none of the parameters is an Accept header
+    handle_parameters adds it before going through the parameters, and curl_command does the same
+
+    ASKING FOR A COMPRESSED RESPONSE
+
+    --compressed works like it does in curl, handle_parameters adds Accept-Encoding: gzip, deflate
+    unless one of the parameters is an Accept-Encoding header, in which case that one is sent as it is
+
+    reqwest already asks for gzip on its own and decodes it while the body is read,
+    but then it also drops the Content-Encoding and Content-Length headers so the size on the wire is lost
+    So with --compressed build_client turns that off with gzip(false)
+    and main decodes the body itself after reading it, which is how it can report both sizes
+
+    br isn't in the list because there is nothing here that can decode it,
+    and asking for it would get back a body that can only be shown as binary
+
+    PRINTING THE EQUIVALENT CURL COMMAND
+
+    With --print-curl the request is also written out as a curl command line before it is sent,
+    which is handy for bug reports where the other person doesn't have hurl installed
+
+    curl_command builds the string from the same method, url, and parameters that go into the request builder:
+        - headers become -H 'key: value'
+        - query parameters are added to the url the same way the query method on the builder would,
+          unless --raw-query already put them there
+        - data is collected into JSON and passed with -d along with a Content-Type header,
+          or with --data-urlencode for each field when --form is set
+        - multipart forms use -F with an @ in front of file names
+        - basic auth becomes -u and a token becomes an Authorization header
+
+    Files given with =@ and :=@ are read just like in handle_parameters so the command is self contained
+
+    Every argument goes through shell_quote which wraps it in single quotes,
+    the only character that needs special care inside single quotes is the single quote itself
+    which is written as '\'' i.e. close the quote, an escaped quote, and open the quote again
+
+    Headers and cookies that come from a session are not part of the parameters so they are not included
+
+    SAVING THE REQUEST AS A .HTTP FILE
+
+    With --save-http <path> the request is also written to a file in the format editors like VS Code's REST Client
+    and the JetBrains HTTP client can send again: the method and URL on the first line, one header per line,
+    a blank line, and then the body
+
+    http_request builds it from the same method, url, and parameters as curl_command does:
+        - query parameters are added to the url unless --raw-query already put them there
+        - data is collected into pretty printed JSON with a Content-Type header,
+          or joined into an urlencoded body when --form is set
+        - multipart forms are written out part by part with a fixed boundary,
+          and a file part is a < filename line which those editors replace with the contents of the file
+        - basic auth and a token become Authorization headers,
+          the editors send a header as it is written so basic_auth_header base64 encodes user:password
+          the same way the basic_auth method of reqwest does
+
+    The file is written once the response is back, so http_response_comment can add the status at the end
+    as comment lines starting with #, which the editors skip when sending the request
+    A request that fails outright is still saved, just without the status
+
+    Headers and cookies that come from a session are not included, the same as for --print-curl
+
     PARSE HELPER
 
     The parse function will take in the raw URL string
@@ -53,6 +199,42 @@
             > In other words, if a request wants to be made to localhost:8080 can simply use :8080
 
     If neither of these two scenarios applies, then the given string is parsed directly
+
+    A BASE URL FOR PATHS
+
+    A URL that starts with a slash, e.g. /users/1, is only a path
+    When a base URL is set, with --base-url or base_url in the configuration file,
+    with_base_url puts the two together with exactly one slash between them before anything else is done,
+    so https://api.example.com/v2/ and /users/1 become https://api.example.com/v2/users/1
+
+    The joined URL then goes through the same parsing as any other,
+    so a base without a transport still gets http, or https with --secure
+
+    Without a base URL a path is parsed the same way it always was
+
+    PATH PARAMETERS
+
+    A URL can have placeholders for path segments, e.g. example.com/users/:id/posts/:post,
+    which substitute_path_params fills in from data parameters with the same key, e.g. id:=123 post=hello
+
+    A placeholder is a / followed by a colon and a name made of letters, digits, and underscores starting with a letter,
+    so :8080/users/:id still works as the localhost shortcut and a port like example.com:8080 is left alone
+    Only the part before any ? or # is looked at
+
+    Both key=value and key:=value can fill a placeholder,
+    a JSON string like id:='"abc"' is used without its quotes and anything else is used as written
+
+    The parameters that were used are taken out of the list so they don't also end up in the request body,
+    and a placeholder with no parameter is an Error::MissingPathParameter rather than a request to a literal :id
+
+    The value is put into the URL as is, Url::parse escapes things like spaces
+    but a / in the value will start a new path segment
+
+    The app calls this before anything else looks at the URL or the parameters,
+    so the method is only inferred as POST if there is data left over after the substitution
+
+    Both shortcuts can be turned off with --no-localhost-shortcut,
+    in which case :8080 goes through the normal parsing below just like any other input
     
     If that succeeds then it can just be returned; otherwise, a scheme is added to the given URL
 
@@ -94,6 +276,20 @@
 
     Adding query string elements is also easy given the query method on the builder
 
+    RAW QUERY PARAMETERS
+
+    The query method on the builder percent-encodes every key and value,
+    which is what we want unless the value was already encoded,
+    e.g. a==a%2Fb would be sent as a=a%252Fb
+
+    With --raw-query the query parameters are skipped in handle_parameters
+    and instead append_raw_query glues them onto the URL as key=value pairs joined with &
+    before the request builder is created
+
+    The url crate still escapes characters that can never appear in a query, like spaces,
+    but everything else is left alone, so a value containing & or # will split the parameter or start a fragment
+    That is the price of asking for the value to be sent as is
+
     The RawJsonData type just uses serde to parse the string into a Value before inserting into the data hash map
 
     HANDLING FILES
@@ -113,6 +309,25 @@
     
     FormFile is simple here due to the file function provided by the Form type
 
+    A BODY WRITTEN IN YAML
+
+    --body-yaml <file> reads the whole body from a YAML document instead of from data parameters
+
+    serde_yaml can deserialize straight into a serde_json::Value, the same type the JSON data is built from,
+    so yaml_to_json is a single call and the result is sent with the json method like any other JSON body
+    YAML mappings become objects, sequences become arrays, and scalars keep their types, so port: 8080 is a number
+    A mapping key that isn't a string has no JSON equivalent and is reported as a YAML error
+
+    The file is read in perform before anything else is sent, so it shows up in --print-curl as well
+    validate refuses --body-yaml together with data parameters or --form, as there would be two bodies
+
+    UPLOAD PROGRESS
+
+    When stdout is a terminal, which atty tells us, and --no-progress is not given,
+    a FormFile is added with progress::file_part instead of Form::file
+    That wraps the file in a reader which prints how much of it has been sent to stderr as reqwest reads it,
+    see the progress module
+
     Calling unwrap on the multipart
     because the existence of a FormFile parameter is equivalent to is_multipart being true
     so it is known that multipart must not be None in this branch of the match statement
@@ -145,6 +360,9 @@
     The other two cases with colons mean that the user is giving a password and the app doesn't prompt the user to enter one
     In the first case - myUserName: - it's saying that no password will be provided
 
+    perform calls parse_auth once and hands the result to both handle_auth and http_request,
+    so the password is only asked for once even when --save-http needs it too
+
     ADDING SESSIONS TO THE CLIENT
 
     The client is responsible for actually making the network request
@@ -164,23 +382,42 @@
     Furthermore, if it's not in read only mode which means the session is to be updated,
     then the parameters and authentication information are passed to the session for updating
 
+    CONDITIONAL REQUESTS
+
+    With --cache the session is asked for the If-None-Match and If-Modified-Since headers of the URL,
+    and the response is stored under resp.url() which has the key==value query parameters in it
+    as handle_parameters adds them to the builder with query
+
+    So the URL to look up is worked out by cache_key the same way, appending the query parameters to a copy of url,
+    otherwise a request with query parameters would never be found in the cache,
+    or worse be sent the validators of the same path without them
+    With --raw-query the parameters are already part of url and it is used as it is
+
 ***/
 
-use crate::app::{App, Method, Parameter};
+use crate::app::{App, HttpVersion, Method, Parameter};
+use crate::connection;
 use crate::errors::{Error, HurlResult};
+use crate::progress;
 use crate::session::Session;
 use log::{info, debug, trace, log_enabled, self};
+use reqwest::header::{HeaderMap, ACCEPT, ACCEPT_ENCODING, HOST, RETRY_AFTER};
 use reqwest::multipart::Form;
-use reqwest::{Client, RequestBuilder, Response, Url};
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
 use rpassword;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
+
+const HTTP_FILE_BOUNDARY: &str = "hurl-boundary";
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+const COMPRESSED_ACCEPT_ENCODING: &str = "gzip, deflate";
 
 pub fn perform_method(
     app: &App,
+    client: &Client,
     method: &Method,
     session: &mut Option<Session>
 ) -> HurlResult<Response> {
@@ -188,6 +425,7 @@ pub fn perform_method(
 
     perform(
         app,
+        client,
         method.into(),
         session,
         &method_data.url,
@@ -197,15 +435,25 @@ pub fn perform_method(
 
 pub fn perform(
     app: &App,
+    client: &Client,
     method: reqwest::Method,
     session: &mut Option<Session>,
     raw_url: &str,
     parameters: &Vec<Parameter>
 ) -> HurlResult<Response> {
-    let client = Client::new();
-    let url = parse(app, raw_url)?;
+    let deadline = app.max_time.map(|secs| Instant::now() + Duration::from_secs(secs));
+    let mut url = parse(app, raw_url)?;
     debug!("Parsed url: {}", url);
 
+    if app.raw_query {
+        append_raw_query(&mut url, parameters);
+        debug!("Url with raw query: {}", url);
+    }
+
+    if app.trace_connection || app.verbose > 0 {
+        eprint!("{}", connection::trace(&url));
+    }
+
     let is_multipart = parameters.iter().any(|p| p.is_form_file());
 
     if is_multipart {
@@ -215,6 +463,29 @@ pub fn perform(
         }
     }
 
+    let body = match &app.body_yaml {
+        Some(path) => Some(yaml_to_json(&std::fs::read_to_string(path)?)?),
+        None => None,
+    };
+
+    if app.print_curl {
+        println!("{}", curl_command(app, &method, &url, is_multipart, body.as_ref(), parameters)?);
+    }
+
+    let basic_auth = match &app.auth {
+        Some(auth) => Some(parse_auth(auth)?),
+        None => None,
+    };
+
+    let http_file = match app.save_http {
+        Some(_) => {
+            let auth = basic_auth.as_ref();
+            Some(http_request(app, &method, &url, is_multipart, body.as_ref(), auth, parameters)?)
+        }
+        None => None,
+    };
+
+    let url_key = cache_key(&url, parameters, app.raw_query);
     let mut builder = client.request(method, url);
 
     builder = handle_session(
@@ -222,12 +493,92 @@ pub fn perform(
         session,
         parameters,
         !app.read_only,
+        app.append_headers,
         &app.auth,
         &app.token
     );
-    builder = handle_parameters(builder, app.form, is_multipart, parameters)?;
-    builder = handle_auth(builder, &app.auth, &app.token)?;
 
+    if app.cache {
+        if let Some(s) = session {
+            trace!("Adding conditional headers from the cache");
+            builder = s.add_conditional_headers(builder, &url_key);
+        }
+    }
+
+    let show_progress = !app.no_progress && atty::is(atty::Stream::Stdout);
+    builder = handle_parameters(
+        builder,
+        app.form,
+        is_multipart,
+        app.raw_query,
+        show_progress,
+        app.accept.as_deref(),
+        app.compressed,
+        body.as_ref(),
+        parameters
+    )?;
+
+    if let Some(host) = &app.host_header {
+        trace!("Overriding Host header: {}", host);
+        builder = builder.header(HOST, host.as_str());
+    }
+
+    builder = handle_auth(builder, basic_auth, &app.token);
+
+    let mut retries_left = app.retries;
+
+    let result = loop {
+        let retry = if retries_left > 0 { builder.try_clone() } else { None };
+        let result = send(builder);
+
+        let delay = match &result {
+            Ok(resp) if resp.status() == StatusCode::TOO_MANY_REQUESTS => {
+                retry_after(resp.headers(), SystemTime::now()).unwrap_or(DEFAULT_RETRY_AFTER)
+            }
+            _ => break result,
+        };
+
+        let next = match retry {
+            Some(next) => next,
+            None => break result,
+        };
+
+        if let Some(deadline) = deadline {
+            if Instant::now() + delay > deadline {
+                debug!("Not retrying as waiting {:?} would pass the maximum time", delay);
+                break result;
+            }
+        }
+
+        if !app.quiet {
+            eprintln!("429 Too Many Requests, retrying in {}s", delay.as_secs_f64());
+        }
+
+        std::thread::sleep(delay);
+        retries_left -= 1;
+        builder = next;
+    };
+
+    if let Some(deadline) = deadline {
+        if Instant::now() > deadline {
+            debug!("Exceeded the maximum time of {:?} seconds", app.max_time);
+            return Err(Error::ClientTimeout);
+        }
+    }
+
+    if let (Some(path), Some(mut content)) = (&app.save_http, http_file) {
+        if let Ok(resp) = &result {
+            content.push_str(&http_response_comment(resp.version(), resp.status()));
+        }
+
+        trace!("Saving the request to {}", path.display());
+        std::fs::write(path, content)?;
+    }
+
+    result
+}
+
+fn send(builder: RequestBuilder) -> HurlResult<Response> {
     if log_enabled!(log::Level::Info) {
         let start = Instant::now();
         let result = builder.send().map_err(From:: from);
@@ -239,11 +590,306 @@ pub fn perform(
     }
 }
 
+fn retry_after(headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or(Duration::from_secs(0)))
+}
+
+pub fn build_client(app: &App) -> reqwest::Result<Client> {
+    let mut builder = Client::builder();
+
+    if let Some(HttpVersion::Http2) = app.http_version {
+        trace!("Using HTTP/2 with prior knowledge");
+        builder = builder.h2_prior_knowledge();
+    }
+
+    if let Some(secs) = app.max_time {
+        builder = builder.timeout(Duration::from_secs(secs));
+    }
+
+    if app.compressed {
+        trace!("Leaving decompression of the response to hurl");
+        builder = builder.gzip(false);
+    }
+
+    if let Some(proxy) = &app.proxy {
+        trace!("Sending requests through the proxy {}", proxy);
+        builder = builder.proxy(reqwest::Proxy::all(proxy.as_str())?);
+    } else if app.use_system_proxy {
+        trace!("Using the proxy configured on the system");
+        builder = builder.use_sys_proxy();
+    }
+
+    builder.build()
+}
+
+fn curl_command(
+    app: &App,
+    method: &reqwest::Method,
+    url: &Url,
+    is_multipart: bool,
+    body: Option<&Value>,
+    parameters: &Vec<Parameter>
+) -> HurlResult<String> {
+    let mut args = vec!["curl".to_owned(), "-X".to_owned(), method.as_str().to_owned()];
+    let mut url = url.clone();
+    let mut data: HashMap<&String, Value> = HashMap::new();
+
+    if let Some(HttpVersion::Http2) = app.http_version {
+        args.push("--http2-prior-knowledge".to_owned());
+    }
+
+    if let Some(accept) = accept_header(app.accept.as_ref().map(String::as_str), parameters) {
+        args.push("-H".to_owned());
+        args.push(shell_quote(&format!("Accept: {}", accept)));
+    }
+
+    if app.compressed {
+        args.push("--compressed".to_owned());
+    }
+
+    for param in parameters.iter() {
+        match param {
+            Parameter::Header { key, value } => {
+                args.push("-H".to_owned());
+                args.push(shell_quote(&format!("{}: {}", key, value)));
+            }
+            Parameter::Data { key, value } => {
+                if is_multipart {
+                    args.push("-F".to_owned());
+                    args.push(shell_quote(&format!("{}={}", key, value)));
+                } else {
+                    data.insert(key, Value::String(value.to_owned()));
+                }
+            }
+            Parameter::Query { key, value } => {
+                if !app.raw_query {
+                    url.query_pairs_mut().append_pair(key, value);
+                }
+            }
+            Parameter::RawJsonData { key, value } => {
+                let v: Value = serde_json::from_str(value)?;
+                data.insert(key, v);
+            }
+            Parameter::RawJsonDataFile { key, filename } => {
+                let file = File::open(filename)?;
+                let reader = BufReader::new(file);
+                let v: Value = serde_json::from_reader(reader)?;
+                data.insert(key, v);
+            }
+            Parameter::DataFile { key, filename } => {
+                let value = std::fs::read_to_string(filename)?;
+                data.insert(key, Value::String(value));
+            }
+            Parameter::FormFile { key, filename } => {
+                args.push("-F".to_owned());
+                args.push(shell_quote(&format!("{}=@{}", key, filename)));
+            }
+        }
+    }
+
+    if let Some(body) = body {
+        args.push("-H".to_owned());
+        args.push(shell_quote("Content-Type: application/json"));
+        args.push("-d".to_owned());
+        args.push(shell_quote(&serde_json::to_string(body)?));
+    } else if !data.is_empty() {
+        if app.form {
+            for (key, value) in data.iter() {
+                let value = match value {
+                    Value::String(s) => s.to_owned(),
+                    v => v.to_string(),
+                };
+                args.push("--data-urlencode".to_owned());
+                args.push(shell_quote(&format!("{}={}", key, value)));
+            }
+        } else {
+            args.push("-H".to_owned());
+            args.push(shell_quote("Content-Type: application/json"));
+            args.push("-d".to_owned());
+            args.push(shell_quote(&serde_json::to_string(&data)?));
+        }
+    }
+
+    if let Some(host) = &app.host_header {
+        args.push("-H".to_owned());
+        args.push(shell_quote(&format!("Host: {}", host)));
+    }
+
+    if let Some(auth) = &app.auth {
+        args.push("-u".to_owned());
+        args.push(shell_quote(auth));
+    }
+
+    if let Some(token) = &app.token {
+        args.push("-H".to_owned());
+        args.push(shell_quote(&format!("Authorization: Bearer {}", token)));
+    }
+
+    args.push(shell_quote(url.as_str()));
+
+    Ok(args.join(" "))
+}
+
+fn http_request(
+    app: &App,
+    method: &reqwest::Method,
+    url: &Url,
+    is_multipart: bool,
+    body: Option<&Value>,
+    basic_auth: Option<&(String, Option<String>)>,
+    parameters: &Vec<Parameter>
+) -> HurlResult<String> {
+    let mut url = url.clone();
+    let mut headers = Vec::new();
+    let mut data: HashMap<&String, Value> = HashMap::new();
+    let mut parts = Vec::new();
+
+    if let Some(accept) = accept_header(app.accept.as_ref().map(String::as_str), parameters) {
+        headers.push(format!("Accept: {}", accept));
+    }
+
+    if let Some(encoding) = accept_encoding_header(app.compressed, parameters) {
+        headers.push(format!("Accept-Encoding: {}", encoding));
+    }
+
+    for param in parameters.iter() {
+        match param {
+            Parameter::Header { key, value } => {
+                headers.push(format!("{}: {}", key, value));
+            }
+            Parameter::Data { key, value } => {
+                if is_multipart {
+                    parts.push(format!("Content-Disposition: form-data; name=\"{}\"\n\n{}", key, value));
+                } else {
+                    data.insert(key, Value::String(value.to_owned()));
+                }
+            }
+            Parameter::Query { key, value } => {
+                if !app.raw_query {
+                    url.query_pairs_mut().append_pair(key, value);
+                }
+            }
+            Parameter::RawJsonData { key, value } => {
+                let v: Value = serde_json::from_str(value)?;
+                data.insert(key, v);
+            }
+            Parameter::RawJsonDataFile { key, filename } => {
+                let file = File::open(filename)?;
+                let reader = BufReader::new(file);
+                let v: Value = serde_json::from_reader(reader)?;
+                data.insert(key, v);
+            }
+            Parameter::DataFile { key, filename } => {
+                let value = std::fs::read_to_string(filename)?;
+                data.insert(key, Value::String(value));
+            }
+            Parameter::FormFile { key, filename } => {
+                parts.push(format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\n\n< {}",
+                    key, filename, filename
+                ));
+            }
+        }
+    }
+
+    let content = if let Some(body) = body {
+        headers.push("Content-Type: application/json".to_owned());
+        Some(serde_json::to_string_pretty(body)?)
+    } else if is_multipart {
+        headers.push(format!("Content-Type: multipart/form-data; boundary={}", HTTP_FILE_BOUNDARY));
+        let mut content = String::new();
+
+        for part in parts {
+            content.push_str(&format!("--{}\n{}\n", HTTP_FILE_BOUNDARY, part));
+        }
+
+        content.push_str(&format!("--{}--", HTTP_FILE_BOUNDARY));
+        Some(content)
+    } else if !data.is_empty() {
+        if app.form {
+            headers.push("Content-Type: application/x-www-form-urlencoded".to_owned());
+            let mut encoded = Url::parse("http://localhost/").unwrap();
+
+            for (key, value) in data.iter() {
+                let value = match value {
+                    Value::String(s) => s.to_owned(),
+                    v => v.to_string(),
+                };
+                encoded.query_pairs_mut().append_pair(key, &value);
+            }
+
+            encoded.query().map(ToOwned::to_owned)
+        } else {
+            headers.push("Content-Type: application/json".to_owned());
+            Some(serde_json::to_string_pretty(&data)?)
+        }
+    } else {
+        None
+    };
+
+    if let Some(host) = &app.host_header {
+        headers.push(format!("Host: {}", host));
+    }
+
+    if let Some((username, password)) = basic_auth {
+        headers.push(format!("Authorization: {}", basic_auth_header(username, password.as_deref())));
+    }
+
+    if let Some(token) = &app.token {
+        headers.push(format!("Authorization: Bearer {}", token));
+    }
+
+    let mut s = format!("{} {}\n", method.as_str(), url.as_str());
+
+    for header in headers {
+        s.push_str(&header);
+        s.push('\n');
+    }
+
+    if let Some(content) = content {
+        s.push('\n');
+        s.push_str(&content);
+        s.push('\n');
+    }
+
+    Ok(s)
+}
+
+fn http_response_comment(version: reqwest::Version, status: reqwest::StatusCode) -> String {
+    format!(
+        "\n# Response\n# {:?} {} {}\n",
+        version,
+        status.as_u16(),
+        status.canonical_reason().unwrap_or("Unknown")
+    )
+}
+
+fn basic_auth_header(username: &str, password: Option<&str>) -> String {
+    let credentials = format!("{}:{}", username, password.unwrap_or(""));
+    format!("Basic {}", base64::encode(&credentials))
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
 fn parse(app: &App, s: &str) -> Result<Url, reqwest::UrlError> {
-    if s.starts_with(":/") {
-        return Url::parse(&format!("http://localhost{}", &s[1..]));
-    } else if s.starts_with(":") {
-        return Url::parse(&format!("http://localhost{}", s))
+    let joined = with_base_url(app.base_url.as_deref(), s);
+    let s = joined.as_str();
+
+    if !app.no_localhost_shortcut {
+        if s.starts_with(":/") {
+            return Url::parse(&format!("http://localhost{}", &s[1..]));
+        } else if s.starts_with(":") {
+            return Url::parse(&format!("http://localhost{}", s))
+        }
     }
 
     match Url::parse(s) {
@@ -258,14 +904,152 @@ fn parse(app: &App, s: &str) -> Result<Url, reqwest::UrlError> {
     }
 }
 
+fn with_base_url(base_url: Option<&str>, s: &str) -> String {
+    match base_url {
+        Some(base) if s.starts_with('/') => format!("{}{}", base.trim_end_matches('/'), s),
+        _ => s.to_owned(),
+    }
+}
+
+pub fn substitute_path_params(
+    raw_url: &str,
+    parameters: Vec<Parameter>
+) -> HurlResult<(String, Vec<Parameter>)> {
+    let path_end = raw_url.find(|c| c == '?' || c == '#').unwrap_or_else(|| raw_url.len());
+    let (path, rest) = raw_url.split_at(path_end);
+
+    let mut parameters = parameters;
+    let mut url = String::with_capacity(raw_url.len());
+    let mut remaining = path;
+
+    while let Some(idx) = remaining.find("/:") {
+        let after = &remaining[idx + 2..];
+        let name_len = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or_else(|| after.len());
+        let name = &after[..name_len];
+
+        url.push_str(&remaining[..idx + 1]);
+
+        if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            let value = take_path_value(&mut parameters, name)
+                .ok_or_else(|| Error::MissingPathParameter(name.to_owned()))?;
+            trace!("Substituting :{} in url with {}", name, value);
+            url.push_str(&value);
+        } else {
+            url.push(':');
+            url.push_str(name);
+        }
+
+        remaining = &after[name_len..];
+    }
+
+    url.push_str(remaining);
+    url.push_str(rest);
+
+    Ok((url, parameters))
+}
+
+fn take_path_value(parameters: &mut Vec<Parameter>, name: &str) -> Option<String> {
+    let idx = parameters.iter().position(|p| match p {
+        Parameter::Data { key, .. } | Parameter::RawJsonData { key, .. } => key == name,
+        _ => false,
+    })?;
+
+    match parameters.remove(idx) {
+        Parameter::Data { value, .. } => Some(value),
+        Parameter::RawJsonData { value, .. } => match serde_json::from_str(&value) {
+            Ok(Value::String(s)) => Some(s),
+            _ => Some(value),
+        },
+        _ => unreachable!(),
+    }
+}
+
+fn cache_key(url: &Url, parameters: &[Parameter], is_raw_query: bool) -> String {
+    let mut url = url.clone();
+
+    if !is_raw_query {
+        for parameter in parameters {
+            if let Parameter::Query { key, value } = parameter {
+                url.query_pairs_mut().append_pair(key, value);
+            }
+        }
+    }
+
+    url.as_str().to_owned()
+}
+
+fn append_raw_query(url: &mut Url, parameters: &Vec<Parameter>) {
+    let pairs: Vec<String> = parameters
+        .iter()
+        .filter_map(|p| match p {
+            Parameter::Query { key, value } => Some(format!("{}={}", key, value)),
+            _ => None,
+        })
+        .collect();
+
+    if pairs.is_empty() {
+        return;
+    }
+
+    let query = match url.query() {
+        Some(existing) if !existing.is_empty() => format!("{}&{}", existing, pairs.join("&")),
+        _ => pairs.join("&"),
+    };
+
+    url.set_query(Some(&query));
+}
+
+fn accept_header<'a>(accept: Option<&'a str>, parameters: &[Parameter]) -> Option<&'a str> {
+    let overridden = parameters.iter().any(|p| match p {
+        Parameter::Header { key, .. } => key.eq_ignore_ascii_case("accept"),
+        _ => false,
+    });
+
+    if overridden {
+        None
+    } else {
+        accept
+    }
+}
+
+fn accept_encoding_header(compressed: bool, parameters: &[Parameter]) -> Option<&'static str> {
+    let overridden = parameters.iter().any(|p| match p {
+        Parameter::Header { key, .. } => key.eq_ignore_ascii_case("accept-encoding"),
+        _ => false,
+    });
+
+    if compressed && !overridden {
+        Some(COMPRESSED_ACCEPT_ENCODING)
+    } else {
+        None
+    }
+}
+
 fn handle_parameters(
     mut builder: RequestBuilder,
     is_form: bool,
     is_multipart: bool,
+    is_raw_query: bool,
+    show_progress: bool,
+    accept: Option<&str>,
+    compressed: bool,
+    body: Option<&Value>,
     parameters: &Vec<Parameter>
 ) -> HurlResult<RequestBuilder> {
     let mut data: HashMap<&String, Value> = HashMap::new();
 
+    if let Some(accept) = accept_header(accept, parameters) {
+        trace!("Adding Accept header: {}", accept);
+        builder = builder.header(ACCEPT, accept);
+    }
+
+    if let Some(encoding) = accept_encoding_header(compressed, parameters) {
+        trace!("Adding Accept-Encoding header: {}", encoding);
+        builder = builder.header(ACCEPT_ENCODING, encoding);
+    }
+
     let mut multipart = if is_multipart {
         Some(Form::new())
     } else {
@@ -287,8 +1071,12 @@ fn handle_parameters(
                 }
             }
             Parameter::Query { key, value } => {
-                trace!("Adding query parameter: {}", key);
-                builder = builder.query(&[(key, value)]);
+                if is_raw_query {
+                    trace!("Skipping raw query parameter already in url: {}", key);
+                } else {
+                    trace!("Adding query parameter: {}", key);
+                    builder = builder.query(&[(key, value)]);
+                }
             }
             Parameter::RawJsonData { key, value } => {
                 trace!("Adding JSON data: {}", key);
@@ -309,16 +1097,24 @@ fn handle_parameters(
             }
             Parameter::FormFile { key, filename } => {
                 trace!("Adding file={} with key={}", filename, key);
-                multipart = Some(
-                    multipart
-                        .unwrap()
-                        .file(key.to_owned(), filename.to_owned())?,
-                );
+                if show_progress {
+                    let part = progress::file_part(filename)?;
+                    multipart = Some(multipart.unwrap().part(key.to_owned(), part));
+                } else {
+                    multipart = Some(
+                        multipart
+                            .unwrap()
+                            .file(key.to_owned(), filename.to_owned())?,
+                    );
+                }
             }
         }
     }
 
-    if let Some(m) = multipart {
+    if let Some(body) = body {
+        trace!("Adding JSON body from YAML");
+        builder = builder.json(body);
+    } else if let Some(m) = multipart {
         builder = builder.multipart(m);
     } else {
         if !data.is_empty() {
@@ -333,13 +1129,16 @@ fn handle_parameters(
     Ok(builder)
 }
 
+fn yaml_to_json(yaml: &str) -> HurlResult<Value> {
+    Ok(serde_yaml::from_str(yaml)?)
+}
+
 fn handle_auth(
     mut builder: RequestBuilder,
-    auth: &Option<String>,
+    basic_auth: Option<(String, Option<String>)>,
     token: &Option<String>
-) -> HurlResult<RequestBuilder> {
-    if let Some(auth_string) = auth {
-        let (username, maybe_password) = parse_auth(&auth_string)?;
+) -> RequestBuilder {
+    if let Some((username, maybe_password)) = basic_auth {
         trace!("Parsed basic authentication. Username={}", username);
         builder = builder.basic_auth(username, maybe_password);
     }
@@ -349,7 +1148,7 @@ fn handle_auth(
         builder = builder.bearer_auth(bearer);
     }
 
-    Ok(builder)
+    builder
 }
 
 fn parse_auth(s: &str) -> HurlResult<(String, Option<String>)> {
@@ -373,6 +1172,7 @@ fn handle_session(
     session: &mut Option<Session>,
     parameters: &Vec<Parameter>,
     update_session: bool,
+    append_headers: bool,
     auth: &Option<String>,
     token: &Option<String>
 ) -> RequestBuilder {
@@ -382,10 +1182,494 @@ fn handle_session(
         
         if update_session {
             trace!("Updating session with parameters from this request");
-            s.update_with_parameters(parameters);
+            s.update_with_parameters(parameters, append_headers);
             s.update_auth(auth, token)
         }
     }
 
     builder
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structopt::StructOpt;
+
+    fn raw_json(key: &str, value: &str) -> Parameter {
+        Parameter::RawJsonData {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        }
+    }
+
+    fn query(key: &str, value: &str) -> Parameter {
+        Parameter::Query {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        }
+    }
+
+    #[test]
+    fn substitute_path_params_single() {
+        let params = vec![raw_json("id", "123"), query("full", "true")];
+
+        let (url, params) = substitute_path_params("example.com/users/:id?x=1", params).unwrap();
+
+        assert_eq!(url, "example.com/users/123?x=1");
+        assert_eq!(params, vec![query("full", "true")]);
+    }
+
+    #[test]
+    fn substitute_path_params_multiple() {
+        let params = vec![
+            Parameter::Data {
+                key: "post".to_owned(),
+                value: "hello".to_owned(),
+            },
+            raw_json("id", "7"),
+            raw_json("slug", r#""a-b""#),
+        ];
+
+        let (url, params) =
+            substitute_path_params(":8080/users/:id/posts/:post/:slug", params).unwrap();
+
+        assert_eq!(url, ":8080/users/7/posts/hello/a-b");
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn substitute_path_params_leaves_ports_alone() {
+        let (url, params) = substitute_path_params("http://example.com:8080/users", vec![]).unwrap();
+
+        assert_eq!(url, "http://example.com:8080/users");
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn accept_header_from_option() {
+        let params = vec![query("page", "1")];
+
+        assert_eq!(accept_header(Some("application/json"), &params), Some("application/json"));
+        assert_eq!(accept_header(None, &params), None);
+    }
+
+    #[test]
+    fn accept_header_overridden_by_parameter() {
+        let params = vec![Parameter::Header {
+            key: "accept".to_owned(),
+            value: "text/html".to_owned(),
+        }];
+
+        assert_eq!(accept_header(Some("application/json"), &params), None);
+    }
+
+    #[test]
+    fn substitute_path_params_missing_value() {
+        let params = vec![raw_json("id", "1"), query("post", "2")];
+
+        match substitute_path_params("example.com/users/:id/posts/:post", params) {
+            Err(Error::MissingPathParameter(name)) => assert_eq!(name, "post"),
+            other => panic!("expected a missing path parameter, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_joins_path_to_base_url() {
+        let app = App::from_iter(&["hurl", "--base-url", "https://api.example.com", "/users/1"]);
+        assert_eq!(parse(&app, "/users/1").unwrap().as_str(), "https://api.example.com/users/1");
+
+        let app = App::from_iter(&["hurl", "--base-url", "api.example.com/v2/", "/users/1"]);
+        assert_eq!(parse(&app, "/users/1").unwrap().as_str(), "http://api.example.com/v2/users/1");
+    }
+
+    #[test]
+    fn parse_expands_the_localhost_shortcut_unless_disabled() {
+        let app = App::from_iter(&["hurl", ":8080"]);
+        assert_eq!(parse(&app, ":8080").unwrap().as_str(), "http://localhost:8080/");
+        assert_eq!(parse(&app, ":/users").unwrap().as_str(), "http://localhost/users");
+
+        let app = App::from_iter(&["hurl", "--no-localhost-shortcut", ":8080"]);
+        assert!(parse(&app, ":8080").is_err());
+    }
+
+    #[test]
+    fn parse_ignores_base_url_for_full_urls() {
+        let app = App::from_iter(&["hurl", "--base-url", "https://api.example.com", "example.org/users/1"]);
+
+        assert_eq!(parse(&app, "example.org/users/1").unwrap().as_str(), "http://example.org/users/1");
+        assert_eq!(parse(&app, ":8080/users/1").unwrap().as_str(), "http://localhost:8080/users/1");
+    }
+
+    #[test]
+    fn with_base_url_leaves_paths_alone_without_a_base() {
+        assert_eq!(with_base_url(None, "/users/1"), "/users/1");
+        assert_eq!(with_base_url(Some("https://api.example.com/"), "/users/1"), "https://api.example.com/users/1");
+    }
+
+    #[test]
+    fn append_raw_query_keeps_values_as_they_are() {
+        let params = vec![query("q", "a%20b"), raw_json("id", "1"), query("tags", "x,y")];
+        let base = "http://example.com/search?page=1";
+
+        let encoded = handle_parameters(Client::new().get(base), false, false, false, false, None, false, None, &params)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(encoded.url().as_str(), "http://example.com/search?page=1&q=a%2520b&tags=x%2Cy");
+
+        let mut raw = Url::parse(base).unwrap();
+        append_raw_query(&mut raw, &params);
+        assert_eq!(raw.as_str(), "http://example.com/search?page=1&q=a%20b&tags=x,y");
+
+        let sent = handle_parameters(Client::new().get(raw.clone()), false, false, true, false, None, false, None, &params)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(sent.url(), &raw);
+
+        let mut untouched = Url::parse("http://example.com/search").unwrap();
+        append_raw_query(&mut untouched, &vec![raw_json("id", "1")]);
+        assert_eq!(untouched.as_str(), "http://example.com/search");
+    }
+
+    #[test]
+    fn cache_key_matches_the_url_that_is_requested() {
+        let url = Url::parse("http://example.com/items?page=1").unwrap();
+        let params = vec![query("q", "a b&c"), raw_json("id", "1"), query("sort", "new")];
+
+        let requested = Client::new()
+            .get(url.clone())
+            .query(&[("q", "a b&c"), ("sort", "new")])
+            .build()
+            .unwrap();
+
+        assert_eq!(cache_key(&url, &params, false), requested.url().as_str());
+        assert_eq!(cache_key(&url, &[raw_json("id", "1")], false), "http://example.com/items?page=1");
+        assert_eq!(cache_key(&url, &params, true), "http://example.com/items?page=1");
+    }
+
+    #[test]
+    fn yaml_to_json_converts_a_document() {
+        let yaml = "name: hurl\nport: 8080\nsecure: false\ntags:\n  - cli\n  - http\nowner:\n  login: ruben\n  id: null\n";
+
+        assert_eq!(
+            yaml_to_json(yaml).unwrap(),
+            serde_json::json!({
+                "name": "hurl",
+                "port": 8080,
+                "secure": false,
+                "tags": ["cli", "http"],
+                "owner": { "login": "ruben", "id": null }
+            })
+        );
+    }
+
+    #[test]
+    fn yaml_to_json_rejects_invalid_yaml() {
+        assert!(matches!(yaml_to_json("a: [1, 2"), Err(Error::Yaml(_))));
+    }
+
+    #[test]
+    fn build_client_applies_every_option() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let app = App::from_iter(&[
+            "hurl", "--http-version", "2", "--max-time", "5", "--compressed",
+            "--proxy", "http://127.0.0.1:3128", "example.com",
+        ]);
+        assert!(build_client(&app).is_ok());
+
+        let app = App::from_iter(&["hurl", "--use-system-proxy", "example.com"]);
+        assert!(build_client(&app).is_ok());
+
+        let app = App::from_iter(&["hurl", "--proxy", "not a url", "example.com"]);
+        assert!(build_client(&app).is_err());
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"hello").unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let url = "http://proxied.example/";
+        let body = gzipped.clone();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 1024];
+            let n = stream.read(&mut buffer).unwrap();
+            let head = format!("HTTP/1.1 200 OK\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\n\r\n", body.len());
+            stream.write_all(head.as_bytes()).unwrap();
+            stream.write_all(&body).unwrap();
+            String::from_utf8_lossy(&buffer[..n]).into_owned()
+        });
+
+        let app = App::from_iter(&["hurl", "--max-time", "5", "--compressed", "--proxy", &proxy, url]);
+        let client = build_client(&app).unwrap();
+        let mut resp = client.get(url).send().unwrap();
+        let mut received = Vec::new();
+        resp.read_to_end(&mut received).unwrap();
+        let request = server.join().unwrap();
+
+        assert!(request.starts_with("GET http://proxied.example/ HTTP/1.1\r\n"));
+        assert_eq!(received, gzipped);
+    }
+
+    #[test]
+    fn perform_sends_a_custom_method() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/cache/item", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 1024];
+            let n = stream.read(&mut buffer).unwrap();
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").unwrap();
+            String::from_utf8_lossy(&buffer[..n]).into_owned()
+        });
+
+        let mut app = App::from_iter(&["hurl", "--method", "PURGE", &url]);
+        app.validate().unwrap();
+        let client = build_client(&app).unwrap();
+        let resp = perform(&app, &client, app.request_method(), &mut None, &url, &app.parameters).unwrap();
+
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        assert!(server.join().unwrap().starts_with("PURGE /cache/item HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn perform_sends_the_host_header_override() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/status", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 1024];
+            let n = stream.read(&mut buffer).unwrap();
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").unwrap();
+            String::from_utf8_lossy(&buffer[..n]).to_ascii_lowercase()
+        });
+
+        let app = App::from_iter(&["hurl", "--host-header", "api.internal", &url]);
+        let client = build_client(&app).unwrap();
+        let resp = perform(&app, &client, reqwest::Method::GET, &mut None, &url, &app.parameters).unwrap();
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let request = server.join().unwrap();
+        assert!(request.contains("\r\nhost: api.internal\r\n"), "{}", request);
+        assert_eq!(request.matches("\r\nhost:").count(), 1);
+    }
+
+    #[test]
+    fn perform_asks_for_a_compressed_response() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/feed", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 1024];
+            let n = stream.read(&mut buffer).unwrap();
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").unwrap();
+            String::from_utf8_lossy(&buffer[..n]).to_ascii_lowercase()
+        });
+
+        let app = App::from_iter(&["hurl", "--compressed", &url]);
+        let client = build_client(&app).unwrap();
+        let resp = perform(&app, &client, reqwest::Method::GET, &mut None, &url, &app.parameters).unwrap();
+
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        assert!(server.join().unwrap().contains("\r\naccept-encoding: gzip, deflate\r\n"));
+
+        let header = vec![Parameter::Header {
+            key: "Accept-Encoding".to_owned(),
+            value: "identity".to_owned(),
+        }];
+        assert_eq!(accept_encoding_header(true, &header), None);
+        assert_eq!(accept_encoding_header(false, &[]), None);
+    }
+
+    #[test]
+    fn curl_command_quotes_headers_and_a_json_body() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote("it's"), r#"'it'\''s'"#);
+
+        let app = App::from_iter(&["hurl", "-a", "bob:secret", "example.com/users"]);
+        let url = parse(&app, "example.com/users").unwrap();
+        let params = vec![
+            Parameter::Header {
+                key: "X-Api-Key".to_owned(),
+                value: "secret".to_owned(),
+            },
+            raw_json("user", r#"{"name":"o'brien"}"#),
+            query("notify", "true"),
+        ];
+
+        assert_eq!(
+            curl_command(&app, &reqwest::Method::POST, &url, false, None, &params).unwrap(),
+            r#"curl -X POST -H 'X-Api-Key: secret' -H 'Content-Type: application/json' -d '{"user":{"name":"o'\''brien"}}' -u 'bob:secret' 'http://example.com/users?notify=true'"#
+        );
+    }
+
+    #[test]
+    fn http_request_writes_a_post_with_headers_and_a_json_body() {
+        let app = App::from_iter(&["hurl", "--token", "abc123", "example.com/users"]);
+        let url = parse(&app, "example.com/users").unwrap();
+        let params = vec![
+            Parameter::Header {
+                key: "X-Api-Key".to_owned(),
+                value: "secret".to_owned(),
+            },
+            raw_json("user", r#"{"name":"ruben","admin":true}"#),
+            query("notify", "true"),
+        ];
+        let auth = parse_auth("bob:secret").unwrap();
+
+        let mut content =
+            http_request(&app, &reqwest::Method::POST, &url, false, None, Some(&auth), &params).unwrap();
+        content.push_str(&http_response_comment(reqwest::Version::HTTP_11, reqwest::StatusCode::CREATED));
+
+        assert_eq!(
+            content,
+            "POST http://example.com/users?notify=true\n\
+             X-Api-Key: secret\n\
+             Content-Type: application/json\n\
+             Authorization: Basic Ym9iOnNlY3JldA==\n\
+             Authorization: Bearer abc123\n\
+             \n\
+             {\n  \"user\": {\n    \"admin\": true,\n    \"name\": \"ruben\"\n  }\n}\n\
+             \n\
+             # Response\n\
+             # HTTP/1.1 201 Created\n"
+        );
+    }
+
+    #[test]
+    fn basic_auth_header_matches_reqwest() {
+        for (username, password) in &[("bob", Some("secret")), ("bob", None), ("ruben", Some("p:ss word"))] {
+            let request = Client::new()
+                .get("http://example.com/")
+                .basic_auth(username, password.as_ref())
+                .build()
+                .unwrap();
+
+            assert_eq!(
+                basic_auth_header(username, *password),
+                request.headers()[reqwest::header::AUTHORIZATION].to_str().unwrap()
+            );
+        }
+
+        assert_eq!(basic_auth_header("bob", None), "Basic Ym9iOg==");
+    }
+
+    #[test]
+    fn retry_after_reads_seconds_and_http_dates() {
+        // Wed, 21 Oct 2015 07:28:00 GMT
+        let now = std::time::UNIX_EPOCH + Duration::from_secs(1_445_412_480);
+        let mut headers = HeaderMap::new();
+
+        headers.insert(RETRY_AFTER, "120".parse().unwrap());
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(120)));
+
+        headers.insert(RETRY_AFTER, "Wed, 21 Oct 2015 07:28:30 GMT".parse().unwrap());
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(30)));
+
+        headers.insert(RETRY_AFTER, "Wed, 21 Oct 2015 07:27:00 GMT".parse().unwrap());
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(0)));
+
+        headers.insert(RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(retry_after(&headers, now), None);
+        assert_eq!(retry_after(&HeaderMap::new(), now), None);
+    }
+
+    #[test]
+    fn perform_stops_retrying_at_the_max_time() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/busy", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&requests);
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut buffer = [0; 1024];
+                let _ = stream.read(&mut buffer);
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = stream.write_all(
+                    b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 1\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+                );
+            }
+        });
+
+        let app = App::from_iter(&["hurl", "--retries", "5", "--max-time", "2", &url]);
+        let client = build_client(&app).unwrap();
+        let started = Instant::now();
+        let resp = perform(&app, &client, reqwest::Method::GET, &mut None, &url, &app.parameters).unwrap();
+
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn perform_reuses_the_connection_of_a_shared_client() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ping", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            let mut peers = Vec::new();
+
+            while peers.len() < 2 {
+                let (mut stream, peer) = listener.accept().unwrap();
+                stream.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+                let mut buffer = [0; 1024];
+
+                while peers.len() < 2 {
+                    match stream.read(&mut buffer) {
+                        Ok(n) if n > 0 => {
+                            peers.push(peer);
+                            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").unwrap();
+                        }
+                        _ => break,
+                    }
+                }
+            }
+
+            peers
+        });
+
+        let app = App::from_iter(&["hurl", &url]);
+        let client = build_client(&app).unwrap();
+
+        for _ in 0..2 {
+            let resp = perform(&app, &client, reqwest::Method::GET, &mut None, &url, &app.parameters).unwrap();
+            assert_eq!(resp.status(), reqwest::StatusCode::OK);
+            drop(resp);
+            // give the connection a moment to go back to the pool
+            std::thread::sleep(Duration::from_millis(50));
+        }
+
+        let peers = server.join().unwrap();
+        assert_eq!(peers[0], peers[1]);
+    }
+}
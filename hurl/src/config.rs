@@ -29,6 +29,15 @@
     the expected data structure
 
     The use of unwrap here is for expedience
+
+    base_url is a default prefix for URLs which are given as just a path,
+    e.g. with base_url = "https://api.example.com" hurl /users/1 requests https://api.example.com/users/1
+
+    summary = true prints the summary line after every request like --summary does,
+    which --no-summary can still turn off
+
+    proxy = "http://127.0.0.1:3128" sends every request through that proxy like --proxy does,
+    and use_system_proxy = true picks up the proxy configured on the system like --use-system-proxy
 ***/
 
 use serde::Deserialize;
@@ -45,6 +54,10 @@ pub struct Config {
     pub auth: Option<String>,
     pub token: Option<String>,
     pub secure: Option<bool>,
+    pub base_url: Option<String>,
+    pub summary: Option<bool>,
+    pub proxy: Option<String>,
+    pub use_system_proxy: Option<bool>,
 }
 
 pub fn config_file(app: &App) -> PathBuf {
@@ -0,0 +1,134 @@
+/***
+ *
+ *
+ *
+    CONNECTION MODULE
+
+    When a request fails to connect it's not always clear why,
+    the host might resolve to an unexpected address, or to an IPv6 address the server isn't listening on,
+    or the port might not be the one that was meant
+
+    --trace-connection, or any level of --verbose, prints that information before the request is made:
+
+        Connecting to localhost over http on port 8080 (from the URL)
+          127.0.0.1:8080
+          [::1]:8080
+
+    RESOLVING THE HOST OURSELVES
+
+    The blocking client in reqwest doesn't tell us which address it connected to,
+    nor the local address the connection was made from
+
+    So the host is resolved separately with the standard library's ToSocketAddrs,
+    which uses the same system resolver, and every candidate address is listed in the order it came back
+    The connection is made to the first of them that accepts it, usually the first one in the list
+
+    The port is the one in the URL or the default one for the transport, 80 for http and 443 for https,
+    which is what parse in the client module ends up with after filling in a missing transport
+    The output says which of the two it is, as a URL like example.com:443 given without a transport
+    still goes over http to port 443
+
+    A host that can't be resolved is reported in the output rather than as an error,
+    the request still goes ahead and fails with reqwest's own error the same as it would without the flag
+
+    An IP address in the URL resolves to itself, so it's listed as the only candidate
+
+***/
+
+use reqwest::Url;
+use std::fmt::Write;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+
+pub fn resolve(url: &Url) -> io::Result<Vec<SocketAddr>> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "URL has no host"))?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "URL has no port"))?;
+
+    // An IPv6 host comes back from host_str in brackets, which ToSocketAddrs doesn't accept
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    Ok((host, port).to_socket_addrs()?.collect())
+}
+
+pub fn trace(url: &Url) -> String {
+    describe(url, resolve(url))
+}
+
+fn describe(url: &Url, addrs: io::Result<Vec<SocketAddr>>) -> String {
+    let mut out = String::new();
+
+    let port_source = if url.port().is_some() {
+        "from the URL"
+    } else {
+        "the default"
+    };
+
+    let _ = writeln!(
+        out,
+        "Connecting to {} over {} on port {} ({})",
+        url.host_str().unwrap_or(""),
+        url.scheme(),
+        url.port_or_known_default().map(|p| p.to_string()).unwrap_or_default(),
+        port_source
+    );
+
+    match addrs {
+        Ok(ref addrs) if addrs.is_empty() => {
+            let _ = writeln!(out, "  no addresses found");
+        }
+        Ok(addrs) => {
+            for addr in addrs {
+                let _ = writeln!(out, "  {}", addr);
+            }
+        }
+        Err(e) => {
+            let _ = writeln!(out, "  could not resolve: {}", e);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_localhost_uses_the_port_from_the_url() {
+        let url = Url::parse("http://localhost:8080/health").unwrap();
+        let addrs = resolve(&url).unwrap();
+
+        assert!(!addrs.is_empty());
+        assert!(addrs.iter().all(|addr| addr.ip().is_loopback() && addr.port() == 8080));
+    }
+
+    #[test]
+    fn resolve_ip_address_with_default_port() {
+        let url = Url::parse("https://[::1]/").unwrap();
+
+        assert_eq!(resolve(&url).unwrap(), vec!["[::1]:443".parse::<SocketAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn describe_lists_every_candidate() {
+        let url = Url::parse("http://localhost:8080/").unwrap();
+        let addrs = vec!["127.0.0.1:8080".parse().unwrap(), "[::1]:8080".parse().unwrap()];
+
+        assert_eq!(
+            describe(&url, Ok(addrs)),
+            "Connecting to localhost over http on port 8080 (from the URL)\n  127.0.0.1:8080\n  [::1]:8080\n"
+        );
+
+        let url = Url::parse("https://nowhere.invalid/").unwrap();
+        let err = io::Error::new(io::ErrorKind::Other, "no such host");
+
+        assert_eq!(
+            describe(&url, Err(err)),
+            "Connecting to nowhere.invalid over https on port 443 (the default)\n  could not resolve: no such host\n"
+        );
+    }
+}
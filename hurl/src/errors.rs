@@ -32,6 +32,7 @@
         - serde_json::error::Error
         - std::io::Error (dealing with file system errors)
         - reqwest::UrlError (URL parsing)
+        - serde_yaml::Error (reading a --body-yaml file), kept as its message
 
     To handle load failure of syntax definitions add a variant to the error enum
     and support for printing the error
@@ -51,6 +52,21 @@ pub enum Error {
     IO(std::io::ErrorKind),
     UrlParseError(reqwest::UrlError),
     SyntaxLoadError(&'static str),
+    UnsupportedHttpVersion(String),
+    PresetNotFound(String),
+    UnknownMethod(String),
+    ResponseTooLarge(u64),
+    MissingPathParameter(String),
+    Yaml(String),
+    ConflictingBody(&'static str),
+    TooManyUrlsToDiff(usize),
+    Toml(String),
+    InvalidMethod(String),
+    ConflictingMethod(String, &'static str),
+    OperationNotFound(String),
+    UnsupportedExample(String),
+    JsonPathNotFound(String),
+    InvalidJsonPath(String),
 }
 
 pub type HurlResult<T> = Result<T, Error>;
@@ -91,6 +107,51 @@ impl fmt::Display for Error {
             Error::SyntaxLoadError(typ) => {
                 write!(f, "Error loading syntax for {}", typ)
             }
+            Error::UnsupportedHttpVersion(v) => {
+                write!(f, "Unsupported HTTP version: {} (expected 1.1 or 2)", v)
+            }
+            Error::PresetNotFound(name) => {
+                write!(f, "No preset named {}", name)
+            }
+            Error::UnknownMethod(m) => {
+                write!(f, "Unknown HTTP method: {}", m)
+            }
+            Error::ResponseTooLarge(limit) => {
+                write!(f, "Response body is larger than the limit of {} bytes", limit)
+            }
+            Error::MissingPathParameter(name) => {
+                write!(f, "No value given for :{} in the URL, add a parameter like {}=value", name, name)
+            }
+            Error::Yaml(msg) => {
+                write!(f, "YAML error: {}", msg)
+            }
+            Error::ConflictingBody(other) => {
+                write!(f, "--body-yaml can't be combined with {}", other)
+            }
+            Error::TooManyUrlsToDiff(count) => {
+                write!(f, "--diff compares two responses but {} URLs were given", count)
+            }
+            Error::Toml(msg) => {
+                write!(f, "Could not write the report as TOML: {}", msg)
+            }
+            Error::InvalidMethod(m) => {
+                write!(f, "Invalid HTTP method: {:?} is not a valid method name", m)
+            }
+            Error::ConflictingMethod(method, cmd) => {
+                write!(f, "--method {} can't be combined with the {} subcommand", method, cmd)
+            }
+            Error::OperationNotFound(id) => {
+                write!(f, "No operation with the operationId {} in the OpenAPI spec", id)
+            }
+            Error::UnsupportedExample(id) => {
+                write!(f, "The example body of {} is not a JSON object so it can't be used", id)
+            }
+            Error::JsonPathNotFound(path) => {
+                write!(f, "Nothing at {} in the response body", path)
+            }
+            Error::InvalidJsonPath(path) => {
+                write!(f, "Invalid path {:?}, expected keys and indexes like data.items.0.name", path)
+            }
         }
     }
 }
@@ -143,6 +204,13 @@ impl From<std::io::Error> for Error {
     }
 }
 
+impl From<serde_yaml::Error> for Error {
+    #[inline]
+    fn from(err: serde_yaml::Error) -> Error {
+        Error::Yaml(err.to_string())
+    }
+}
+
 impl From<reqwest::UrlError> for Error {
     #[inline]
     fn from(err: reqwest::UrlError) -> Error {
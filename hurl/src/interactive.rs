@@ -0,0 +1,240 @@
+/***
+ *
+ *
+ *
+    INTERACTIVE MODULE
+
+    --interactive is for trying things out by hand: hurl shows the request it is about to make
+    and asks before sending it, and any parameter whose value is just ? is asked for first
+
+    e.g. hurl --interactive POST example.com/login username=ruben password=?
+    asks for the password, prints the request, and only sends it if the answer is y
+
+    PLACEHOLDERS
+
+    A placeholder is a parameter whose value, or file name for the parameters that read a file, is exactly ?
+    is_placeholder checks for that, and fill_placeholders asks for a value for each one using the key as the question
+
+    The answer replaces the ? as is, so key:=? still expects JSON and key=@? still expects a file name
+
+    PROMPTING
+
+    The questions go through the Prompt trait rather than straight to the terminal,
+    TtyPrompt is the real thing which writes the question to stderr and reads a line from stdin,
+    while the tests use a prompt with canned answers
+
+    This is the same kind of interaction as parse_auth asking for a password with rpassword,
+    except that the answers are echoed as there is no reason to hide them
+
+    confirm prints the method, URL, and parameters, and anything other than y or yes means no
+
+    WHEN IT RUNS
+
+    main calls prepare after any preset has been applied and saved,
+    so a preset can keep its placeholders and ask for them every time it is used
+
+    Prompting only makes sense when someone is there to answer,
+    so when stdin is not a terminal --interactive is ignored and the request is made as it is
+
+***/
+
+use crate::app::{App, Parameter};
+use crate::errors::HurlResult;
+use log::debug;
+use std::io::{self, BufRead, Write};
+
+pub const PLACEHOLDER: &str = "?";
+
+pub trait Prompt {
+    fn ask(&mut self, question: &str) -> HurlResult<String>;
+}
+
+pub struct TtyPrompt;
+
+impl Prompt for TtyPrompt {
+    fn ask(&mut self, question: &str) -> HurlResult<String> {
+        eprint!("{}", question);
+        io::stderr().flush()?;
+
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+
+        Ok(answer.trim_end_matches(|c| c == '\r' || c == '\n').to_owned())
+    }
+}
+
+pub fn prepare<P: Prompt>(app: &mut App, prompt: &mut P) -> HurlResult<bool> {
+    if !atty::is(atty::Stream::Stdin) {
+        debug!("Not prompting as stdin is not a terminal");
+        return Ok(true);
+    }
+
+    let (method, url, parameters) = match &mut app.cmd {
+        Some(cmd) => {
+            let name = cmd.name().to_owned();
+            let data = cmd.data_mut();
+            (name, data.url.clone(), &mut data.parameters)
+        }
+        None => {
+            let name = app.request_method().to_string();
+            (name, app.url.clone().unwrap_or_default(), &mut app.parameters)
+        }
+    };
+
+    fill_placeholders(prompt, parameters)?;
+
+    confirm(prompt, &method, &url, parameters)
+}
+
+pub fn is_placeholder(param: &Parameter) -> bool {
+    let value = match param {
+        Parameter::Header { value, .. } => value,
+        Parameter::Data { value, .. } => value,
+        Parameter::RawJsonData { value, .. } => value,
+        Parameter::Query { value, .. } => value,
+        Parameter::FormFile { filename, .. } => filename,
+        Parameter::DataFile { filename, .. } => filename,
+        Parameter::RawJsonDataFile { filename, .. } => filename,
+    };
+
+    value == PLACEHOLDER
+}
+
+pub fn fill_placeholders<P: Prompt>(prompt: &mut P, parameters: &mut Vec<Parameter>) -> HurlResult<()> {
+    for param in parameters.iter_mut().filter(|p| is_placeholder(p)) {
+        let answer = prompt.ask(&format!("{}: ", param.key()))?;
+
+        match param {
+            Parameter::Header { value, .. }
+            | Parameter::Data { value, .. }
+            | Parameter::RawJsonData { value, .. }
+            | Parameter::Query { value, .. } => *value = answer,
+            Parameter::FormFile { filename, .. }
+            | Parameter::DataFile { filename, .. }
+            | Parameter::RawJsonDataFile { filename, .. } => *filename = answer,
+        }
+    }
+
+    Ok(())
+}
+
+pub fn confirm<P: Prompt>(
+    prompt: &mut P,
+    method: &str,
+    url: &str,
+    parameters: &[Parameter]
+) -> HurlResult<bool> {
+    eprintln!("{} {}", method, url);
+
+    for param in parameters {
+        eprintln!("  {}", describe(param));
+    }
+
+    let answer = prompt.ask("Send this request? [y/N] ")?;
+
+    Ok(is_yes(&answer))
+}
+
+fn is_yes(answer: &str) -> bool {
+    let answer = answer.trim().to_lowercase();
+
+    answer == "y" || answer == "yes"
+}
+
+fn describe(param: &Parameter) -> String {
+    match param {
+        Parameter::Header { key, value } => format!("{}:{}", key, value),
+        Parameter::Data { key, value } => format!("{}={}", key, value),
+        Parameter::RawJsonData { key, value } => format!("{}:={}", key, value),
+        Parameter::Query { key, value } => format!("{}=={}", key, value),
+        Parameter::FormFile { key, filename } => format!("{}@{}", key, filename),
+        Parameter::DataFile { key, filename } => format!("{}=@{}", key, filename),
+        Parameter::RawJsonDataFile { key, filename } => format!("{}:=@{}", key, filename),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    struct CannedPrompt {
+        answers: VecDeque<&'static str>,
+        questions: Vec<String>,
+    }
+
+    impl CannedPrompt {
+        fn new(answers: &[&'static str]) -> Self {
+            CannedPrompt {
+                answers: answers.iter().cloned().collect(),
+                questions: Vec::new(),
+            }
+        }
+    }
+
+    impl Prompt for CannedPrompt {
+        fn ask(&mut self, question: &str) -> HurlResult<String> {
+            self.questions.push(question.to_owned());
+            Ok(self.answers.pop_front().unwrap_or("").to_owned())
+        }
+    }
+
+    fn data(key: &str, value: &str) -> Parameter {
+        Parameter::Data {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        }
+    }
+
+    #[test]
+    fn is_placeholder_only_matches_a_lone_question_mark() {
+        assert!(is_placeholder(&data("password", "?")));
+        assert!(is_placeholder(&Parameter::DataFile {
+            key: "notes".to_owned(),
+            filename: "?".to_owned(),
+        }));
+        assert!(!is_placeholder(&data("question", "why?")));
+        assert!(!is_placeholder(&data("empty", "")));
+        assert!(!is_placeholder(&Parameter::Query {
+            key: "?".to_owned(),
+            value: "1".to_owned(),
+        }));
+    }
+
+    #[test]
+    fn fill_placeholders_asks_for_each_placeholder_in_order() {
+        let mut prompt = CannedPrompt::new(&["hunter2", "[1,2]"]);
+        let mut params = vec![
+            data("username", "ruben"),
+            data("password", "?"),
+            Parameter::RawJsonData {
+                key: "ids".to_owned(),
+                value: "?".to_owned(),
+            },
+        ];
+
+        fill_placeholders(&mut prompt, &mut params).unwrap();
+
+        assert_eq!(prompt.questions, vec!["password: ", "ids: "]);
+        assert_eq!(
+            params,
+            vec![
+                data("username", "ruben"),
+                data("password", "hunter2"),
+                Parameter::RawJsonData {
+                    key: "ids".to_owned(),
+                    value: "[1,2]".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn confirm_only_accepts_yes() {
+        for (answer, expected) in &[("y", true), ("YES", true), (" y ", true), ("", false), ("n", false), ("sure", false)] {
+            let mut prompt = CannedPrompt::new(&[answer]);
+
+            assert_eq!(confirm(&mut prompt, "GET", "http://localhost", &[]).unwrap(), *expected);
+        }
+    }
+}
@@ -0,0 +1,174 @@
+/***
+ *
+ *
+ *
+    JSON PATH MODULE
+
+    A script often only needs one value out of a response, like the id of what was just created,
+    and piping the whole body through jq for that is one more tool to have installed
+
+    With --jq-path only the value at the path is printed, without the status and headers:
+
+        hurl --jq-path data.items.0.name example.com/items
+
+    THE PATH
+
+    A path is a list of steps separated by dots, each one the key of an object or the index of an array,
+    so data.items.0.name is the name of the first item in the items of data
+
+    An index can also be written in brackets, data.items[0].name, which is the same path
+    A leading dot is allowed as jq writes its paths that way, and an empty path or a lone dot is the whole body
+
+    A step of digits is an index when the value is an array and a key when it is an object,
+    as an object is free to have keys like "0"
+
+    Anything else in brackets, or brackets that aren't closed, is Error::InvalidJsonPath
+
+    FOLLOWING THE PATH
+
+    select walks the steps one at a time from the top of the body
+    The first step that has nothing to follow, a missing key, an index past the end of the array,
+    or any step into a string, number, or the like, stops the walk with Error::JsonPathNotFound
+    naming the path up to and including that step, so it is clear which part of the path went wrong
+
+    main returns the error so hurl exits non-zero and a script can tell nothing was found
+
+    PRINTING THE VALUE
+
+    A string is printed without its quotes so it can be used as it is, e.g. in $(hurl ...),
+    anything else is printed as JSON with the same indentation as a whole body would be
+
+***/
+
+use crate::errors::{Error, HurlResult};
+use serde_json::Value;
+
+#[derive(Debug, PartialEq)]
+enum Step<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+pub fn select<'a>(value: &'a Value, path: &str) -> HurlResult<&'a Value> {
+    let mut current = value;
+    let mut walked = String::new();
+
+    for step in steps(path)? {
+        let next = match step {
+            Step::Key(key) => {
+                if !walked.is_empty() {
+                    walked.push('.');
+                }
+                walked.push_str(key);
+
+                match current {
+                    Value::Object(fields) => fields.get(key),
+                    Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+                    _ => None,
+                }
+            }
+            Step::Index(index) => {
+                walked.push_str(&format!("[{}]", index));
+                current.as_array().and_then(|items| items.get(index))
+            }
+        };
+
+        current = next.ok_or_else(|| Error::JsonPathNotFound(walked.clone()))?;
+    }
+
+    Ok(current)
+}
+
+fn steps(path: &str) -> HurlResult<Vec<Step<'_>>> {
+    let invalid = || Error::InvalidJsonPath(path.to_owned());
+    let trimmed = path.strip_prefix('.').unwrap_or(path);
+    let mut steps = Vec::new();
+
+    if trimmed.is_empty() {
+        return Ok(steps);
+    }
+
+    for segment in trimmed.split('.') {
+        let (key, mut rest) = match segment.find('[') {
+            Some(start) => segment.split_at(start),
+            None => (segment, ""),
+        };
+
+        if key.is_empty() && rest.is_empty() {
+            return Err(invalid());
+        }
+
+        if !key.is_empty() {
+            steps.push(Step::Key(key));
+        }
+
+        while !rest.is_empty() {
+            let end = rest.find(']').ok_or_else(invalid)?;
+            let index = rest[1..end].parse::<usize>().map_err(|_| invalid())?;
+            steps.push(Step::Index(index));
+
+            rest = &rest[end + 1..];
+
+            if !rest.is_empty() && !rest.starts_with('[') {
+                return Err(invalid());
+            }
+        }
+    }
+
+    Ok(steps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn body() -> Value {
+        json!({
+            "data": {
+                "items": [
+                    {"name": "first", "tags": ["a", "b"]},
+                    {"name": "second", "tags": []}
+                ],
+                "total": 2,
+                "0": "a key that looks like an index"
+            }
+        })
+    }
+
+    #[test]
+    fn select_follows_objects_and_arrays() {
+        let body = body();
+
+        assert_eq!(select(&body, "data.total").unwrap(), &json!(2));
+        assert_eq!(select(&body, "data.items.0.name").unwrap(), &json!("first"));
+        assert_eq!(select(&body, "data.items[1].name").unwrap(), &json!("second"));
+        assert_eq!(select(&body, ".data.items.0.tags[1]").unwrap(), &json!("b"));
+        assert_eq!(select(&body, "data.0").unwrap(), &json!("a key that looks like an index"));
+        assert_eq!(select(&body, ".").unwrap(), &body);
+        assert_eq!(select(&json!([[1, 2], [3]]), "[0][1]").unwrap(), &json!(2));
+    }
+
+    #[test]
+    fn select_names_the_missing_part_of_the_path() {
+        let body = body();
+        let missing = |path: &str| match select(&body, path) {
+            Err(Error::JsonPathNotFound(walked)) => walked,
+            other => panic!("expected JsonPathNotFound for {}, got {:?}", path, other),
+        };
+
+        assert_eq!(missing("data.items.5.name"), "data.items.5");
+        assert_eq!(missing("data.items[2]"), "data.items[2]");
+        assert_eq!(missing("data.nope.name"), "data.nope");
+        assert_eq!(missing("data.total.value"), "data.total.value");
+    }
+
+    #[test]
+    fn select_rejects_a_malformed_path() {
+        let body = body();
+
+        for path in &["data..items", "data.items[x]", "data.items[0", "data.items[0]name"] {
+            assert!(matches!(select(&body, path), Err(Error::InvalidJsonPath(_))), "{}", path);
+        }
+    }
+}
@@ -110,13 +110,23 @@
 
     Using the log_level method on the app will get a value to set up logging
 
-    The pretty_env_logger crate uses environment variables to configure what to print,
-    so the RUST_LOG environment variable is explicitly set to the value acquired
+    The pretty_env_logger crate is normally configured through the RUST_LOG environment variable,
+    instead init_logger takes its formatted_builder and hands the filter from log_filter straight to parse_filters,
+    so what is logged only depends on the command line and not on what happens to be in the environment
 
-    The format is RUST_LOG=binary_name=level
+    The filter has the form binary_name=level
     where binary_name is the name of the binary
     and level is one of the five level values that log defines: trace, debug, info, warn, and error
 
+    --quiet makes log_filter None, in which case no logger is set up at all and every log macro is a no-op
+
+    LOGGING AS JSON
+
+    With --log-stderr-json the format of the builder is replaced so each message is a line of JSON on stderr,
+    e.g. {"level":"DEBUG","message":"Parsed url: http://example.com/","target":"hurl::client","timestamp":1602835200000}
+    with the timestamp in milliseconds since the Unix epoch like in the --log-file entries,
+    which is easy for another program to read while the response itself still goes to stdout
+
     CORE OF THE APP
 
     Use the cmd (short for command), property on our app to direct what type of request to make
@@ -215,6 +225,32 @@
     This is because of the type alias for OrderedJson telling serde that it should use a BTreeMap
     as the container for the top level JSON object
 
+    JSON WITH THE WRONG CONTENT TYPE
+
+    Some servers send JSON as text/plain or even text/html, so the Content-Type alone can't be trusted
+
+    pretty_json decides whether to treat the body as JSON:
+    a JSON Content-Type, application/json or anything ending in +json, always is,
+    and otherwise the body is if it starts with { or [ once leading whitespace is trimmed off
+    An HTML page starts with < so it is never mistaken for JSON
+
+    Either way the body still has to parse, anything that doesn't is printed as plain text
+
+    With --strict-content-type only the Content-Type counts, so JSON sent as text/plain is printed as it came
+
+    The body is parsed into an OrderedJson when it is an object, so the keys are sorted as before,
+    and into a plain serde_json::Value otherwise, which lets a top level array be pretty printed too
+
+    INDENTING THE JSON
+
+    serde_json::to_string_pretty always indents by two spaces,
+    so --indent 4 goes through json_string which builds a serde_json::Serializer
+    with a PrettyFormatter using four spaces as its indent instead
+
+    --indent 0 or --compact prints the whole body on one line with serde_json::to_string,
+    a PrettyFormatter with an empty indent would still put every value on its own line
+    The default of 2 gives exactly what to_string_pretty did before
+
     ADDING CONFIGURATION TO THE APP
 
     Calling process_config_file after parsing and validating the command line arguments
@@ -242,6 +278,33 @@
     because the session is needed to possibly fill in data in the request
     and the session will be filled in with other data provided as part of the request as parameters
 
+    PRESETS
+
+    Right after the configuration is processed a preset given with --preset is loaded
+    and applied to the app, so everything after that point sees the request as if it was typed out
+
+    When --save-preset is given the request is saved before it is sent,
+    that way a request that fails can still be fixed up and replayed
+
+    The parameters from --params-file are merged in after the preset is applied,
+    so a saved preset includes them just like it includes the command line parameters
+
+    With --interactive the placeholders are filled in and the request is confirmed after the preset is saved,
+    see the interactive module, and answering no ends the program without making a request
+
+    Path parameters like :id are substituted into the URL last, so a placeholder for one can be answered interactively
+
+    MORE THAN ONE URL
+
+    Without a method subcommand, the first URL and any extra_urls are sent one after another by dispatch_all
+    with the same method, parameters, session, and client settings, and each one is logged like a single request
+
+    With more than one URL each response is preceded by a separator line like ==> GET example.com/a
+    so it is clear which response belongs to which URL
+
+    A request that fails doesn't stop the rest, the error is printed and the first one is returned at the end
+    so the exit status still says something went wrong
+
     ADDING SYNTAX HIGHLIGHTING
     
     The syntect dependency is added to for a bit of polish to the application
@@ -279,34 +342,289 @@
     Finally, print out the terminal reset character which ends all highlighting
     and puts the user back into normal shell mode,
     so that the highlighting isn't leaked onto later shell commands
+
+    SILENT SUCCESS
+
+    For scripts and cron health checks the --silent-success flag turns handle_response inside out
+
+    A 2xx response prints nothing and main returns Ok so the exit code is 0
+    The same goes for a 1xx or 3xx, e.g. a 304 Not Modified for a conditional request is not a failure,
+    and redirects that are followed never get here anyway
+
+    A 4xx or 5xx is printed in full as usual,
+    and then the status is returned as Error::ClientWithStatus so that main exits non-zero
+
+    Transport failures never reach handle_response and are already reported through the ? operator
+
+    The session is still updated in both cases so cookies keep flowing in scripted runs
+
+    SHOWING COOKIES
+
+    Cookies arrive as Set-Cookie headers, which are printed like any other header
+    but are hard to read with all of their attributes crammed into one line
+
+    With --show-cookies, or any level of --verbose, a separate Cookies section is printed after the headers
+    with one cookie per line, using the same resp.cookies() iterator the session module uses to remember them
+
+    Each line has the name and value followed by whichever of the domain, path,
+    and expiry attributes the server sent, plus the Secure and HttpOnly flags
+
+    reqwest hands back the expiry as a SystemTime which has no nice display format,
+    so it is shown as seconds since the Unix epoch
+
+    LIMITING THE RESPONSE SIZE
+
+    resp.text() reads the entire body into a String no matter how big it is
+
+    When --max-response-size is given read_body reads the body itself instead
+    If the server says up front in Content-Length that the body is too big we give up before reading anything,
+    otherwise the Response is used as a Read and wrapped with take so that at most one byte more than the limit is read
+
+    Getting that extra byte means the body is over the limit and Error::ResponseTooLarge is returned
+    without the rest of the body ever being buffered
+
+    DECODING A COMPRESSED BODY
+
+    With --compressed reqwest leaves the body alone, see client.rs, so what read_body hands back are the bytes on the wire
+    decode_body then looks at the Content-Encoding header:
+        - gzip (or the old x-gzip) goes through a GzDecoder
+        - deflate is supposed to be zlib wrapped but some servers send the raw stream,
+          so a ZlibDecoder is tried first and a DeflateDecoder if that fails
+        - anything else, including no Content-Encoding at all, is left as it is
+
+    The limit from --max-response-size also applies to the decoded body,
+    otherwise a small response could inflate into something huge
+
+    handle_response prints both sizes under the transfer summary,
+    e.g. Decoded 12.4 KB from 2.1 KB of gzip
+
+    THE SUMMARY LINE
+
+    With --summary, or summary = true in the config file, a line like
+        GET https://example.com/ -> 200 OK (123ms, 4.5 KB)
+    is written to stderr once the response has been handled, whatever the verbosity
+    so it doesn't get mixed up with the body when stdout is piped somewhere,
+    and --no-summary turns it off again for one run when the config file turns it on
+
+    format_summary builds the line from the method, the final URL of the response, the status,
+    the time since the request was started, and the number of bytes received
+    The bytes are the ones on the wire, so with --compressed it is the compressed size
+    With --silent-success the body is never read so the Content-Length is used, or 0 without one
+
+    finish_response prints it because every way through handle_response ends there,
+    except the silent success one which prints it itself
+
+    BINARY BODIES
+
+    The body is read as raw bytes rather than with resp.text(),
+    which would replace anything that isn't valid text with replacement characters
+
+    It is treated as binary when the Content-Type says so, e.g. image/png or application/octet-stream,
+    or when the bytes are not valid UTF-8 and the Content-Type is not some kind of text
+
+    Text that isn't quite UTF-8 is still printed with from_utf8_lossy,
+    which does not look at the charset of the response like text() does
+
+    Rather than dumping garbage into the terminal a binary body is summarized with its size,
+    followed by a hex dump of the first BINARY_PREVIEW_BYTES bytes in the same layout as xxd:
+    the offset, sixteen bytes as pairs of hex digits, and the printable ASCII characters with dots for the rest
+
+    LOGGING REQUESTS
+
+    The result of client::perform is kept as a Result for a moment rather than using ? straight away,
+    so that request_log::record can write the outcome to the --log-file whether the request worked or not
+    Only then is the error returned or the response handled as before
+
+    DUMPING HEADERS AS JSON
+
+    With --dump-headers <path> the response headers are also written to the file as a JSON object,
+    which is much easier for a script to pick apart than the highlighted text on stdout
+
+    headers_json goes over the same resp.headers() as the display code, but builds an OrderedJson
+    so the names come out sorted, the names are kept lowercase as reqwest stores them
+
+    A header that appears once maps to a string and one that appears more than once,
+    like Set-Cookie often does, maps to an array of its values in the order they were received
+
+    The file is written first thing in handle_response so it is there even with --silent-success,
+    and the body is printed to stdout as usual
+
+    HIDING ERROR BODIES
+
+    Error responses often come with a whole HTML page for a body which buries the status and headers
+
+    With --no-body-on-error a 4xx or 5xx response only has its status and headers printed,
+    and with --error-body-limit <bytes> only the first that many bytes of the body are printed
+    Successful responses are always printed in full
+
+    visible_body decides how much of the body to show, None meaning none of it,
+    and whatever is left out is mentioned with its size so it is clear something was skipped
+
+    A truncated JSON body usually won't parse any more so it gets printed as plain text
+    The exit status is not affected, that is still up to --silent-success
+
+    JSON ARRAYS AS TABLES
+
+    With --table a body that is a JSON array of objects is printed as a table instead of as JSON,
+    see the table module
+
+    The body is parsed again as a plain serde_json::Value for this,
+    as an OrderedJson is a map and a top level array doesn't parse into it
+    Anything format_table can't turn into a table is printed the usual way
+
+    FAILING WITH THE BODY
+
+    --fail-with-body is for scripts that need to stop on an error but still want to see why
+
+    The response is printed exactly as it would be without the flag, highlighting and all,
+    and only once that is done finish_response returns Error::ClientWithStatus for a 4xx or 5xx
+    so main exits non-zero, the same way --silent-success does
+
+    Hiding the body is the opposite of what the flag is for,
+    so it can't be combined with --no-body-on-error or --error-body-limit
+
+    DIFFING TWO RESPONSES
+
+    With --diff two requests are made and only the differences between their bodies are printed,
+    handy for spotting when an API changes its output or to compare two environments,
+    e.g. hurl --diff staging.example.com/users example.com/users
+
+    With a single URL, or a method subcommand, the same request is made twice,
+    and with two URLs each is requested once, validate turns away any more than that
+
+    diff_responses reads each body and pretty prints it if it is JSON, so a change to one field is one changed line,
+    then format_diff uses the similar crate to make a line based unified diff of the two
+    The --- and +++ lines name the request and status each side came from,
+    and the hint about a missing newline at the end is turned off as pretty printed JSON never has one
+
+    Identical bodies print No differences, and the exit status is 0 either way,
+    the requests are still logged and the session updated like any other request
+
+    SAVING BODIES TO A DIRECTORY
+
+    With --output-dir the status and headers are printed as usual,
+    but the body is written to a file in that directory instead of being printed
+
+    output_filename names the file after the path of the URL the response came from,
+    run through make_safe_pathname from the session module so it is safe to use as a file name,
+    e.g. example.com/users/1 is saved as users_1 and a URL with no path as index
+
+    Together with several URLs this downloads each of them into its own file,
+    and when a name is already taken, by an earlier file or by two URLs with the same path,
+    unique_path adds -1, -2, and so on until it finds one that is free
+
+    The directory is created if it doesn't exist yet
+
+    PRINTING A REPORT
+
+    With --format json or --format toml the response is printed as a single document, see the report module
+
+    The report is made as soon as the body is read, with the same headers_json map as --dump-headers,
+    and takes the place of all the other output, nothing is highlighted as the output is meant for other programs
+    The session, --silent-success, and --fail-with-body work the same as they do otherwise
+
+    PRINTING ONE VALUE
+
+    With --jq-path the body is parsed as JSON and only the value at the path is printed, see the json_path module
+
+    Like --format this is meant for scripts, so the status and headers are left out,
+    and it is handled right after the report in handle_response once the body has been read and decoded
+    A body that isn't JSON is a JSON error and nothing at the path is Error::JsonPathNotFound,
+    either way main returns the error and hurl exits non-zero
+
+    SIZE AND SPEED
+
+    Under the headers a line like Received 1.5 MB in 320 ms (4.7 MB/s) says how big the body was
+    and how quickly it came in
+
+    main already takes an Instant right before each request for the request log,
+    and it is passed on to handle_response so the time covers sending the request and reading the whole body
+
+    human_size shows bytes in B, KB, MB, or GB going up in steps of 1024 with one decimal place,
+    and transfer_summary puts the line together, leaving out the speed when no time has passed at all
+
+    CONDITIONAL REQUESTS
+
+    With --cache and a session, a response with an ETag or Last-Modified header is kept in the session,
+    and the next request to the same URL asks the server to only send the body if it has changed,
+    see the caching section of the session module
+
+    When the server answers 304 Not Modified, apply_cache hands back the body from the cache in place of the empty one
+    along with a CacheHit, and the usual output prints 304 Not Modified (cached) under the headers
+    and the body from the cache is printed in place of the empty one, using the cached content type so JSON is still pretty printed
+    Any other response is given to the session to update the cache, which is saved with the rest of the session
+
+    apply_cache runs right after the body is read and decoded on every path that uses the body,
+    --format, --jq-path, --diff, and the usual output, so a 304 never leaves any of them with an empty body
+    Only the usual output mentions the cache, the others are read by programs
 ***/
 
+
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
 use heck::TitleCase;
 use log::trace;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use structopt::StructOpt;
 use syntect::highlighting::Theme;
 use syntect::parsing::SyntaxSet;
 
 mod app;
+mod benchmark;
 mod client;
 mod config;
+mod connection;
 mod directories;
 mod errors;
+mod interactive;
+mod json_path;
+mod openapi;
+mod presets;
+mod progress;
+mod report;
+mod request_log;
 mod session;
 mod syntax;
+mod table;
 
-use errors::HurlResult;
+use errors::{Error, HurlResult};
 
 type OrderedJson = std::collections::BTreeMap<String, serde_json::Value>;
 
+const BINARY_PREVIEW_BYTES: usize = 256;
+
 fn main() -> HurlResult<()> {
     let mut app = app::App::from_args();
+
+    if let Some(shell) = app.generate_completions {
+        std::io::stdout().write_all(&app::completion_script(shell))?;
+        return Ok(());
+    }
+
     app.validate()?;
     app.process_config_file();
 
-    if let Some(level) = app.log_level() {
-        std::env::set_var("RUST_LOG", format!("hurl={}", level));
-        pretty_env_logger::init();
+    if let Some(name) = app.preset.clone() {
+        presets::Preset::load(&name)?.apply(&mut app)?;
+    }
+
+    app.process_openapi()?;
+    app.process_params_file()?;
+
+    if let Some(name) = app.save_preset.clone() {
+        presets::Preset::from_app(&app, name).save()?;
+    }
+
+    if app.interactive && !interactive::prepare(&mut app, &mut interactive::TtyPrompt)? {
+        eprintln!("Request not sent");
+        return Ok(());
+    }
+
+    app.process_path_params()?;
+
+    if let Some(filter) = app.log_filter() {
+        init_logger(&filter, app.log_stderr_json);
     }
 
     let (ss, ts) = syntax::build()?;
@@ -317,25 +635,167 @@ fn main() -> HurlResult<()> {
         .as_ref()
         .map(|name| session::Session::get_or_create(&app, name.clone(), app.host()));
 
+    let http_client = client::build_client(&app)?;
+
+    if let Some(count) = app.benchmark {
+        return benchmark::run(&app, &http_client, count, &mut session);
+    }
+
+    if app.diff {
+        return diff_responses(&app, &http_client, &mut session);
+    }
+
     match app.cmd {
         Some(ref method) => {
-            let resp = client::perform_method(&app, method, &mut session)?;
-            handle_response(&app, &ss, theme, resp, &mut session)
+            let sent_at = SystemTime::now();
+            let started = Instant::now();
+            let result = client::perform_method(&app, &http_client, method, &mut session);
+            let request_method: reqwest::Method = method.into();
+            request_log::record(&app, &request_method, &method.data().url, sent_at, started, &result)?;
+
+            handle_response(&app, &ss, theme, &request_method, result?, &mut session, started)
         }
         None => {
-            let url = app.url.take().unwrap();
-            let has_data = app.parameters.iter().any(|p| p.is_data());
+            let mut urls = vec![app.url.take().unwrap()];
+            urls.append(&mut app.extra_urls);
+            let method = app.request_method();
+
+            dispatch_all(&method, &urls, |url| {
+                let sent_at = SystemTime::now();
+                let started = Instant::now();
+                let result = client::perform(&app, &http_client, method.clone(), &mut session, url, &app.parameters);
+                request_log::record(&app, &method, url, sent_at, started, &result)?;
+
+                handle_response(&app, &ss, theme, &method, result?, &mut session, started)
+            })
+        }
+    }
+}
 
-            let method = if has_data {
-                reqwest::Method::POST
-            } else {
-                reqwest::Method::GET
-            };
+fn init_logger(filter: &str, json: bool) {
+    let mut builder = pretty_env_logger::formatted_builder();
+    builder.parse_filters(filter);
+
+    if json {
+        builder.format(|buf, record| {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0);
+            let entry = serde_json::json!({
+                "level": record.level().to_string(),
+                "target": record.target(),
+                "message": record.args().to_string(),
+                "timestamp": timestamp,
+            });
+
+            writeln!(buf, "{}", entry)
+        });
+    }
 
-            let resp = client::perform(&app, method, &mut session, &url, &app.parameters)?;
+    builder.init();
+}
 
-            handle_response(&app, &ss, theme, resp, &mut session)
+fn diff_responses(
+    app: &app::App,
+    http_client: &reqwest::Client,
+    session: &mut Option<session::Session>
+) -> HurlResult<()> {
+    let (method, urls, parameters) = match &app.cmd {
+        Some(cmd) => {
+            let data = cmd.data();
+            (cmd.into(), vec![data.url.clone(), data.url.clone()], &data.parameters)
         }
+        None => {
+            let url = app.url.clone().unwrap();
+            let other = app.extra_urls.first().cloned().unwrap_or_else(|| url.clone());
+            (app.request_method(), vec![url, other], &app.parameters)
+        }
+    };
+
+    let mut sides = Vec::new();
+
+    for url in &urls {
+        let sent_at = SystemTime::now();
+        let started = Instant::now();
+        let result = client::perform(app, http_client, method.clone(), session, url, parameters);
+        request_log::record(app, &method, url, sent_at, started, &result)?;
+
+        let mut resp = result?;
+        let body = read_body(&mut resp, app.max_response_size)?;
+        let body = if app.compressed {
+            decode_body(resp.headers(), body, app.max_response_size)?
+        } else {
+            body
+        };
+        let (body, cache_hit) = apply_cache(app, session, &resp, body);
+        update_session(app, &resp, session)?;
+
+        let content_type = cache_hit.as_ref().and_then(|hit| hit.content_type.as_deref()).unwrap_or_else(|| {
+            resp.headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("")
+        });
+        let body = String::from_utf8_lossy(&body).into_owned();
+        let body = pretty_json(content_type, &body, app.strict_content_type, app.json_indent()).unwrap_or(body);
+
+        sides.push((format!("{} {} ({})", method, url, resp.status()), body));
+    }
+
+    let (new_label, new) = sides.pop().unwrap();
+    let (old_label, old) = sides.pop().unwrap();
+
+    match format_diff(&old_label, &old, &new_label, &new) {
+        Some(diff) => print!("{}", diff),
+        None => println!("No differences"),
+    }
+
+    Ok(())
+}
+
+fn format_diff(old_label: &str, old: &str, new_label: &str, new: &str) -> Option<String> {
+    if old == new {
+        return None;
+    }
+
+    let diff = similar::TextDiff::from_lines(old, new);
+
+    Some(
+        diff.unified_diff()
+            .missing_newline_hint(false)
+            .header(old_label, new_label)
+            .to_string(),
+    )
+}
+
+fn dispatch_all<F>(method: &reqwest::Method, urls: &[String], mut send: F) -> HurlResult<()>
+where
+    F: FnMut(&str) -> HurlResult<()>,
+{
+    let mut first_error = None;
+
+    for (i, url) in urls.iter().enumerate() {
+        if urls.len() > 1 {
+            if i > 0 {
+                println!("");
+            }
+            println!("==> {} {}", method, url);
+        }
+
+        if let Err(e) = send(url) {
+            if urls.len() == 1 {
+                return Err(e);
+            }
+
+            eprintln!("{}", e);
+            first_error.get_or_insert(e);
+        }
+    }
+
+    match first_error {
+        Some(e) => Err(e),
+        None => Ok(()),
     }
 }
 
@@ -343,11 +803,60 @@ fn handle_response(
     app: &app::App,
     ss: &SyntaxSet,
     theme: &Theme,
+    method: &reqwest::Method,
     mut resp: reqwest::Response,
-    session: &mut Option<session::Session>
+    session: &mut Option<session::Session>,
+    started: Instant
 ) -> HurlResult<()> {
     let status = resp.status();
 
+    if let Some(path) = &app.dump_headers {
+        let headers = serde_json::to_string_pretty(&headers_json(resp.headers()))?;
+        std::fs::write(path, headers)?;
+    }
+
+    if app.silent_success && !(status.is_client_error() || status.is_server_error()) {
+        trace!("Suppressing output for successful response");
+        let bytes = resp.content_length().unwrap_or(0);
+        print_summary(app, &format_summary(method, resp.url(), status, started.elapsed(), bytes));
+        return update_session(app, &resp, session);
+    }
+
+    if let Some(format) = app.format {
+        let body = read_body(&mut resp, app.max_response_size)?;
+        let summary = format_summary(method, resp.url(), status, started.elapsed(), body.len() as u64);
+        let body = if app.compressed {
+            decode_body(resp.headers(), body, app.max_response_size)?
+        } else {
+            body
+        };
+        let (body, _) = apply_cache(app, session, &resp, body);
+        let version = format!("{:?}", resp.version());
+        let report = report::Report::new(status, version, headers_json(resp.headers()), &body);
+        println!("{}", report::render(&report, format)?);
+
+        return finish_response(app, &resp, session, status, &summary);
+    }
+
+    if let Some(path) = &app.jq_path {
+        let body = read_body(&mut resp, app.max_response_size)?;
+        let summary = format_summary(method, resp.url(), status, started.elapsed(), body.len() as u64);
+        let body = if app.compressed {
+            decode_body(resp.headers(), body, app.max_response_size)?
+        } else {
+            body
+        };
+        let (body, _) = apply_cache(app, session, &resp, body);
+        let value = serde_json::from_slice::<serde_json::Value>(&body)?;
+
+        match json_path::select(&value, path)? {
+            serde_json::Value::String(s) => println!("{}", s),
+            other => println!("{}", json_string(other, app.json_indent())?),
+        }
+
+        return finish_response(app, &resp, session, status, &summary);
+    }
+
     let mut s = format!(
         "{:?} {} {}\n",
         resp.version(),
@@ -367,11 +876,18 @@ fn handle_response(
         ));
     }
 
-    let result = resp.text()?;
+    let body = read_body(&mut resp, app.max_response_size)?;
+    let wire_len = body.len();
+    let summary = format_summary(method, resp.url(), status, started.elapsed(), wire_len as u64);
+    let body = if app.compressed {
+        decode_body(resp.headers(), body, app.max_response_size)?
+    } else {
+        body
+    };
 
     let content_length = match resp.content_length() {
         Some(len) => len,
-        None => result.len() as u64,
+        None => body.len() as u64,
     };
 
     headers.push(format!(
@@ -383,25 +899,467 @@ fn handle_response(
     s.push_str(&(&headers[..]).join("\n"));
     highlight_string(ss, theme, "HTTP", &s);
 
+    println!("{}", transfer_summary(wire_len as u64, started.elapsed()));
+
+    if let Some(encoding) = content_encoding(resp.headers()).filter(|_| app.compressed) {
+        println!(
+            "Decoded {} from {} of {}",
+            human_size(body.len() as u64),
+            human_size(wire_len as u64),
+            encoding
+        );
+    }
+
+    let (body, cache_hit) = apply_cache(app, session, &resp, body);
+
+    if cache_hit.is_some() {
+        println!("304 Not Modified (cached)");
+    }
+
     println!("");
 
-    let result_json: serde_json::Result<OrderedJson> = serde_json::from_str(&result);
+    if app.show_cookies || app.verbose > 0 {
+        let cookies: Vec<String> = resp.cookies().map(|c| format_cookie(&c)).collect();
+
+        if !cookies.is_empty() {
+            println!("\nCookies:");
+
+            for cookie in cookies {
+                println!("  {}", cookie);
+            }
+
+            println!("");
+        }
+    }
+
+    if let Some(dir) = &app.output_dir {
+        std::fs::create_dir_all(dir)?;
+        let path = unique_path(dir, &output_filename(resp.url()));
+        std::fs::write(&path, &body)?;
+        println!("Saved {} bytes to {}", body.len(), path.display());
+
+        return finish_response(app, &resp, session, status, &summary);
+    }
+
+    let full_body = body;
+    let body = match visible_body(status, &full_body, app.no_body_on_error, app.error_body_limit) {
+        Some(body) => body,
+        None => {
+            println!("Body of {} bytes not shown", full_body.len());
+            return finish_response(app, &resp, session, status, &summary);
+        }
+    };
+
+    let content_type = cache_hit.as_ref().and_then(|hit| hit.content_type.as_deref()).unwrap_or_else(|| {
+        resp.headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+    });
+
+    if is_binary(content_type, body) {
+        println!("Binary body, {} bytes", body.len());
+        println!("{}", hex_dump(&body[..body.len().min(BINARY_PREVIEW_BYTES)]));
+
+        if body.len() > BINARY_PREVIEW_BYTES {
+            println!("...");
+        }
+
+        return finish_response(app, &resp, session, status, &summary);
+    }
 
-    match result_json {
-        Ok(result_value) => {
-            let result_str = serde_json::to_string_pretty(&result_value)?;
+    let result = String::from_utf8_lossy(&body).into_owned();
 
-            highlight_string(ss, theme, "JSON", &result_str);
+    let table = if app.table {
+        serde_json::from_str(&result)
+            .ok()
+            .and_then(|value| table::format_table(&value))
+    } else {
+        None
+    };
+
+    if let Some(table) = table {
+        print!("{}", table);
+    } else {
+        match pretty_json(content_type, &result, app.strict_content_type, app.json_indent()) {
+            Some(result_str) => {
+                highlight_string(ss, theme, "JSON", &result_str);
+            }
+            None => {
+                trace!("Not printing the body as JSON");
+                println!("{}", result);
+            }
         }
+    }
+
+    if body.len() < full_body.len() {
+        println!("... {} more bytes not shown", full_body.len() - body.len());
+    }
+
+    finish_response(app, &resp, session, status, &summary)
+}
+
+struct CacheHit {
+    content_type: Option<String>,
+}
+
+fn apply_cache(
+    app: &app::App,
+    session: &mut Option<session::Session>,
+    resp: &reqwest::Response,
+    body: Vec<u8>
+) -> (Vec<u8>, Option<CacheHit>) {
+    let s = match session {
+        Some(s) if app.cache => s,
+        _ => return (body, None),
+    };
+
+    let url = resp.url().as_str();
+
+    if let Some(cached) = s.cached_body(url, resp.status()) {
+        let hit = CacheHit {
+            content_type: cached.content_type.clone(),
+        };
+        return (cached.body.clone().into_bytes(), Some(hit));
+    }
+
+    s.update_cache(url, resp.status(), resp.headers(), &body);
+    (body, None)
+}
+
+fn finish_response(
+    app: &app::App,
+    resp: &reqwest::Response,
+    session: &mut Option<session::Session>,
+    status: reqwest::StatusCode,
+    summary: &str
+) -> HurlResult<()> {
+    print_summary(app, summary);
+    update_session(app, resp, session)?;
+
+    if (app.silent_success || app.fail_with_body) && (status.is_client_error() || status.is_server_error()) {
+        return Err(Error::ClientWithStatus(status));
+    }
+
+    Ok(())
+}
+
+fn print_summary(app: &app::App, summary: &str) {
+    if app.show_summary() {
+        eprintln!("{}", summary);
+    }
+}
+
+fn format_summary(
+    method: &reqwest::Method,
+    url: &reqwest::Url,
+    status: reqwest::StatusCode,
+    elapsed: std::time::Duration,
+    bytes: u64
+) -> String {
+    format!(
+        "{} {} -> {} {} ({}ms, {})",
+        method,
+        url,
+        status.as_u16(),
+        status.canonical_reason().unwrap_or("Unknown"),
+        elapsed.as_millis(),
+        human_size(bytes)
+    )
+}
+
+fn output_filename(url: &reqwest::Url) -> String {
+    let path = url.path().trim_matches('/');
+
+    if path.is_empty() {
+        return "index".to_owned();
+    }
+
+    session::make_safe_pathname(path)
+}
+
+fn unique_path(dir: &Path, filename: &str) -> PathBuf {
+    let mut path = dir.join(filename);
+    let mut counter = 1;
+
+    while path.exists() {
+        path = dir.join(format!("{}-{}", filename, counter));
+        counter += 1;
+    }
+
+    path
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn transfer_summary(bytes: u64, elapsed: std::time::Duration) -> String {
+    let secs = elapsed.as_secs_f64();
+    let summary = format!("Received {} in {} ms", human_size(bytes), elapsed.as_millis());
+
+    if secs > 0.0 {
+        format!("{} ({}/s)", summary, human_size((bytes as f64 / secs) as u64))
+    } else {
+        summary
+    }
+}
+
+fn visible_body(
+    status: reqwest::StatusCode,
+    body: &[u8],
+    no_body_on_error: bool,
+    error_body_limit: Option<usize>
+) -> Option<&[u8]> {
+    if !(status.is_client_error() || status.is_server_error()) {
+        return Some(body);
+    }
+
+    if no_body_on_error {
+        return None;
+    }
+
+    match error_body_limit {
+        Some(limit) => Some(&body[..body.len().min(limit)]),
+        None => Some(body),
+    }
+}
+
+fn headers_json(headers: &reqwest::header::HeaderMap) -> OrderedJson {
+    let mut json = OrderedJson::new();
+
+    for key in headers.keys() {
+        let mut values: Vec<serde_json::Value> = headers
+            .get_all(key)
+            .iter()
+            .map(|v| String::from_utf8_lossy(v.as_bytes()).into_owned().into())
+            .collect();
+
+        let value = if values.len() == 1 {
+            values.remove(0)
+        } else {
+            serde_json::Value::Array(values)
+        };
+
+        json.insert(key.as_str().to_owned(), value);
+    }
+
+    json
+}
+
+fn read_body(resp: &mut reqwest::Response, max_size: Option<u64>) -> HurlResult<Vec<u8>> {
+    let mut body = Vec::new();
+
+    let limit = match max_size {
+        Some(limit) => limit,
+        None => {
+            resp.read_to_end(&mut body)?;
+            return Ok(body);
+        }
+    };
+
+    if let Some(len) = resp.content_length() {
+        if len > limit {
+            return Err(Error::ResponseTooLarge(limit));
+        }
+    }
+
+    resp.by_ref().take(limit + 1).read_to_end(&mut body)?;
+
+    if body.len() as u64 > limit {
+        return Err(Error::ResponseTooLarge(limit));
+    }
+
+    Ok(body)
+}
+
+fn content_encoding(headers: &reqwest::header::HeaderMap) -> Option<&str> {
+    headers
+        .get(reqwest::header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|encoding| !encoding.is_empty() && !encoding.eq_ignore_ascii_case("identity"))
+}
+
+fn decode_body(
+    headers: &reqwest::header::HeaderMap,
+    body: Vec<u8>,
+    max_size: Option<u64>
+) -> HurlResult<Vec<u8>> {
+    let encoding = match content_encoding(headers) {
+        Some(encoding) => encoding.to_ascii_lowercase(),
+        None => return Ok(body),
+    };
+
+    trace!("Decoding a body with Content-Encoding: {}", encoding);
+
+    match encoding.as_str() {
+        "gzip" | "x-gzip" => inflate(GzDecoder::new(&body[..]), max_size),
+        "deflate" => inflate(ZlibDecoder::new(&body[..]), max_size)
+            .or_else(|_| inflate(DeflateDecoder::new(&body[..]), max_size)),
+        _ => {
+            trace!("Leaving the body as it is, can't decode {}", encoding);
+            Ok(body)
+        }
+    }
+}
+
+fn inflate<R: Read>(mut decoder: R, max_size: Option<u64>) -> HurlResult<Vec<u8>> {
+    let mut body = Vec::new();
+
+    let limit = match max_size {
+        Some(limit) => limit,
+        None => {
+            decoder.read_to_end(&mut body)?;
+            return Ok(body);
+        }
+    };
+
+    decoder.take(limit + 1).read_to_end(&mut body)?;
+
+    if body.len() as u64 > limit {
+        return Err(Error::ResponseTooLarge(limit));
+    }
+
+    Ok(body)
+}
+
+fn pretty_json(content_type: &str, body: &str, strict_content_type: bool, indent: usize) -> Option<String> {
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    let json_type = mime == "application/json" || mime.ends_with("+json");
+
+    let body = body.trim();
+    let looks_like_json = body.starts_with('{') || body.starts_with('[');
+
+    if !(json_type || (!strict_content_type && looks_like_json)) {
+        return None;
+    }
+
+    let pretty = match serde_json::from_str::<OrderedJson>(body) {
+        Ok(object) => json_string(&object, indent),
+        Err(_) => serde_json::from_str::<serde_json::Value>(body)
+            .and_then(|value| json_string(&value, indent)),
+    };
+
+    match pretty {
+        Ok(pretty) => Some(pretty),
         Err(e) => {
             trace!("Failed to parse result to JSON: {}", e);
-            println!("{}", result);
+            None
         }
     }
+}
+
+fn json_string<T: serde::Serialize>(value: &T, indent: usize) -> serde_json::Result<String> {
+    if indent == 0 {
+        return serde_json::to_string(value);
+    }
+
+    let indent = " ".repeat(indent);
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+    let mut ser = serde_json::Serializer::with_formatter(Vec::new(), formatter);
+    value.serialize(&mut ser)?;
+
+    Ok(String::from_utf8(ser.into_inner()).expect("serde_json only writes valid UTF-8"))
+}
+
+fn is_binary(content_type: &str, body: &[u8]) -> bool {
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+
+    let binary_type = mime.starts_with("image/")
+        || mime.starts_with("audio/")
+        || mime.starts_with("video/")
+        || mime.starts_with("font/")
+        || mime == "application/octet-stream"
+        || mime == "application/pdf"
+        || mime == "application/zip"
+        || mime == "application/gzip";
+
+    if binary_type {
+        return true;
+    }
+
+    std::str::from_utf8(body).is_err() && !mime.starts_with("text/")
+}
+
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut lines = Vec::new();
+
+    for (i, chunk) in bytes.chunks(16).enumerate() {
+        let mut hex = String::new();
+
+        for (j, b) in chunk.iter().enumerate() {
+            if j > 0 && j % 2 == 0 {
+                hex.push(' ');
+            }
+            hex.push_str(&format!("{:02x}", b));
+        }
+
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+            .collect();
 
+        lines.push(format!("{:08x}: {:<39}  {}", i * 16, hex, ascii));
+    }
+
+    lines.join("\n")
+}
+
+fn format_cookie(cookie: &reqwest::cookie::Cookie) -> String {
+    let mut s = format!("{}={}", cookie.name(), cookie.value());
+
+    if let Some(domain) = cookie.domain() {
+        s.push_str(&format!("; Domain={}", domain));
+    }
+
+    if let Some(path) = cookie.path() {
+        s.push_str(&format!("; Path={}", path));
+    }
+
+    if let Some(max_age) = cookie.max_age() {
+        s.push_str(&format!("; Max-Age={}", max_age.as_secs()));
+    }
+
+    if let Some(expires) = cookie.expires() {
+        if let Ok(since_epoch) = expires.duration_since(UNIX_EPOCH) {
+            s.push_str(&format!("; Expires={}", since_epoch.as_secs()));
+        }
+    }
+
+    if cookie.secure() {
+        s.push_str("; Secure");
+    }
+
+    if cookie.http_only() {
+        s.push_str("; HttpOnly");
+    }
+
+    s
+}
+
+fn update_session(
+    app: &app::App,
+    resp: &reqwest::Response,
+    session: &mut Option<session::Session>
+) -> HurlResult<()> {
     if !app.read_only {
         if let Some(s) = session {
-            s.update_with_response(&resp);
+            s.update_with_response(resp);
             s.save(app)?;
         }
     }
@@ -425,4 +1383,385 @@ fn highlight_string(ss: &SyntaxSet, theme: &Theme, syntax: &str, string: &str) {
     }
 
     println!("\x1b[0m");
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, SET_COOKIE};
+
+    #[test]
+    fn headers_json_collects_duplicate_headers_into_arrays() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.append(SET_COOKIE, HeaderValue::from_static("a=1"));
+        headers.append(SET_COOKIE, HeaderValue::from_static("b=2"));
+
+        let json = serde_json::to_value(headers_json(&headers)).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "content-type": "application/json",
+                "set-cookie": ["a=1", "b=2"],
+            })
+        );
+    }
+
+    #[test]
+    fn dispatch_all_sends_every_url_and_returns_the_first_error() {
+        let urls = vec![
+            "example.com/a".to_owned(),
+            "example.com/b".to_owned(),
+            "example.com/c".to_owned(),
+        ];
+        let mut sent = Vec::new();
+
+        let result = dispatch_all(&reqwest::Method::GET, &urls, |url| {
+            sent.push(url.to_owned());
+            if url.ends_with('a') {
+                Ok(())
+            } else {
+                Err(Error::MissingPathParameter(url.to_owned()))
+            }
+        });
+
+        assert_eq!(sent, urls);
+        match result {
+            Err(Error::MissingPathParameter(url)) => assert_eq!(url, "example.com/b"),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    fn serve_once(response: &'static str) -> String {
+        use std::io::Write;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 1024];
+            let _ = stream.read(&mut buffer);
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+
+        format!("http://{}/", addr)
+    }
+
+    #[test]
+    fn fail_with_body_prints_the_body_and_fails() {
+        let body = b"{\"error\":\"broken\"}";
+        let app = app::App::from_iter(&["hurl", "--fail-with-body", "example.com"]);
+
+        assert_eq!(
+            visible_body(reqwest::StatusCode::INTERNAL_SERVER_ERROR, body, app.no_body_on_error, app.error_body_limit),
+            Some(&body[..])
+        );
+
+        let url = serve_once(
+            "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 18\r\nContent-Type: application/json\r\n\r\n{\"error\":\"broken\"}",
+        );
+        let resp = reqwest::get(&url).unwrap();
+        let (ss, ts) = syntax::build().unwrap();
+
+        match handle_response(&app, &ss, &ts.themes["Solarized (dark)"], &reqwest::Method::GET, resp, &mut None, Instant::now()) {
+            Err(Error::ClientWithStatus(status)) => assert_eq!(status.as_u16(), 500),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn silent_success_is_quiet_on_2xx_and_fails_on_5xx() {
+        let app = app::App::from_iter(&["hurl", "--silent-success", "example.com"]);
+        let (ss, ts) = syntax::build().unwrap();
+        let theme = &ts.themes["Solarized (dark)"];
+
+        let url = serve_once("HTTP/1.1 200 OK\r\nContent-Length: 2\r\nContent-Type: application/json\r\n\r\n{}");
+        let resp = reqwest::get(&url).unwrap();
+
+        assert!(handle_response(&app, &ss, theme, &reqwest::Method::GET, resp, &mut None, Instant::now()).is_ok());
+
+        let url = serve_once("HTTP/1.1 304 Not Modified\r\n\r\n");
+        let resp = reqwest::get(&url).unwrap();
+
+        assert!(handle_response(&app, &ss, theme, &reqwest::Method::GET, resp, &mut None, Instant::now()).is_ok());
+
+        let url = serve_once(
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 18\r\nContent-Type: application/json\r\n\r\n{\"error\":\"broken\"}",
+        );
+        let resp = reqwest::get(&url).unwrap();
+
+        match handle_response(&app, &ss, theme, &reqwest::Method::GET, resp, &mut None, Instant::now()) {
+            Err(Error::ClientWithStatus(status)) => assert_eq!(status.as_u16(), 503),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn format_cookie_lists_the_attributes_that_are_set() {
+        let url = serve_once(
+            "HTTP/1.1 200 OK\r\n\
+             Set-Cookie: session=abc123; Domain=example.com; Path=/api; Max-Age=3600; Expires=Wed, 21 Oct 2015 07:28:00 GMT; Secure; HttpOnly\r\n\
+             Set-Cookie: theme=dark\r\n\
+             Content-Length: 0\r\n\r\n",
+        );
+        let resp = reqwest::get(&url).unwrap();
+        let cookies: Vec<String> = resp.cookies().map(|c| format_cookie(&c)).collect();
+
+        assert_eq!(
+            cookies,
+            vec![
+                "session=abc123; Domain=example.com; Path=/api; Max-Age=3600; Expires=1445412480; Secure; HttpOnly",
+                "theme=dark",
+            ]
+        );
+    }
+
+    #[test]
+    fn read_body_stops_past_the_max_response_size() {
+        let with_length = "HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n0123456789";
+        let without_length = "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n0123456789";
+
+        let mut resp = reqwest::get(&serve_once(with_length)).unwrap();
+        assert!(matches!(read_body(&mut resp, Some(5)), Err(Error::ResponseTooLarge(5))));
+
+        let mut resp = reqwest::get(&serve_once(without_length)).unwrap();
+        assert!(matches!(read_body(&mut resp, Some(5)), Err(Error::ResponseTooLarge(5))));
+
+        let mut resp = reqwest::get(&serve_once(without_length)).unwrap();
+        assert_eq!(read_body(&mut resp, Some(10)).unwrap(), b"0123456789".to_vec());
+
+        let mut resp = reqwest::get(&serve_once(with_length)).unwrap();
+        assert_eq!(read_body(&mut resp, None).unwrap(), b"0123456789".to_vec());
+    }
+
+    #[test]
+    fn fail_with_body_conflicts_with_hiding_the_body() {
+        assert!(app::App::from_iter_safe(&["hurl", "--fail-with-body", "--no-body-on-error", "example.com"]).is_err());
+        assert!(app::App::from_iter_safe(&["hurl", "--fail-with-body", "--error-body-limit", "10", "example.com"]).is_err());
+    }
+
+    #[test]
+    fn format_diff_shows_changed_lines() {
+        let old = "{\n  \"name\": \"hurl\",\n  \"version\": 1\n}\n";
+        let new = "{\n  \"name\": \"hurl\",\n  \"version\": 2\n}\n";
+
+        let expected = [
+            "--- GET a (200 OK)",
+            "+++ GET b (200 OK)",
+            "@@ -1,4 +1,4 @@",
+            " {",
+            "   \"name\": \"hurl\",",
+            "-  \"version\": 1",
+            "+  \"version\": 2",
+            " }",
+            "",
+        ];
+
+        assert_eq!(
+            format_diff("GET a (200 OK)", old, "GET b (200 OK)", new).unwrap(),
+            expected.join("\n")
+        );
+    }
+
+    #[test]
+    fn format_diff_of_identical_bodies_is_none() {
+        assert!(format_diff("a", "same\n", "b", "same\n").is_none());
+    }
+
+    #[test]
+    fn output_filename_comes_from_the_url_path() {
+        let name = |url: &str| output_filename(&reqwest::Url::parse(url).unwrap());
+
+        assert_eq!(name("http://example.com/users/1"), "users_1");
+        assert_eq!(name("http://example.com/files/report.json?x=1"), "files_report_json");
+        assert_eq!(name("http://example.com/posts/"), "posts");
+        assert_eq!(name("http://example.com"), "index");
+    }
+
+    #[test]
+    fn unique_path_counts_up_past_taken_names() {
+        let dir = std::env::temp_dir().join(format!("hurl-output-dir-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(unique_path(&dir, "users_1"), dir.join("users_1"));
+
+        std::fs::write(dir.join("users_1"), "first").unwrap();
+        assert_eq!(unique_path(&dir, "users_1"), dir.join("users_1-1"));
+
+        std::fs::write(dir.join("users_1-1"), "second").unwrap();
+        assert_eq!(unique_path(&dir, "users_1"), dir.join("users_1-2"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn human_size_picks_a_unit() {
+        assert_eq!(human_size(0), "0 B");
+        assert_eq!(human_size(1023), "1023 B");
+        assert_eq!(human_size(1024), "1.0 KB");
+        assert_eq!(human_size(1536), "1.5 KB");
+        assert_eq!(human_size(5 * 1024 * 1024), "5.0 MB");
+        assert_eq!(human_size(3 * 1024 * 1024 * 1024), "3.0 GB");
+        assert_eq!(human_size(4096 * 1024 * 1024 * 1024), "4096.0 GB");
+    }
+
+    #[test]
+    fn format_summary_has_the_method_url_status_and_timing() {
+        use std::time::Duration;
+
+        let url = reqwest::Url::parse("https://example.com/users?page=2").unwrap();
+
+        assert_eq!(
+            format_summary(&reqwest::Method::GET, &url, reqwest::StatusCode::OK, Duration::from_millis(123), 4608),
+            "GET https://example.com/users?page=2 -> 200 OK (123ms, 4.5 KB)"
+        );
+        assert_eq!(
+            format_summary(
+                &reqwest::Method::DELETE,
+                &url,
+                reqwest::StatusCode::NOT_FOUND,
+                Duration::from_secs(2),
+                0
+            ),
+            "DELETE https://example.com/users?page=2 -> 404 Not Found (2000ms, 0 B)"
+        );
+
+        let app = app::App::from_iter(&["hurl", "--summary", "example.com"]);
+        assert!(app.show_summary());
+        assert!(app::App::from_iter_safe(&["hurl", "--summary", "--no-summary", "example.com"]).is_err());
+    }
+
+    #[test]
+    fn transfer_summary_works_out_the_speed() {
+        use std::time::Duration;
+
+        assert_eq!(
+            transfer_summary(2 * 1024 * 1024, Duration::from_millis(500)),
+            "Received 2.0 MB in 500 ms (4.0 MB/s)"
+        );
+        assert_eq!(
+            transfer_summary(512, Duration::from_secs(2)),
+            "Received 512 B in 2000 ms (256 B/s)"
+        );
+        assert_eq!(transfer_summary(10, Duration::from_secs(0)), "Received 10 B in 0 ms");
+    }
+
+    #[test]
+    fn decode_body_follows_the_content_encoding() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use reqwest::header::{HeaderMap, CONTENT_ENCODING};
+
+        let json = br#"{"name":"ruben","tags":["rust","rust","rust","rust"]}"#.to_vec();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&json).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(decode_body(&headers, json.clone(), None).unwrap(), json);
+
+        headers.insert(CONTENT_ENCODING, "gzip".parse().unwrap());
+        assert_eq!(decode_body(&headers, gzipped.clone(), None).unwrap(), json);
+        assert!(matches!(
+            decode_body(&headers, gzipped, Some(10)),
+            Err(Error::ResponseTooLarge(10))
+        ));
+
+        headers.insert(CONTENT_ENCODING, "br".parse().unwrap());
+        assert_eq!(decode_body(&headers, b"???".to_vec(), None).unwrap(), b"???".to_vec());
+    }
+
+    #[test]
+    fn hex_dump_shows_offsets_hex_and_ascii() {
+        let png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR\x00\x01";
+
+        assert_eq!(
+            hex_dump(png),
+            "00000000: 8950 4e47 0d0a 1a0a 0000 000d 4948 4452  .PNG........IHDR\n\
+             00000010: 0001                                     .."
+        );
+        assert_eq!(hex_dump(b""), "");
+    }
+
+    #[test]
+    fn is_binary_looks_at_the_content_type_then_the_bytes() {
+        let png = b"\x89PNG\r\n\x1a\n";
+
+        assert!(is_binary("image/png", b"looks like text"));
+        assert!(is_binary("application/octet-stream; charset=binary", b"abc"));
+        assert!(is_binary("", png));
+        assert!(is_binary("application/json", png));
+        assert!(!is_binary("text/plain", png));
+        assert!(!is_binary("application/json", b"{\"a\":1}"));
+        assert!(!is_binary("", "caf\u{e9}".as_bytes()));
+    }
+
+    #[test]
+    fn visible_body_hides_error_bodies_only() {
+        use reqwest::StatusCode;
+
+        let body = b"<html>something went wrong</html>";
+
+        assert_eq!(visible_body(StatusCode::NOT_FOUND, body, true, None), None);
+        assert_eq!(visible_body(StatusCode::BAD_GATEWAY, body, true, Some(6)), None);
+        assert_eq!(visible_body(StatusCode::OK, body, true, None), Some(&body[..]));
+    }
+
+    #[test]
+    fn visible_body_truncates_error_bodies_only() {
+        use reqwest::StatusCode;
+
+        let body = b"<html>something went wrong</html>";
+
+        assert_eq!(
+            visible_body(StatusCode::INTERNAL_SERVER_ERROR, body, false, Some(6)),
+            Some(&b"<html>"[..])
+        );
+        assert_eq!(visible_body(StatusCode::OK, body, false, Some(6)), Some(&body[..]));
+        assert_eq!(visible_body(StatusCode::NOT_FOUND, body, false, None), Some(&body[..]));
+    }
+
+    #[test]
+    fn pretty_json_ignores_a_wrong_content_type() {
+        assert_eq!(
+            pretty_json("text/plain; charset=utf-8", "  {\"b\":1,\"a\":[true]}\n", false, 2).unwrap(),
+            "{\n  \"a\": [\n    true\n  ],\n  \"b\": 1\n}"
+        );
+        assert_eq!(pretty_json("text/html", "[1]", false, 2).unwrap(), "[\n  1\n]");
+        assert_eq!(pretty_json("application/problem+json", "{}", true, 2).unwrap(), "{}");
+    }
+
+    #[test]
+    fn pretty_json_uses_the_indent() {
+        let body = r#"{"b":{"c":1},"a":[true]}"#;
+
+        assert_eq!(
+            pretty_json("application/json", body, false, 2).unwrap(),
+            "{\n  \"a\": [\n    true\n  ],\n  \"b\": {\n    \"c\": 1\n  }\n}"
+        );
+        assert_eq!(
+            pretty_json("application/json", body, false, 4).unwrap(),
+            "{\n    \"a\": [\n        true\n    ],\n    \"b\": {\n        \"c\": 1\n    }\n}"
+        );
+        assert_eq!(
+            pretty_json("application/json", body, false, 0).unwrap(),
+            r#"{"a":[true],"b":{"c":1}}"#
+        );
+
+        let app = app::App::from_iter(&["hurl", "--compact", "example.com"]);
+        assert_eq!(app.json_indent(), 0);
+        assert_eq!(app::App::from_iter(&["hurl", "example.com"]).json_indent(), 2);
+        assert!(app::App::from_iter_safe(&["hurl", "--compact", "--indent", "4", "example.com"]).is_err());
+    }
+
+    #[test]
+    fn pretty_json_leaves_other_bodies_alone() {
+        assert_eq!(pretty_json("text/html", "<html><body>{}</body></html>", false, 2), None);
+        assert_eq!(pretty_json("text/plain", "{not json", false, 2), None);
+        assert_eq!(pretty_json("text/plain", "{\"a\":1}", true, 2), None);
+        assert_eq!(pretty_json("", "42", false, 2), None);
+    }
+}
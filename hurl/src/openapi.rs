@@ -0,0 +1,304 @@
+/***
+ *
+ *
+ *
+    OPENAPI MODULE
+
+    Exploring an API described by an OpenAPI spec usually starts with copying the path,
+    the method, and the example body out of the spec by hand
+
+    --from-openapi takes the spec and --operation the operationId of one of its operations,
+    and the request is filled in from there:
+
+        hurl --from-openapi petstore.yaml --operation createPet
+
+    Only the basics are read: the method, the path, and the example body of the request,
+    there is no following of $ref, no checking against the schema, and nothing from the security schemes
+
+    READING THE SPEC
+
+    YAML is a superset of JSON so serde_yaml reads a spec written in either
+
+    Spec, PathItem, Operation, RequestBody, and MediaType are the parts of the OpenAPI document we care about,
+    with serde filling them in and ignoring every other field
+    The operations of a path are one field per method, as that is how the spec lays them out,
+    and PathItem::operations hands them back as a list of (method, operation) pairs
+
+    FINDING THE OPERATION
+
+    find_operation walks every operation of every path looking for the operationId
+    An operationId is unique within a spec so the first match is the one
+
+    A missing operation is reported with Error::OperationNotFound naming the operationId,
+    rather than as a generic failure, as a typo there is the likely reason
+
+    THE URL
+
+    The url of the first server is put in front of the path, and without servers the path is used as it is,
+    so --base-url or base_url in the config can supply the host in that case
+
+    Path templates like {id} are turned into the :id placeholders hurl already fills in from data parameters,
+    so hurl --from-openapi api.yaml --operation getUser id=5 requests /users/5
+    A template whose name hurl couldn't use as a placeholder, e.g. {user-id}, is left alone
+
+    THE BODY
+
+    The example of the application/json content of the requestBody is used,
+    looking at example first, then the first of examples, and then the example of the schema
+
+    The example has to be a JSON object, each of its fields becomes a key:=value parameter
+    so the command line can replace any of them, anything else is Error::UnsupportedExample
+
+***/
+
+use crate::app::Parameter;
+use crate::errors::{Error, HurlResult};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Deserialize)]
+struct Spec {
+    #[serde(default)]
+    servers: Vec<Server>,
+    #[serde(default)]
+    paths: BTreeMap<String, PathItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Server {
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PathItem {
+    get: Option<Operation>,
+    put: Option<Operation>,
+    post: Option<Operation>,
+    delete: Option<Operation>,
+    options: Option<Operation>,
+    head: Option<Operation>,
+    patch: Option<Operation>,
+    trace: Option<Operation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Operation {
+    #[serde(rename = "operationId")]
+    operation_id: Option<String>,
+    #[serde(rename = "requestBody")]
+    request_body: Option<RequestBody>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RequestBody {
+    #[serde(default)]
+    content: BTreeMap<String, MediaType>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MediaType {
+    example: Option<Value>,
+    #[serde(default)]
+    examples: BTreeMap<String, Example>,
+    schema: Option<Schema>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Example {
+    value: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Schema {
+    example: Option<Value>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub url: String,
+    pub parameters: Vec<Parameter>,
+}
+
+impl PathItem {
+    fn operations(&self) -> Vec<(&'static str, &Operation)> {
+        let all = vec![
+            ("GET", &self.get),
+            ("PUT", &self.put),
+            ("POST", &self.post),
+            ("DELETE", &self.delete),
+            ("OPTIONS", &self.options),
+            ("HEAD", &self.head),
+            ("PATCH", &self.patch),
+            ("TRACE", &self.trace),
+        ];
+
+        all.into_iter()
+            .filter_map(|(method, op)| op.as_ref().map(|op| (method, op)))
+            .collect()
+    }
+}
+
+impl MediaType {
+    fn example(&self) -> Option<&Value> {
+        self.example
+            .as_ref()
+            .or_else(|| self.examples.values().find_map(|e| e.value.as_ref()))
+            .or_else(|| self.schema.as_ref().and_then(|s| s.example.as_ref()))
+    }
+}
+
+pub fn find_operation(spec: &str, operation_id: &str) -> HurlResult<Request> {
+    let spec: Spec = serde_yaml::from_str(spec)?;
+
+    for (path, item) in &spec.paths {
+        for (method, op) in item.operations() {
+            if op.operation_id.as_deref() != Some(operation_id) {
+                continue;
+            }
+
+            let server = spec.servers.first().map(|s| s.url.trim_end_matches('/')).unwrap_or("");
+            let url = format!("{}{}", server, placeholders(path));
+            let parameters = match body_example(op) {
+                Some(example) => example_parameters(operation_id, example)?,
+                None => vec![],
+            };
+
+            return Ok(Request {
+                method: method.to_owned(),
+                url,
+                parameters,
+            });
+        }
+    }
+
+    Err(Error::OperationNotFound(operation_id.to_owned()))
+}
+
+fn body_example(op: &Operation) -> Option<&Value> {
+    let content = &op.request_body.as_ref()?.content;
+
+    content
+        .get("application/json")
+        .or_else(|| content.iter().find(|(ty, _)| ty.ends_with("+json")).map(|(_, media)| media))
+        .and_then(MediaType::example)
+}
+
+fn example_parameters(operation_id: &str, example: &Value) -> HurlResult<Vec<Parameter>> {
+    match example {
+        Value::Object(fields) => Ok(fields
+            .iter()
+            .map(|(key, value)| Parameter::RawJsonData {
+                key: key.to_owned(),
+                value: value.to_string(),
+            })
+            .collect()),
+        _ => Err(Error::UnsupportedExample(operation_id.to_owned())),
+    }
+}
+
+fn placeholders(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    let mut rest = path;
+
+    while let Some(start) = rest.find('{') {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let name = &rest[start + 1..end];
+        let usable = name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+        out.push_str(&rest[..start]);
+
+        if usable && rest[..start].ends_with('/') {
+            out.push(':');
+            out.push_str(name);
+        } else {
+            out.push_str(&rest[start..=end]);
+        }
+
+        rest = &rest[end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r#"
+openapi: 3.0.0
+servers:
+  - url: https://api.example.com/v1/
+paths:
+  /pets:
+    get:
+      operationId: listPets
+    post:
+      operationId: createPet
+      requestBody:
+        content:
+          application/json:
+            example:
+              name: Rex
+              tags: [good, dog]
+  /pets/{petId}:
+    put:
+      operationId: updatePet
+      requestBody:
+        content:
+          application/json:
+            schema:
+              example: "not an object"
+"#;
+
+    #[test]
+    fn find_operation_fills_in_the_method_url_and_body() {
+        assert_eq!(
+            find_operation(SPEC, "createPet").unwrap(),
+            Request {
+                method: "POST".to_owned(),
+                url: "https://api.example.com/v1/pets".to_owned(),
+                parameters: vec![
+                    Parameter::RawJsonData {
+                        key: "name".to_owned(),
+                        value: r#""Rex""#.to_owned(),
+                    },
+                    Parameter::RawJsonData {
+                        key: "tags".to_owned(),
+                        value: r#"["good","dog"]"#.to_owned(),
+                    },
+                ],
+            }
+        );
+
+        let list = find_operation(SPEC, "listPets").unwrap();
+        assert_eq!(list.method, "GET");
+        assert!(list.parameters.is_empty());
+    }
+
+    #[test]
+    fn find_operation_reports_what_it_cant_use() {
+        assert!(matches!(
+            find_operation(SPEC, "deletePet"),
+            Err(Error::OperationNotFound(ref id)) if id == "deletePet"
+        ));
+        assert!(matches!(
+            find_operation(SPEC, "updatePet"),
+            Err(Error::UnsupportedExample(ref id)) if id == "updatePet"
+        ));
+    }
+
+    #[test]
+    fn placeholders_turn_templates_into_path_parameters() {
+        assert_eq!(placeholders("/pets/{petId}"), "/pets/:petId");
+        assert_eq!(placeholders("/users/{id}/posts/{post_id}"), "/users/:id/posts/:post_id");
+        assert_eq!(placeholders("/users/{user-id}"), "/users/{user-id}");
+        assert_eq!(placeholders("/files/report.{ext}"), "/files/report.{ext}");
+    }
+}
@@ -0,0 +1,238 @@
+/***
+ *
+ *
+ *
+    PRESETS MODULE
+
+    Sessions remember headers and cookies between requests,
+    but sometimes the whole request is what needs to be remembered
+
+    A preset is a named snapshot of a request: the method, the URL, the parameters,
+    and the authentication information that were given on the command line
+
+    Saving a preset:
+
+        hurl --save-preset deploy -a bob:secret POST example.com/deploy X-Env:prod force:=true
+
+    Replaying it later:
+
+        hurl --preset deploy
+
+    DEFINING THE PRESET
+
+    Just like the Session struct, deriving Serialize and Deserialize lets serde
+    read and write the struct as JSON
+
+    The method is stored by name rather than as the Method enum,
+    and is None when no subcommand was given so the usual GET/POST inference still applies on replay
+    A method given with --method is stored by name too, and as it isn't one of the subcommands
+    it goes back into --method when the preset is applied
+
+    For the parameters to be stored the Parameter enum in the app module also derives Serialize and Deserialize
+
+    STORING THE PRESET
+
+    The file storage mirrors the session module,
+    presets live as JSON files in a presets directory inside the configuration directory
+    and the name is passed through make_safe_pathname to get the filename
+
+    Presets are not tied to a host like sessions are, as the URL is part of the preset itself
+
+    A missing preset is reported with its own error rather than a bare IO error
+    so it's obvious which name could not be found
+
+    APPLYING THE PRESET
+
+    apply fills in the App struct as if the preset had been typed on the command line
+
+    Anything that was actually given on the command line wins,
+    so the request only comes from the preset if neither a method subcommand nor a URL was given,
+    and the authentication is only used if none was passed in
+
+***/
+
+use crate::app::{parse_method, App, Method, MethodData, Parameter};
+use crate::directories::DIRECTORIES;
+use crate::errors::{Error, HurlResult};
+use crate::session::make_safe_pathname;
+use serde::{Deserialize, Serialize};
+use std::fs::{create_dir_all, File, OpenOptions};
+use std::io::{BufReader, BufWriter, ErrorKind};
+use std::path::PathBuf;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Preset {
+    name: String,
+    method: Option<String>,
+    url: String,
+    parameters: Vec<Parameter>,
+    auth: Option<String>,
+    token: Option<String>,
+}
+
+impl Preset {
+    pub fn from_app(app: &App, name: String) -> Self {
+        let (method, url, parameters) = match &app.cmd {
+            Some(cmd) => {
+                let data = cmd.data();
+                (Some(cmd.name().to_owned()), data.url.clone(), data.parameters.clone())
+            }
+            None => (
+                app.method.as_ref().map(ToString::to_string),
+                app.url.clone().unwrap_or_default(),
+                app.parameters.clone(),
+            ),
+        };
+
+        Preset {
+            name,
+            method,
+            url,
+            parameters,
+            auth: app.auth.clone(),
+            token: app.token.clone(),
+        }
+    }
+
+    pub fn load(name: &str) -> HurlResult<Self> {
+        let file = File::open(Preset::path(name)).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => Error::PresetNotFound(name.to_owned()),
+            _ => e.into(),
+        })?;
+        let reader = BufReader::new(file);
+
+        serde_json::from_reader(reader).map_err(|e| e.into())
+    }
+
+    pub fn save(&self) -> HurlResult<()> {
+        create_dir_all(Preset::dir())?;
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(Preset::path(&self.name))?;
+
+        let writer = BufWriter::new(file);
+
+        serde_json::to_writer(writer, &self).map_err(|e| e.into())
+    }
+
+    pub fn apply(self, app: &mut App) -> HurlResult<()> {
+        if app.cmd.is_none() && app.url.is_none() {
+            match self.method {
+                Some(method) if app.method.is_none() && Method::is_name(&method) => {
+                    let data = MethodData {
+                        url: self.url,
+                        parameters: self.parameters,
+                    };
+                    app.cmd = Some(Method::from_name(&method, data)?);
+                }
+                Some(method) => {
+                    if app.method.is_none() {
+                        app.method = Some(parse_method(&method)?);
+                    }
+                    app.url = Some(self.url);
+                    app.parameters = self.parameters;
+                }
+                None => {
+                    app.url = Some(self.url);
+                    app.parameters = self.parameters;
+                }
+            }
+        }
+
+        if app.auth.is_none() {
+            app.auth = self.auth;
+        }
+
+        if app.token.is_none() {
+            app.token = self.token;
+        }
+
+        Ok(())
+    }
+
+    fn path(name: &str) -> PathBuf {
+        let mut filename = make_safe_pathname(name);
+
+        filename.push_str(".json");
+        Preset::dir().join(filename)
+    }
+
+    fn dir() -> PathBuf {
+        DIRECTORIES.config().join("presets")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structopt::StructOpt;
+
+    fn round_trip(preset: &Preset) -> Preset {
+        serde_json::from_str(&serde_json::to_string(preset).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn preset_round_trips_the_request_and_auth() {
+        let saved = App::from_iter(&[
+            "hurl", "-a", "bob:secret", "POST", "example.com/deploy", "X-Env:prod", "force:=true",
+        ]);
+        let preset = round_trip(&Preset::from_app(&saved, "deploy".to_owned()));
+
+        let mut app = App::from_iter(&["hurl"]);
+        preset.apply(&mut app).unwrap();
+
+        let cmd = app.cmd.as_ref().expect("the preset should set the method subcommand");
+        assert_eq!(cmd.name(), "POST");
+        assert_eq!(cmd.data().url, "example.com/deploy");
+        assert_eq!(
+            cmd.data().parameters,
+            vec![
+                Parameter::Header {
+                    key: "X-Env".to_owned(),
+                    value: "prod".to_owned(),
+                },
+                Parameter::RawJsonData {
+                    key: "force".to_owned(),
+                    value: "true".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(app.auth.as_deref(), Some("bob:secret"));
+        assert_eq!(app.token, None);
+    }
+
+    #[test]
+    fn preset_gives_way_to_the_command_line() {
+        let mut saved = App::from_iter(&[
+            "hurl", "--method", "PURGE", "-t", "abc123", "-a", "bob:secret", "example.com/cache", "page==2",
+        ]);
+        saved.validate().unwrap();
+        let preset = round_trip(&Preset::from_app(&saved, "purge".to_owned()));
+
+        let mut app = App::from_iter(&["hurl"]);
+        round_trip(&preset).apply(&mut app).unwrap();
+
+        assert!(app.cmd.is_none());
+        assert_eq!(app.method.as_ref().map(reqwest::Method::as_str), Some("PURGE"));
+        assert_eq!(app.url.as_deref(), Some("example.com/cache"));
+        assert_eq!(
+            app.parameters,
+            vec![Parameter::Query {
+                key: "page".to_owned(),
+                value: "2".to_owned(),
+            }]
+        );
+
+        let mut app = App::from_iter(&["hurl", "-a", "alice:pw", "example.com/other"]);
+        preset.apply(&mut app).unwrap();
+
+        assert!(app.method.is_none());
+        assert_eq!(app.url.as_deref(), Some("example.com/other"));
+        assert!(app.parameters.is_empty());
+        assert_eq!(app.auth.as_deref(), Some("alice:pw"));
+        assert_eq!(app.token.as_deref(), Some("abc123"));
+    }
+}
@@ -0,0 +1,167 @@
+/***
+ *
+ *
+ *
+    UPLOAD PROGRESS MODULE
+
+    Uploading a big file with a key@filename parameter can take a while with nothing to show for it
+
+    The blocking multipart support in reqwest has no hook for progress,
+    but it will happily read a part from anything which implements Read,
+    so the file is wrapped in a ProgressReader and given to multipart::Part::reader_with_length
+
+    Every time reqwest reads another chunk of the file to send it, the reader counts the bytes
+    and redraws a line like upload.bin: 52428800/104857600 bytes (50%)
+
+    THE PROGRESS READER
+
+    ProgressReader wraps any Read and any Write, the reader is where the bytes come from
+    and the writer is where the progress line goes, which is stderr when uploading
+    so the progress never gets mixed in with the response printed on stdout
+
+    Being generic over the writer is also what lets the test check the output using a Vec<u8>
+
+    The line starts with a carriage return so each update overwrites the last one,
+    and it is only redrawn when the percentage changes rather than on every read
+    A newline is written once the last byte has been read so the response starts on a fresh line
+
+    BUILDING THE PART
+
+    Form::file does more than open the file, it also sets the file name
+    and guesses the content type from the extension with mime_guess
+
+    file_part does the same using the same crate, so the only difference in the request is where the bytes come from
+    Using reader_with_length rather than reader means the size of the file is still known up front,
+    so reqwest sends a Content-Length just like it does with Form::file
+
+    The client module only uses file_part when stdout is a terminal and --no-progress is not given
+
+***/
+
+use crate::errors::HurlResult;
+use reqwest::multipart::Part;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+pub struct ProgressReader<R, W> {
+    inner: R,
+    out: W,
+    name: String,
+    sent: u64,
+    total: u64,
+    last_percent: Option<u64>,
+}
+
+impl<R: Read, W: Write> ProgressReader<R, W> {
+    pub fn new(inner: R, out: W, name: String, total: u64) -> Self {
+        ProgressReader {
+            inner,
+            out,
+            name,
+            sent: 0,
+            total,
+            last_percent: None,
+        }
+    }
+
+    fn report(&mut self, done: bool) -> io::Result<()> {
+        let percent = if self.total == 0 {
+            100
+        } else {
+            self.sent.min(self.total) * 100 / self.total
+        };
+
+        if self.last_percent != Some(percent) {
+            self.last_percent = Some(percent);
+            write!(
+                self.out,
+                "\r{}: {}/{} bytes ({}%)",
+                self.name, self.sent, self.total, percent
+            )?;
+        }
+
+        if done {
+            writeln!(self.out)?;
+        }
+
+        self.out.flush()
+    }
+}
+
+impl<R: Read, W: Write> Read for ProgressReader<R, W> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.sent += n as u64;
+
+        let done = n == 0 || self.sent >= self.total;
+        if done && self.last_percent == Some(100) {
+            return Ok(n);
+        }
+
+        self.report(done)?;
+
+        Ok(n)
+    }
+}
+
+pub fn file_part(filename: &str) -> HurlResult<Part> {
+    let path = Path::new(filename);
+    let file = File::open(path)?;
+    let total = file.metadata()?.len();
+
+    let name = path
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_else(|| filename.to_owned());
+
+    let mime = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| mime_guess::from_ext(ext).first_or_octet_stream())
+        .unwrap_or(mime_guess::mime::APPLICATION_OCTET_STREAM);
+
+    let reader = ProgressReader::new(file, io::stderr(), name.clone(), total);
+
+    let part = Part::reader_with_length(reader, total)
+        .file_name(name)
+        .mime_str(mime.as_ref())?;
+
+    Ok(part)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn progress_reader_counts_bytes_and_reports() {
+        let data = vec![7u8; 10];
+        let mut out = Vec::new();
+
+        {
+            let mut reader = ProgressReader::new(Cursor::new(data.clone()), &mut out, "data.bin".to_owned(), 10);
+            let mut chunk = [0u8; 4];
+            let mut read_back = Vec::new();
+
+            loop {
+                let n = reader.read(&mut chunk).unwrap();
+                if n == 0 {
+                    break;
+                }
+                read_back.extend_from_slice(&chunk[..n]);
+            }
+
+            assert_eq!(read_back, data);
+            assert_eq!(reader.sent, 10);
+        }
+
+        let out = String::from_utf8(out).unwrap();
+
+        assert_eq!(
+            out,
+            "\rdata.bin: 4/10 bytes (40%)\rdata.bin: 8/10 bytes (80%)\rdata.bin: 10/10 bytes (100%)\n"
+        );
+    }
+}
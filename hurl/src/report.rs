@@ -0,0 +1,150 @@
+/***
+ *
+ *
+ *
+    REPORT MODULE
+
+    With --format json or --format toml the response is printed as one structured document
+    instead of the highlighted status line, headers, and body, which is easier for another program to read
+
+    THE REPORT
+
+    A Report has the status code and its reason, the HTTP version, the body, and the headers
+    The headers are the same map dump_headers writes, a header sent more than once is an array of its values
+
+    A body that parses as JSON is included as JSON so it is part of the document rather than an escaped string,
+    anything else is included as a string, with invalid UTF-8 replaced
+
+    Format is parsed from the --format argument with FromStr, the same way structopt parses any other value,
+    and render turns a Report into the text to print
+
+    TOML
+
+    TOML can't represent everything JSON can, there is no null, and a table can't be followed by a plain value
+
+    The report is converted to a toml::Value first rather than serialized straight to a string,
+    which takes care of putting the plain values before the tables
+
+    A body that still can't be turned into TOML, e.g. one with a null in it, is put in the report as its JSON text instead,
+    so the rest of the report is still there and nothing about the body is lost
+
+***/
+
+use crate::errors::{Error, HurlResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Toml,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            _ => Err(format!("unknown format {}, expected json or toml", s)),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    pub status: u16,
+    pub reason: String,
+    pub version: String,
+    pub body: Value,
+    pub headers: BTreeMap<String, Value>,
+}
+
+impl Report {
+    pub fn new(
+        status: reqwest::StatusCode,
+        version: String,
+        headers: BTreeMap<String, Value>,
+        body: &[u8],
+    ) -> Report {
+        let body = serde_json::from_slice(body)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(body).into_owned()));
+
+        Report {
+            status: status.as_u16(),
+            reason: status.canonical_reason().unwrap_or("Unknown").to_owned(),
+            version,
+            body,
+            headers,
+        }
+    }
+}
+
+pub fn render(report: &Report, format: Format) -> HurlResult<String> {
+    match format {
+        Format::Json => serde_json::to_string_pretty(report).map_err(Into::into),
+        Format::Toml => render_toml(report),
+    }
+}
+
+fn render_toml(report: &Report) -> HurlResult<String> {
+    let value = match toml::Value::try_from(report) {
+        Ok(value) => value,
+        Err(_) => {
+            let fallback = Report {
+                status: report.status,
+                reason: report.reason.clone(),
+                version: report.version.clone(),
+                body: Value::String(report.body.to_string()),
+                headers: report.headers.clone(),
+            };
+
+            toml::Value::try_from(&fallback).map_err(|e| Error::Toml(e.to_string()))?
+        }
+    };
+
+    toml::to_string(&value).map_err(|e| Error::Toml(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample(body: &[u8]) -> Report {
+        let mut headers = BTreeMap::new();
+        headers.insert("content-type".to_owned(), json!("application/json"));
+        headers.insert("set-cookie".to_owned(), json!(["a=1", "b=2"]));
+
+        Report::new(reqwest::StatusCode::OK, "HTTP/1.1".to_owned(), headers, body)
+    }
+
+    #[test]
+    fn json_report_round_trips() {
+        let report = sample(br#"{"id":1,"tags":["a","b"],"author":{"name":"ruben"}}"#);
+        let rendered = render(&report, Format::Json).unwrap();
+
+        assert_eq!(serde_json::from_str::<Report>(&rendered).unwrap(), report);
+    }
+
+    #[test]
+    fn toml_report_round_trips() {
+        let report = sample(br#"{"id":1,"tags":["a","b"],"author":{"name":"ruben"}}"#);
+        let rendered = render(&report, Format::Toml).unwrap();
+
+        assert_eq!(toml::from_str::<Report>(&rendered).unwrap(), report);
+    }
+
+    #[test]
+    fn toml_report_keeps_a_body_it_cant_represent_as_text() {
+        let report = sample(br#"[{"id":1,"deleted_at":null}]"#);
+        let parsed: Report = toml::from_str(&render(&report, Format::Toml).unwrap()).unwrap();
+
+        assert_eq!(parsed.body, json!(r#"[{"deleted_at":null,"id":1}]"#));
+        assert_eq!(parsed.headers, report.headers);
+        assert_eq!(parsed.status, 200);
+    }
+}
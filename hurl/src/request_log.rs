@@ -0,0 +1,154 @@
+/***
+ *
+ *
+ *
+    REQUEST LOG MODULE
+
+    --log-file <path> appends one line of JSON to the file for every request that is made,
+    which makes it easy to keep a history of a probe that runs on a schedule and plot it later
+
+    Each line has:
+        timestamp   milliseconds since the Unix epoch when the request was sent
+        url         the URL as given on the command line
+        method      the HTTP method
+        status      the status code, or null if no response came back
+        latency_ms  how long it took to get the response headers back
+        error       the error message, or null if the request went through
+
+    The log is separate from what gets printed, so it is written even with --quiet or --silent-success
+
+    WRITING A LINE
+
+    The file is opened with OpenOptions in append mode and created if it doesn't exist yet,
+    so every run adds to the end of the file instead of replacing what was there
+
+    A LogEntry is turned into a single line with serde_json::to_string, which never adds newlines,
+    and a newline is written after it
+
+    Failing to open or write the file comes back as Error::IO through the From impl in the errors module
+
+    WHEN IT IS WRITTEN
+
+    main calls record right after client::perform returns, before the response is handled,
+    so a request which fails to connect or times out is logged with its error before the error is returned
+
+    Requests made by --benchmark are not logged, that mode prints its own report
+
+***/
+
+use crate::app::App;
+use crate::errors::HurlResult;
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Serialize)]
+pub struct LogEntry<'a> {
+    pub timestamp: u64,
+    pub url: &'a str,
+    pub method: &'a str,
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+pub fn record(
+    app: &App,
+    method: &reqwest::Method,
+    url: &str,
+    sent_at: SystemTime,
+    started: Instant,
+    result: &HurlResult<reqwest::Response>
+) -> HurlResult<()> {
+    let path = match &app.log_file {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    let timestamp = sent_at
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    let (status, error) = match result {
+        Ok(resp) => (Some(resp.status().as_u16()), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    let entry = LogEntry {
+        timestamp,
+        url,
+        method: method.as_str(),
+        status,
+        latency_ms: started.elapsed().as_millis() as u64,
+        error,
+    };
+
+    append(path, &entry)
+}
+
+pub fn append(path: &Path, entry: &LogEntry) -> HurlResult<()> {
+    let line = serde_json::to_string(entry)?;
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn append_writes_one_json_line_per_entry() {
+        let path = std::env::temp_dir().join(format!("hurl-request-log-{}.jsonl", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let ok = LogEntry {
+            timestamp: 1_600_000_000_000,
+            url: "http://localhost:8080/health",
+            method: "GET",
+            status: Some(200),
+            latency_ms: 12,
+            error: None,
+        };
+        let failed = LogEntry {
+            timestamp: 1_600_000_001_000,
+            url: "http://localhost:8080/health",
+            method: "GET",
+            status: None,
+            latency_ms: 3,
+            error: Some("connection refused".to_owned()),
+        };
+
+        append(&path, &ok).unwrap();
+        append(&path, &failed).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            serde_json::json!({
+                "timestamp": 1_600_000_000_000u64,
+                "url": "http://localhost:8080/health",
+                "method": "GET",
+                "status": 200,
+                "latency_ms": 12,
+                "error": null,
+            })
+        );
+        assert_eq!(lines[1]["status"], serde_json::Value::Null);
+        assert_eq!(lines[1]["error"], "connection refused");
+    }
+}
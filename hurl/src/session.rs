@@ -75,11 +75,23 @@
     Headers are stored in the headers field on the session, but a few keys are excluded
     as persisting them across request is a no-no
 
+    Some headers can legitimately appear more than once,
+    so with --append-headers the values are collected in appended_headers
+    which maps each key to every value seen for it, skipping exact duplicates
+
+    A header given without --append-headers replaces everything stored for that key,
+    so a key only ever lives in one of the two maps
+
+    appended_headers has the serde default attribute
+    so session files written before it existed still load
+
     Next, if auth data is available, the session needs to be updated
 
     The add_to_request method is as the name implies adding the session to the request
 
     It starts by adding headers to the request, if there are any
+    The header method on the builder appends rather than replaces,
+    so each of the appended values ends up as its own header line
     Further, if there are cookies, 
     turn them into the expected format for the cookie header and add that to the request
 
@@ -91,13 +103,33 @@
 
     The make_safe_pathname helps turn a string into something that is safe for storing on the file system
     This is just one example of a scheme that works but can be something else
+
+    CACHING RESPONSES
+
+    With --cache the session also remembers the last response for each URL that came with an ETag or Last-Modified header,
+    the validators along with the body and its content type, in the cache map keyed by the URL
+
+    conditional_headers gives the If-None-Match and If-Modified-Since headers to send for a URL that is cached,
+    and add_conditional_headers adds them to the request,
+    a server that still has the same version answers with a 304 Not Modified and no body
+
+    update_cache is given every response, a 304 is left alone as it has nothing new,
+    a successful response with validators replaces the entry, and one without them drops the entry
+    so a URL which stops sending validators isn't stuck with an old body
+
+    cached_body hands back the cached entry when the status is a 304 so the body can be shown from the cache
+
+    The response is stored under its own URL which is the URL after any redirects,
+    so a URL that redirects is never found in the cache and is always fetched in full
+
+    cache has the serde default attribute like appended_headers so older session files still load
 ***/
 
 use crate::app::{App, Parameter};
 use crate::directories::DIRECTORIES;
 use crate::errors::HurlResult;
-use reqwest::header::COOKIE;
-use reqwest::RequestBuilder;
+use reqwest::header::{HeaderMap, COOKIE, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{RequestBuilder, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::{create_dir_all, File, OpenOptions};
@@ -112,7 +144,19 @@ pub struct Session {
     auth: Option<String>,
     token: Option<String>,
     headers: HashMap<String, String>,
+    #[serde(default)]
+    appended_headers: HashMap<String, Vec<String>>,
     cookies: Vec<(String, String)>,
+    #[serde(default)]
+    cache: HashMap<String, CachedResponse>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub content_type: Option<String>,
+    pub body: String,
 }
 
 impl Session {
@@ -178,7 +222,7 @@ impl Session {
         serde_json::to_writer(writer, &self).map_err(|e| e.into())
     }
 
-    pub fn update_with_parameters(&mut self, parameters: &Vec<Parameter>) {
+    pub fn update_with_parameters(&mut self, parameters: &Vec<Parameter>, append: bool) {
         for parameter in parameters.iter() {
             match parameter {
                 Parameter::Header { key, value } => {
@@ -188,7 +232,20 @@ impl Session {
                         continue;
                     }
 
-                    self.headers.insert(key.clone(), value.clone());
+                    if append {
+                        let values = self.appended_headers.entry(key.clone()).or_default();
+
+                        if let Some(existing) = self.headers.remove(key) {
+                            values.push(existing);
+                        }
+
+                        if !values.contains(value) {
+                            values.push(value.clone());
+                        }
+                    } else {
+                        self.appended_headers.remove(key);
+                        self.headers.insert(key.clone(), value.clone());
+                    }
                 }
                 _ => {}
             }
@@ -209,6 +266,12 @@ impl Session {
         for (key, value) in self.headers.iter() {
             builder = builder.header(key, value);
         }
+
+        for (key, values) in self.appended_headers.iter() {
+            for value in values {
+                builder = builder.header(key, value);
+            }
+        }
         let cookies = self
             .cookies
             .iter()
@@ -223,6 +286,63 @@ impl Session {
         builder.header(COOKIE, cookies)
     }
 
+    pub fn conditional_headers(&self, url: &str) -> Vec<(reqwest::header::HeaderName, String)> {
+        let mut headers = Vec::new();
+
+        if let Some(cached) = self.cache.get(url) {
+            if let Some(etag) = &cached.etag {
+                headers.push((IF_NONE_MATCH, etag.clone()));
+            }
+
+            if let Some(last_modified) = &cached.last_modified {
+                headers.push((IF_MODIFIED_SINCE, last_modified.clone()));
+            }
+        }
+
+        headers
+    }
+
+    pub fn add_conditional_headers(&self, mut builder: RequestBuilder, url: &str) -> RequestBuilder {
+        for (key, value) in self.conditional_headers(url) {
+            builder = builder.header(key, value);
+        }
+
+        builder
+    }
+
+    pub fn update_cache(&mut self, url: &str, status: StatusCode, headers: &HeaderMap, body: &[u8]) {
+        if status == StatusCode::NOT_MODIFIED || !status.is_success() {
+            return;
+        }
+
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_owned);
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+
+        if etag.is_none() && last_modified.is_none() {
+            self.cache.remove(url);
+            return;
+        }
+
+        self.cache.insert(
+            url.to_owned(),
+            CachedResponse {
+                etag,
+                last_modified,
+                content_type: header(CONTENT_TYPE),
+                body: String::from_utf8_lossy(body).into_owned(),
+            },
+        );
+    }
+
+    pub fn cached_body(&self, url: &str, status: StatusCode) -> Option<&CachedResponse> {
+        if status == StatusCode::NOT_MODIFIED {
+            self.cache.get(url)
+        } else {
+            None
+        }
+    }
+
     pub fn update_with_response(&mut self, resp: &reqwest::Response) {
         for cookie in resp.cookies() {
             self.cookies
@@ -243,4 +363,101 @@ pub fn make_safe_pathname(s: &str) -> String {
     }
 
     buf
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    const URL: &str = "http://example.com/posts";
+
+    fn headers(pairs: &[(reqwest::header::HeaderName, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        for (key, value) in pairs {
+            headers.insert(key.clone(), HeaderValue::from_static(value));
+        }
+
+        headers
+    }
+
+    #[test]
+    fn cache_stores_responses_with_validators() {
+        let mut session = Session::default();
+        let with_etag = headers(&[(ETAG, "\"v1\""), (CONTENT_TYPE, "application/json")]);
+
+        session.update_cache(URL, StatusCode::OK, &with_etag, b"[1]");
+
+        assert_eq!(
+            session.conditional_headers(URL),
+            vec![(IF_NONE_MATCH, "\"v1\"".to_owned())]
+        );
+        assert!(session.conditional_headers("http://example.com/other").is_empty());
+
+        session.update_cache(URL, StatusCode::OK, &headers(&[(LAST_MODIFIED, "Wed, 21 Oct 2015 07:28:00 GMT")]), b"[2]");
+
+        assert_eq!(
+            session.conditional_headers(URL),
+            vec![(IF_MODIFIED_SINCE, "Wed, 21 Oct 2015 07:28:00 GMT".to_owned())]
+        );
+
+        session.update_cache(URL, StatusCode::OK, &HeaderMap::new(), b"[3]");
+
+        assert!(session.conditional_headers(URL).is_empty());
+    }
+
+    #[test]
+    fn not_modified_uses_the_cached_body() {
+        let mut session = Session::default();
+        let with_etag = headers(&[(ETAG, "\"v1\""), (CONTENT_TYPE, "application/json")]);
+        session.update_cache(URL, StatusCode::OK, &with_etag, b"[1]");
+
+        session.update_cache(URL, StatusCode::NOT_MODIFIED, &with_etag, b"");
+        session.update_cache(URL, StatusCode::INTERNAL_SERVER_ERROR, &with_etag, b"oops");
+
+        let cached = session.cached_body(URL, StatusCode::NOT_MODIFIED).unwrap();
+        assert_eq!(cached.body, "[1]");
+        assert_eq!(cached.content_type.as_deref(), Some("application/json"));
+
+        assert!(session.cached_body(URL, StatusCode::OK).is_none());
+        assert!(session.cached_body("http://example.com/other", StatusCode::NOT_MODIFIED).is_none());
+    }
+
+    fn header(key: &str, value: &str) -> Vec<Parameter> {
+        vec![Parameter::Header {
+            key: key.to_owned(),
+            value: value.to_owned(),
+        }]
+    }
+
+    fn sent_values(session: &Session, key: &str) -> Vec<String> {
+        let request = session
+            .add_to_request(reqwest::Client::new().get(URL))
+            .build()
+            .unwrap();
+
+        request
+            .headers()
+            .get_all(key)
+            .iter()
+            .map(|v| v.to_str().unwrap().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn appended_headers_keep_every_value() {
+        let mut session = Session::default();
+
+        session.update_with_parameters(&header("X-Trace", "a"), true);
+        session.update_with_parameters(&header("X-Trace", "b"), true);
+        session.update_with_parameters(&header("X-Trace", "b"), true);
+        assert_eq!(sent_values(&session, "x-trace"), vec!["a", "b"]);
+
+        session.update_with_parameters(&header("X-Trace", "c"), false);
+        assert_eq!(sent_values(&session, "x-trace"), vec!["c"]);
+
+        session.update_with_parameters(&header("X-Trace", "d"), true);
+        assert_eq!(sent_values(&session, "x-trace"), vec!["c", "d"]);
+    }
+}
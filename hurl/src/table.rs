@@ -0,0 +1,147 @@
+/***
+ *
+ *
+ *
+    TABLE MODULE
+
+    List endpoints usually return a JSON array of objects which all have the same handful of keys,
+    and pretty printed that is a tall column of braces that's hard to scan
+
+    With --table such a response is printed as a table instead, one row per object and one column per key:
+
+        id  title  published
+        --  -----  ---------
+        1   Hello  true
+        2   Bye    false
+
+    THE COLUMNS
+
+    The columns are the union of the keys of all the objects, in the order they are first seen
+    serde_json keeps the keys of an object sorted, so within one object that is alphabetical order,
+    and keys that only show up in later objects are added on the end
+
+    An object that doesn't have one of the keys gets a blank cell in that column
+
+    THE CELLS
+
+    A string is printed without its quotes, null as null, and numbers and booleans as they are
+    A nested object or array is printed as compact JSON so the row still fits on one line
+
+    Each column is as wide as its widest cell or header, counted in characters rather than bytes,
+    and the columns are separated by two spaces with trailing spaces trimmed off each line
+
+    WHEN THERE IS NO TABLE
+
+    format_table gives back None when the value isn't an array, is an empty array,
+    or has anything other than an object in it, and the response is printed as JSON as usual
+
+***/
+
+use serde_json::Value;
+
+const SEPARATOR: &str = "  ";
+
+pub fn format_table(value: &Value) -> Option<String> {
+    let rows = match value {
+        Value::Array(rows) if !rows.is_empty() => rows,
+        _ => return None,
+    };
+
+    let mut objects = Vec::with_capacity(rows.len());
+    let mut columns: Vec<&str> = Vec::new();
+
+    for row in rows {
+        let object = row.as_object()?;
+
+        for key in object.keys() {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+
+        objects.push(object);
+    }
+
+    let cells: Vec<Vec<String>> = objects
+        .iter()
+        .map(|object| {
+            columns
+                .iter()
+                .map(|column| object.get(*column).map(cell).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain(std::iter::once(column.chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let header: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+    let rule: Vec<String> = columns.iter().map(|c| "-".repeat(c.chars().count())).collect();
+
+    let mut out = String::new();
+
+    for row in std::iter::once(&header).chain(std::iter::once(&rule)).chain(cells.iter()) {
+        out.push_str(&format_row(row, &widths));
+        out.push('\n');
+    }
+
+    Some(out)
+}
+
+fn format_row(row: &[String], widths: &[usize]) -> String {
+    let padded: Vec<String> = row
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{}{}", cell, " ".repeat(width - cell.chars().count())))
+        .collect();
+
+    padded.join(SEPARATOR).trim_end().to_owned()
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.to_owned(),
+        v => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn format_table_lines_up_columns() {
+        let value = json!([
+            {"id": 1, "title": "Hello", "published": true},
+            {"id": 22, "title": "Bye", "tags": ["a", "b"]},
+            {"id": 3, "title": null, "published": false}
+        ]);
+
+        assert_eq!(
+            format_table(&value).unwrap(),
+            "id  published  title  tags\n\
+             --  ---------  -----  ----\n\
+             1   true       Hello\n\
+             22             Bye    [\"a\",\"b\"]\n\
+             3   false      null\n"
+        );
+    }
+
+    #[test]
+    fn format_table_needs_an_array_of_objects() {
+        assert_eq!(format_table(&json!({"id": 1})), None);
+        assert_eq!(format_table(&json!([])), None);
+        assert_eq!(format_table(&json!([{"id": 1}, 2])), None);
+    }
+}
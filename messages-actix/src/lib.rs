@@ -422,6 +422,179 @@
     converts Option<&T> to Option<T> by cloning the inner data in the Some case, and
     doing nothing in the None case.
 
+    FILTERING THE INDEX
+
+    The index also accepts a query string, e.g. GET /?contains=foo&since=1700000000,
+    to only return the messages which contain the given substring and were sent after the given time
+
+    The web::Query extractor deserializes the query string into the IndexQuery struct the same way
+    web::Json does for a body, and as the fields are Options a missing parameter just means no filtering
+    Both filters can be given together, a message then has to pass both of them
+
+    When filtering, a message on its own is not much use if you want to look it up later,
+    so each match is returned along with its index in the full list as an IndexedMessage
+
+    The two shapes of response are different types so the handler returns an HttpResponse
+    and lets the json method on the builder do the serializing
+
+    FILTERING BY TIME
+
+    Each message is stored as a StoredMessage, its text along with sent_at,
+    the time it was posted in seconds since the Unix epoch taken from SystemTime when it was added
+
+    since keeps the messages with a sent_at after the given time, so since=<the sent_at of a message>
+    leaves that message out, and the filtered response includes the sent_at of every message
+
+    The since of the index is a time while the since of /messages/poll is an index into the list,
+    the two are separate query structs so each only means one thing
+
+    Everything else still only sees the text, e.g. lookup and the unfiltered index
+
+    SHUTTING DOWN GRACEFULLY
+
+    HttpServer::run starts an actix System and blocks until that system stops
+
+    The server listens for SIGINT and SIGTERM itself, and on either one it stops accepting new connections
+    and gives the workers up to SHUTDOWN_TIMEOUT seconds to finish the requests they are already serving
+    before they are forcibly dropped, SIGQUIT skips the waiting and stops straight away
+
+    We set the timeout explicitly rather than relying on the default so it is visible how long a shutdown can take
+
+    Once run returns every worker is gone, so nothing else can be holding the lock on the messages,
+    which makes this the place to flush the messages somewhere if they were ever persisted
+    As they only live in memory for now we just report how many are being dropped
+
+    RESETTING THE REQUEST COUNT
+
+    POST /admin/reset-count sets request_count back to 0 and returns the value it had before,
+    which is handy for starting a fresh measurement window or for tests
+
+    Cell::replace swaps in the new value and hands back the old one in a single call
+    The reset request itself is not counted, so the next request to the same worker sees a count of 1
+
+    Remember that every worker has its own AppState and so its own request_count,
+    which means this only resets the counter of whichever worker happens to handle the reset request
+    The response includes the server_id so you can tell which worker that was,
+    and the other workers carry on counting from where they were
+    With 8 workers there is no way to reset all of the counters with a single request,
+    for that the counter would have to be shared between the workers like the messages are
+
+    e.g. curl -X POST localhost:8080/admin/reset-count
+    gives {"server_id":3,"previous_count":12}
+
+    CORS
+
+    A page served from another origin can only read our responses if we say it may,
+    which is what the Cross-Origin Resource Sharing headers are for
+
+    The actix-cors crate provides this as a middleware, built in the cors function and added with wrap like the Logger
+    It answers the preflight OPTIONS requests a browser sends before a POST with a JSON body on its own,
+    and adds Access-Control-Allow-Origin to the responses for the origins it allows
+
+    Every route here is either a GET or a POST, so those are the only methods that are allowed,
+    and the preflight answer can be cached by the browser for an hour
+
+    MessageApp::new takes the list of origins that are allowed, e.g. http://localhost:3000
+    An empty list allows any origin, which is handy while developing,
+    the Origin of the request is then echoed back as the allowed origin
+
+    main reads the list from the ALLOWED_ORIGINS environment variable as a comma separated list
+    Blank entries are dropped, so ALLOWED_ORIGINS= or a trailing comma doesn't add an empty origin,
+    and an empty variable gives the empty list that allows any origin
+
+    e.g. curl -i -H 'Origin: http://localhost:3000' localhost:8080/
+    has access-control-allow-origin: http://localhost:3000 in the response headers
+
+    POSTING SEVERAL MESSAGES AT ONCE
+
+    POST /send/bulk takes a list of messages, e.g. {"messages": ["a", "b", "c"]},
+    and pushes all of them onto the list while holding the lock once,
+    so no other request can slip a message in between them
+
+    The whole batch counts as one request and the response has the full list of messages
+    along with how many were added
+
+    It is its own resource with the same JsonConfig error handler as /send,
+    but with a larger limit as a batch is naturally bigger than a single message
+
+    e.g. curl -X POST -H 'Content-Type: application/json' localhost:8080/send/bulk -d '{"messages":["a","b"]}'
+    gives {"server_id":2,"request_count":1,"added":2,"messages":["a","b"]}
+
+    METRICS
+
+    GET /metrics gives counters in the plain text format Prometheus scrapes, one metric_name value line each,
+    with a # TYPE comment saying whether it is a counter which only goes up or a gauge which can go either way
+
+    request_count can't be used for this as each worker has its own, and a scrape only reaches one worker
+    So there is also a Metrics struct which is created once in run and shared by every worker through an Arc,
+    in the same way as the messages are, and AppState holds a clone of that Arc
+
+    The counters are AtomicUsize rather than being behind a Mutex,
+    fetch_add adds to one from any thread without taking a lock, and the order of the updates doesn't matter
+    so Ordering::Relaxed is enough
+
+    Every handler calls metrics.hit with the name of its endpoint which adds one to the total
+    and one to the counter for that endpoint, the per endpoint counters are labelled with endpoint="..."
+    A badly formed body for /send or /send/bulk is counted by post_error as it never reaches the handler
+    The number of messages is read from the list itself when the metrics are rendered
+
+    e.g. curl localhost:8080/metrics gives
+        # TYPE messages_requests_total counter
+        messages_requests_total 7
+        # TYPE messages_stored gauge
+        messages_stored 2
+        # TYPE messages_endpoint_requests_total counter
+        messages_endpoint_requests_total{endpoint="index"} 3
+        ...
+
+    LONG POLLING
+
+    GET /messages/poll?since=3 waits for messages to be posted instead of the client asking over and over
+    since is the index the new messages start at, i.e. how many messages the client already has,
+    and the response has every message from there on with its index, e.g. {"messages":[{"index":3,"message":"hi"}]}
+    If there are already more than since messages they are returned straight away
+
+    Waiting uses a Condvar, which goes together with the messages Mutex
+    Like the messages and the metrics it is created once in run and each worker's AppState holds a clone of the Arc
+    post and post_bulk call notify_all on it after adding to the list so every waiting poll wakes up
+
+    wait_timeout_while takes the MutexGuard and gives up the lock while it sleeps,
+    then takes it again to check the condition each time it wakes up,
+    so posting a message is never held up by a poll that is waiting
+
+    The waiting would still tie up an actix worker, and with 8 of them 8 polls would stop the server answering anything,
+    so the wait runs inside web::block which moves it to a separate thread pool and gives back a future
+    The handler is registered with to_async and returns that future mapped into a response
+
+    The closure given to web::block has to be Send, which AppState isn't because of the Cell for request_count,
+    so only clones of the two Arcs go into it
+
+    After POLL_TIMEOUT seconds without a new message the response is 200 with an empty list,
+    and the client just polls again with the same since
+
+    e.g. curl 'localhost:8080/messages/poll?since=0' waits until something is posted
+
+    VALIDATING MESSAGES
+
+    The JsonConfig limit only stops a body that is too big to parse, a message of "" or "   " gets through it fine
+    So post checks the message with validate_message before storing it:
+        - it can't be empty or only whitespace
+        - it can't be more than max_message_len characters, counting chars rather than bytes
+          so a message in another script isn't cut shorter than one in ASCII
+
+    A message that breaks either rule is answered with 400 Bad Request and a PostError,
+    the same shape post_error gives for a body that isn't valid JSON,
+    with the error saying which rule it broke, e.g. "message is longer than 280 characters"
+    Nothing is stored and no poll is woken up
+
+    post_bulk checks every message of the batch the same way before adding any of them,
+    and the error starts with the index of the first bad one, e.g. "message 2 must not be empty"
+
+    max_message_len is the third argument to MessageApp::new and is copied into every worker's AppState,
+    main reads it from the MAX_MESSAGE_LEN environment variable or uses DEFAULT_MAX_MESSAGE_LEN
+
+    e.g. curl -X POST -H 'Content-Type: application/json' localhost:8080/send -d '{"message":" "}'
+    gives {"server_id":0,"request_count":1,"error":"message must not be empty"}
     
 ***/
 
@@ -429,22 +602,96 @@
 #[macro_use]
 extern crate actix_web;
 
+use actix_cors::Cors;
 use actix_web::{
     error::{Error, InternalError, JsonPayloadError},
+    http::{header, Method},
     middleware, web, App, HttpResponse, HttpRequest, HttpServer, Result,
 };
+use futures::Future;
 use serde:: {Deserialize, Serialize};
 use std::cell::Cell;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 static SERVER_COUNTER: AtomicUsize = AtomicUsize::new(0);
 const LOG_FORMAT: &'static str = r#""%r" %s %b "%{User-Agent}i" %D"#;
+const SHUTDOWN_TIMEOUT: u64 = 30;
+const CORS_MAX_AGE: usize = 3600;
+const BULK_LIMIT: usize = 64 * 1024;
+const POLL_TIMEOUT: u64 = 30;
+pub const DEFAULT_MAX_MESSAGE_LEN: usize = 280;
+const ENDPOINTS: [&str; 9] = [
+    "index", "send", "send_bulk", "lookup", "clear", "reset_count", "metrics", "poll", "invalid_body",
+];
 
 struct AppState {
     server_id: usize,
     request_count: Cell<usize>,
-    messages: Arc<Mutex<Vec<String>>>,
+    messages: Arc<Mutex<Vec<StoredMessage>>>,
+    new_message: Arc<Condvar>,
+    metrics: Arc<Metrics>,
+    max_message_len: usize,
+}
+
+#[derive(Clone)]
+struct StoredMessage {
+    text: String,
+    sent_at: u64,
+}
+
+impl StoredMessage {
+    fn new(text: String) -> Self {
+        let sent_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        StoredMessage { text, sent_at }
+    }
+}
+
+struct Metrics {
+    requests: AtomicUsize,
+    endpoints: Vec<(&'static str, AtomicUsize)>,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Metrics {
+            requests: AtomicUsize::new(0),
+            endpoints: ENDPOINTS.iter().map(|name| (*name, AtomicUsize::new(0))).collect(),
+        }
+    }
+
+    fn hit(&self, endpoint: &str) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+
+        if let Some((_, count)) = self.endpoints.iter().find(|(name, _)| *name == endpoint) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn render(&self, stored: usize) -> String {
+        let mut out = String::new();
+
+        out.push_str("# TYPE messages_requests_total counter\n");
+        out.push_str(&format!("messages_requests_total {}\n", self.requests.load(Ordering::Relaxed)));
+        out.push_str("# TYPE messages_stored gauge\n");
+        out.push_str(&format!("messages_stored {}\n", stored));
+        out.push_str("# TYPE messages_endpoint_requests_total counter\n");
+
+        for (name, count) in &self.endpoints {
+            out.push_str(&format!(
+                "messages_endpoint_requests_total{{endpoint=\"{}\"}} {}\n",
+                name,
+                count.load(Ordering::Relaxed)
+            ));
+        }
+
+        out
+    }
 }
 
 #[derive(Serialize)]
@@ -454,8 +701,52 @@ struct IndexResponse {
     messages: Vec<String>,
 }
 
+#[derive(Deserialize)]
+struct IndexQuery {
+    contains: Option<String>,
+    since: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct IndexedMessage {
+    index: usize,
+    message: String,
+    sent_at: u64,
+}
+
+impl IndexedMessage {
+    fn new(index: usize, stored: &StoredMessage) -> Self {
+        IndexedMessage {
+            index,
+            message: stored.text.clone(),
+            sent_at: stored.sent_at,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PollQuery {
+    since: usize,
+}
+
+#[derive(Serialize)]
+struct PollResponse {
+    server_id: usize,
+    request_count: usize,
+    messages: Vec<IndexedMessage>,
+}
+
+#[derive(Serialize)]
+struct FilteredIndexResponse {
+    server_id: usize,
+    request_count: usize,
+    messages: Vec<IndexedMessage>,
+}
+
 pub struct MessageApp {
     port: u16,
+    allowed_origins: Vec<String>,
+    max_message_len: usize,
 }
 
 #[derive(Deserialize)]
@@ -470,6 +761,19 @@ struct PostResponse {
     message: String,
 }
 
+#[derive(Deserialize)]
+struct BulkPostInput {
+    messages: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct BulkPostResponse {
+    server_id: usize,
+    request_count: usize,
+    added: usize,
+    messages: Vec<String>,
+}
+
 #[derive(Serialize)]
 struct PostError {
     server_id: usize,
@@ -477,6 +781,12 @@ struct PostError {
     error: String,
 }
 
+#[derive(Serialize)]
+struct ResetCountResponse {
+    server_id: usize,
+    previous_count: usize,
+}
+
 #[derive(Serialize)]
 struct LookupResponse {
     server_id: usize,
@@ -485,24 +795,35 @@ struct LookupResponse {
 }
 
 impl MessageApp {
-    pub fn new(port: u16) -> Self {
+    pub fn new(port: u16, allowed_origins: Vec<String>, max_message_len: usize) -> Self {
         // same as writing:
         // MessageApp { 
-        //    port: port
+        //    port: port,
+        //    allowed_origins: allowed_origins,
+        //    max_message_len: max_message_len
         // }
-        MessageApp { port }
+        MessageApp { port, allowed_origins, max_message_len }
     }
 
     pub fn run(&self) -> std::io::Result<()> {
         let messages = Arc::new(Mutex::new(vec![]));
+        let remaining = messages.clone();
+        let new_message = Arc::new(Condvar::new());
+        let metrics = Arc::new(Metrics::new());
+        let allowed_origins = self.allowed_origins.clone();
+        let max_message_len = self.max_message_len;
         println!("Starting http server: 127.0.0.1:{}", self.port);
-        HttpServer::new(move || {
+        let result = HttpServer::new(move || {
             App::new()
                 .data(AppState {
                     server_id: SERVER_COUNTER.fetch_add(1, Ordering::SeqCst),
                     request_count: Cell::new(0),
                     messages: messages.clone(),
+                    new_message: new_message.clone(),
+                    metrics: metrics.clone(),
+                    max_message_len,
                 })
+                .wrap(cors(&allowed_origins))
                 .wrap(middleware::Logger::new(LOG_FORMAT))
                 .service(index)
                 .service(
@@ -514,35 +835,81 @@ impl MessageApp {
                         )
                         .route(web::post().to(post)),
                 )
+                .service(
+                    web::resource("/send/bulk")
+                        .data(
+                            web::JsonConfig::default()
+                                .limit(BULK_LIMIT)
+                                .error_handler(post_error),
+                        )
+                        .route(web::post().to(post_bulk)),
+                )
                 .service(clear)
                 .service(lookup)
+                .service(reset_count)
+                .service(get_metrics)
+                .service(web::resource("/messages/poll").route(web::get().to_async(poll)))
         })
         .bind(("127.0.0.1", self.port))?
         .workers(8)
-        .run()
+        .shutdown_timeout(SHUTDOWN_TIMEOUT)
+        .run();
+
+        let ms = remaining.lock().unwrap();
+        println!("Server stopped, dropping {} messages", ms.len());
+
+        result
     }
 }
 
+fn cors(allowed_origins: &[String]) -> Cors {
+    let cors = allowed_origins
+        .iter()
+        .fold(Cors::new(), |cors, origin| cors.allowed_origin(origin));
+
+    cors.allowed_methods(vec![Method::GET, Method::POST])
+        .allowed_header(header::CONTENT_TYPE)
+        .max_age(CORS_MAX_AGE)
+}
+
 #[get("/")]
-fn index(state: web::Data<AppState>) -> Result<web::Json<IndexResponse>> {
+fn index(state: web::Data<AppState>, query: web::Query<IndexQuery>) -> Result<HttpResponse> {
+    state.metrics.hit("index");
     let request_count = state.request_count.get() + 1;
     state.request_count.set(request_count);
     let ms = state.messages.lock().unwrap();
 
-    Ok(web::Json(IndexResponse {
+    if query.contains.is_none() && query.since.is_none() {
+        return Ok(HttpResponse::Ok().json(IndexResponse {
+            server_id: state.server_id,
+            request_count,
+            messages: ms.iter().map(|m| m.text.clone()).collect(),
+        }));
+    }
+
+    let messages = ms
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| query.contains.as_ref().map_or(true, |needle| m.text.contains(needle.as_str())))
+        .filter(|(_, m)| query.since.map_or(true, |since| m.sent_at > since))
+        .map(|(index, m)| IndexedMessage::new(index, m))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(FilteredIndexResponse {
         server_id: state.server_id,
         request_count,
-        messages: ms.clone(),
+        messages,
     }))
 }
 
 #[get("/lookup/{index}")]
 fn lookup(state: web::Data<AppState>, idx: web::Path<usize>) -> Result<web::Json<LookupResponse>> {
+    state.metrics.hit("lookup");
     let request_count = state.request_count.get() + 1;
     state.request_count.set(request_count);
     
     let ms = state.messages.lock().unwrap();
-    let result = ms.get(idx.into_inner()).cloned();
+    let result = ms.get(idx.into_inner()).map(|m| m.text.clone());
 
     Ok(web::Json(LookupResponse {
         server_id: state.server_id,
@@ -553,6 +920,7 @@ fn lookup(state: web::Data<AppState>, idx: web::Path<usize>) -> Result<web::Json
 
 #[post("/clear")]
 fn clear(state: web::Data<AppState>) -> Result<web::Json<IndexResponse>> {
+    state.metrics.hit("clear");
     let request_count = state.request_count.get() + 1;
     state.request_count.set(request_count);
 
@@ -566,23 +934,142 @@ fn clear(state: web::Data<AppState>) -> Result<web::Json<IndexResponse>> {
     }))
 }
 
-fn post(msg: web::Json<PostInput>, state: web::Data<AppState>) -> Result<web::Json<PostResponse>> {
+#[post("/admin/reset-count")]
+fn reset_count(state: web::Data<AppState>) -> Result<web::Json<ResetCountResponse>> {
+    state.metrics.hit("reset_count");
+    let previous_count = state.request_count.replace(0);
+
+    Ok(web::Json(ResetCountResponse {
+        server_id: state.server_id,
+        previous_count,
+    }))
+}
+
+#[get("/metrics")]
+fn get_metrics(state: web::Data<AppState>) -> Result<HttpResponse> {
+    state.metrics.hit("metrics");
+    let stored = state.messages.lock().unwrap().len();
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(state.metrics.render(stored)))
+}
+
+fn post(msg: web::Json<PostInput>, state: web::Data<AppState>) -> Result<HttpResponse> {
+    state.metrics.hit("send");
     let request_count = state.request_count.get() + 1;
     state.request_count.set(request_count);
 
+    if let Err(error) = validate_message(&msg.message, state.max_message_len) {
+        return Ok(invalid_message(&state, request_count, error));
+    }
+
     let mut ms = state.messages.lock().unwrap();
-    ms.push(msg.message.clone());
+    ms.push(StoredMessage::new(msg.message.clone()));
+    state.new_message.notify_all();
 
-    Ok(web::Json(PostResponse {
+    Ok(HttpResponse::Ok().json(PostResponse {
         server_id: state.server_id,
         request_count,
         message: msg.message.clone(),
     }))
 }
 
+fn post_bulk(input: web::Json<BulkPostInput>, state: web::Data<AppState>) -> Result<HttpResponse> {
+    state.metrics.hit("send_bulk");
+    let request_count = state.request_count.get() + 1;
+    state.request_count.set(request_count);
+
+    for (i, message) in input.messages.iter().enumerate() {
+        if let Err(error) = validate_message(message, state.max_message_len) {
+            let error = error.replacen("message", &format!("message {}", i), 1);
+            return Ok(invalid_message(&state, request_count, error));
+        }
+    }
+
+    let added = input.messages.len();
+    let mut ms = state.messages.lock().unwrap();
+    ms.extend(input.into_inner().messages.into_iter().map(StoredMessage::new));
+    state.new_message.notify_all();
+
+    Ok(HttpResponse::Ok().json(BulkPostResponse {
+        server_id: state.server_id,
+        request_count,
+        added,
+        messages: ms.iter().map(|m| m.text.clone()).collect(),
+    }))
+}
+
+fn validate_message(message: &str, max_len: usize) -> std::result::Result<(), String> {
+    if message.trim().is_empty() {
+        return Err("message must not be empty".to_owned());
+    }
+
+    if message.chars().count() > max_len {
+        return Err(format!("message is longer than {} characters", max_len));
+    }
+
+    Ok(())
+}
+
+fn invalid_message(state: &AppState, request_count: usize, error: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(PostError {
+        server_id: state.server_id,
+        request_count,
+        error,
+    })
+}
+
+fn poll(
+    state: web::Data<AppState>,
+    query: web::Query<PollQuery>,
+) -> impl Future<Item = HttpResponse, Error = Error> {
+    state.metrics.hit("poll");
+    let request_count = state.request_count.get() + 1;
+    state.request_count.set(request_count);
+
+    let server_id = state.server_id;
+    let messages = state.messages.clone();
+    let new_message = state.new_message.clone();
+    let since = query.since;
+
+    web::block(move || -> Result<Vec<IndexedMessage>, ()> {
+        Ok(wait_for_messages(&messages, &new_message, since, Duration::from_secs(POLL_TIMEOUT)))
+    })
+    .then(move |res| -> Result<HttpResponse> {
+        match res {
+            Ok(messages) => Ok(HttpResponse::Ok().json(PollResponse {
+                server_id,
+                request_count,
+                messages,
+            })),
+            Err(_) => Ok(HttpResponse::InternalServerError().finish()),
+        }
+    })
+}
+
+fn wait_for_messages(
+    messages: &Mutex<Vec<StoredMessage>>,
+    new_message: &Condvar,
+    since: usize,
+    timeout: Duration,
+) -> Vec<IndexedMessage> {
+    let ms = messages.lock().unwrap();
+    let (ms, _) = new_message
+        .wait_timeout_while(ms, timeout, |ms| ms.len() <= since)
+        .unwrap();
+
+    ms.iter()
+        .enumerate()
+        .skip(since)
+        .map(|(index, m)| IndexedMessage::new(index, m))
+        .collect()
+}
+
 fn post_error(err: JsonPayloadError, req: &HttpRequest) -> Error {
     let extns = req.extensions();
     let state = extns.get::<web::Data<AppState>>().unwrap();
+    state.metrics.hit("invalid_body");
     let request_count = state.request_count.get() + 1;
     state.request_count.set(request_count);
     let post_error = PostError {
@@ -605,4 +1092,258 @@ fn post_error(err: JsonPayloadError, req: &HttpRequest) -> Error {
 //     Ok(web::Json(IndexResponse {
 //         message: hello.to_owned(),
 //     }))
-// }
\ No newline at end of file
+// }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test;
+
+    fn test_state() -> AppState {
+        AppState {
+            server_id: 0,
+            request_count: Cell::new(0),
+            messages: Arc::new(Mutex::new(vec![])),
+            new_message: Arc::new(Condvar::new()),
+            metrics: Arc::new(Metrics::new()),
+            max_message_len: 10,
+        }
+    }
+
+    fn texts(state: &AppState) -> Vec<String> {
+        state.messages.lock().unwrap().iter().map(|m| m.text.clone()).collect()
+    }
+
+    fn stored(text: &str, sent_at: u64) -> StoredMessage {
+        StoredMessage {
+            text: text.to_owned(),
+            sent_at,
+        }
+    }
+
+    fn allow_origin(allowed_origins: &[String], origin: &str) -> Option<String> {
+        let mut app = test::init_service(
+            App::new()
+                .data(test_state())
+                .wrap(cors(allowed_origins))
+                .service(index),
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .header(header::ORIGIN, origin)
+            .to_request();
+        let resp = test::call_service(&mut app, req);
+
+        resp.headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .map(|v| v.to_str().unwrap().to_owned())
+    }
+
+    #[test]
+    fn post_bulk_adds_every_message() {
+        let state = web::Data::new(test_state());
+        state.messages.lock().unwrap().push(StoredMessage::new("first".to_owned()));
+
+        let mut app = test::init_service(
+            App::new()
+                .register_data(state.clone())
+                .route("/send/bulk", web::post().to(post_bulk)),
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/send/bulk")
+            .set_json(&serde_json::json!({ "messages": ["a", "b", "c"] }))
+            .to_request();
+        let resp: serde_json::Value = test::read_response_json(&mut app, req);
+
+        assert_eq!(resp["added"], 3);
+        assert_eq!(resp["request_count"], 1);
+        assert_eq!(resp["messages"], serde_json::json!(["first", "a", "b", "c"]));
+        assert_eq!(texts(&state), vec!["first", "a", "b", "c"]);
+    }
+
+    #[test]
+    fn post_rejects_empty_and_overly_long_messages() {
+        let state = web::Data::new(test_state());
+
+        let mut app = test::init_service(
+            App::new()
+                .register_data(state.clone())
+                .route("/send", web::post().to(post))
+                .route("/send/bulk", web::post().to(post_bulk)),
+        );
+
+        let cases = vec![
+            ("/send", serde_json::json!({ "message": "" }), "message must not be empty"),
+            ("/send", serde_json::json!({ "message": "  \n " }), "message must not be empty"),
+            ("/send", serde_json::json!({ "message": "eleven char" }), "message is longer than 10 characters"),
+            ("/send/bulk", serde_json::json!({ "messages": ["ok", " "] }), "message 1 must not be empty"),
+        ];
+
+        for (uri, body, error) in cases {
+            let req = test::TestRequest::post().uri(uri).set_json(&body).to_request();
+            let resp = test::call_service(&mut app, req);
+            assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+            let resp: serde_json::Value = serde_json::from_slice(&test::read_body(resp)).unwrap();
+            assert_eq!(resp["error"], error);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/send")
+            .set_json(&serde_json::json!({ "message": "ten chars!" }))
+            .to_request();
+        let resp = test::call_service(&mut app, req);
+
+        assert!(resp.status().is_success());
+        assert_eq!(texts(&state), vec!["ten chars!"]);
+    }
+
+    #[test]
+    fn index_filters_by_substring_and_time() {
+        let state = web::Data::new(test_state());
+        state.messages.lock().unwrap().extend(vec![
+            stored("hello old", 100),
+            stored("bye old", 150),
+            stored("hello new", 200),
+            stored("bye new", 300),
+        ]);
+
+        let mut app = test::init_service(App::new().register_data(state.clone()).service(index));
+
+        let mut filtered = |uri: &str| -> Vec<(u64, String, u64)> {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp: serde_json::Value = test::read_response_json(&mut app, req);
+
+            resp["messages"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|m| {
+                    (
+                        m["index"].as_u64().unwrap(),
+                        m["message"].as_str().unwrap().to_owned(),
+                        m["sent_at"].as_u64().unwrap(),
+                    )
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            filtered("/?contains=hello"),
+            vec![(0, "hello old".to_owned(), 100), (2, "hello new".to_owned(), 200)]
+        );
+        assert_eq!(
+            filtered("/?since=150"),
+            vec![(2, "hello new".to_owned(), 200), (3, "bye new".to_owned(), 300)]
+        );
+        assert_eq!(filtered("/?contains=bye&since=150"), vec![(3, "bye new".to_owned(), 300)]);
+        assert!(filtered("/?contains=nothing&since=0").is_empty());
+
+        let req = test::TestRequest::get().uri("/").to_request();
+        let resp: serde_json::Value = test::read_response_json(&mut app, req);
+        assert_eq!(
+            resp["messages"],
+            serde_json::json!(["hello old", "bye old", "hello new", "bye new"])
+        );
+    }
+
+    #[test]
+    fn metrics_count_requests_across_endpoints() {
+        let mut app = test::init_service(
+            App::new()
+                .data(test_state())
+                .service(index)
+                .service(get_metrics)
+                .route("/send", web::post().to(post)),
+        );
+
+        for _ in 0..2 {
+            let req = test::TestRequest::get().uri("/").to_request();
+            test::call_service(&mut app, req);
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/send")
+            .set_json(&serde_json::json!({ "message": "hello" }))
+            .to_request();
+        test::call_service(&mut app, req);
+
+        let req = test::TestRequest::get().uri("/metrics").to_request();
+        let body = test::read_response(&mut app, req);
+        let body = std::str::from_utf8(&body).unwrap();
+        let lines: Vec<&str> = body.lines().collect();
+
+        assert!(lines.contains(&"# TYPE messages_requests_total counter"));
+        assert!(lines.contains(&"messages_requests_total 4"));
+        assert!(lines.contains(&"messages_stored 1"));
+        assert!(lines.contains(&r#"messages_endpoint_requests_total{endpoint="index"} 2"#));
+        assert!(lines.contains(&r#"messages_endpoint_requests_total{endpoint="send"} 1"#));
+        assert!(lines.contains(&r#"messages_endpoint_requests_total{endpoint="metrics"} 1"#));
+        assert!(lines.contains(&r#"messages_endpoint_requests_total{endpoint="clear"} 0"#));
+
+        for line in lines.iter().filter(|l| !l.starts_with('#')) {
+            let mut parts = line.rsplitn(2, ' ');
+            assert!(parts.next().unwrap().parse::<usize>().is_ok(), "bad value in {}", line);
+            assert!(parts.next().unwrap().starts_with("messages_"), "bad name in {}", line);
+        }
+    }
+
+    #[test]
+    fn posting_wakes_up_a_waiting_poll() {
+        let state = web::Data::new(test_state());
+        state.messages.lock().unwrap().push(StoredMessage::new("seen".to_owned()));
+
+        let messages = state.messages.clone();
+        let new_message = state.new_message.clone();
+        let waiter = std::thread::spawn(move || {
+            wait_for_messages(&messages, &new_message, 1, Duration::from_secs(10))
+                .into_iter()
+                .map(|m| (m.index, m.message))
+                .collect::<Vec<_>>()
+        });
+
+        let mut app = test::init_service(
+            App::new()
+                .register_data(state.clone())
+                .route("/send", web::post().to(post)),
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/send")
+            .set_json(&serde_json::json!({ "message": "hello" }))
+            .to_request();
+        test::call_service(&mut app, req);
+
+        assert_eq!(waiter.join().unwrap(), vec![(1, "hello".to_owned())]);
+    }
+
+    #[test]
+    fn wait_for_messages_gives_up_after_the_timeout() {
+        let state = test_state();
+        state.messages.lock().unwrap().push(StoredMessage::new("seen".to_owned()));
+
+        let messages = wait_for_messages(&state.messages, &state.new_message, 1, Duration::from_millis(10));
+
+        assert!(messages.is_empty());
+    }
+
+    #[test]
+    fn cors_allows_any_origin_by_default() {
+        assert_eq!(
+            allow_origin(&[], "http://localhost:3000"),
+            Some("http://localhost:3000".to_owned())
+        );
+    }
+
+    #[test]
+    fn cors_allows_configured_origin() {
+        let allowed = vec!["http://localhost:3000".to_owned()];
+
+        assert_eq!(
+            allow_origin(&allowed, "http://localhost:3000"),
+            Some("http://localhost:3000".to_owned())
+        );
+    }
+}
@@ -28,11 +28,25 @@
     
 ***/
 
-use messages_actix::MessageApp;
+use messages_actix::{MessageApp, DEFAULT_MAX_MESSAGE_LEN};
 
 fn main() -> std::io::Result<()> {
     std::env::set_var("RUST_LOG", "actix-web=info");
     env_logger::init();
-    let app = MessageApp::new(8080);
+    let allowed_origins = std::env::var("ALLOWED_ORIGINS")
+        .map(|origins| {
+            origins
+                .split(',')
+                .map(|o| o.trim())
+                .filter(|o| !o.is_empty())
+                .map(|o| o.to_owned())
+                .collect()
+        })
+        .unwrap_or_else(|_| vec![]);
+    let max_message_len = std::env::var("MAX_MESSAGE_LEN")
+        .ok()
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(DEFAULT_MAX_MESSAGE_LEN);
+    let app = MessageApp::new(8080, allowed_origins, max_message_len);
     app.run()
 }
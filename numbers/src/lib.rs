@@ -37,8 +37,19 @@
 
     ATTRIBUTES
     They come in two forms #[...] and #![...] which annotate the item they precede.
+
+    GENERIC WRITERS
+    Anything that implements std::io::Write can be written to, e.g. stdout, a file, or a Vec<u8>.
+    Taking a generic W: Write instead of printing directly lets tests capture the output in a buffer.
+    output_sequence passes a locked stdout to output_sequence_to, so printing and the tests share one code path.
+
+    FORMATTING WITHOUT PRINTING
+    format_sequence only builds the String, so the caller decides where it goes.
+    Keeping formatting apart from I/O means it can be tested by comparing strings.
 ***/
 
+use std::io::{self, Write};
+
 // pub keyword is a privacy identifier which specifies that 
 // this function should be publicly accessible to user’s of our crate
 pub fn print(limit: u8) {
@@ -75,6 +86,8 @@ fn generate_sequence(limit: u8) -> Vec<u8> {
     (1..=limit).collect()
 }
 
+// kept to compare with generate_sequence, nothing calls it
+#[allow(dead_code)]
 fn generate_sequence_2(limit: u8) -> Vec<u8> {
     
     // By default a vector created with new, is the same as one created with vec![], and does not allocate.
@@ -97,9 +110,27 @@ fn generate_sequence_2(limit: u8) -> Vec<u8> {
 
 // slice example
 fn output_sequence(numbers: &[u8]) {
-    for n in numbers {
-        println!("{}", n);
+    // locking stdout once is also faster than println! locking it for every line
+    output_sequence_to(&mut io::stdout().lock(), numbers).expect("failed to write to stdout");
+}
+
+// joins the numbers with the separator without printing anything
+pub fn format_sequence(numbers: &[u8], separator: &str) -> String {
+    // each number becomes a String first as join works on a slice of strings
+    numbers
+        .iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+// writes each number on its own line to any writer, and nothing at all for an empty slice
+pub fn output_sequence_to<W: Write>(w: &mut W, numbers: &[u8]) -> io::Result<()> {
+    if numbers.is_empty() {
+        return Ok(());
     }
+
+    writeln!(w, "{}", format_sequence(numbers, "\n"))
 }
 
 // array example
@@ -171,4 +202,37 @@ fn generated_sequence_should_work() {
 
     // use assert_eq to ensure that the output of our generate_sequence function is what we expect it to be
     assert_eq!(result, &[1, 2, 3, 4, 5, 6, 7]);
-}
\ No newline at end of file
+}
+
+#[test]
+fn output_sequence_to_should_write_lines() {
+    // a Vec<u8> implements Write so we can capture the output instead of printing it
+    let mut buffer = Vec::new();
+    output_sequence_to(&mut buffer, &[1, 2, 3]).unwrap();
+
+    assert_eq!(buffer, b"1\n2\n3\n");
+}
+
+#[test]
+fn output_sequence_to_should_write_nothing_for_empty_slice() {
+    let mut buffer = Vec::new();
+    output_sequence_to(&mut buffer, &[]).unwrap();
+
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn format_sequence_of_empty_slice_is_empty() {
+    assert_eq!(format_sequence(&[], ", "), "");
+}
+
+#[test]
+fn format_sequence_of_single_number_has_no_separator() {
+    assert_eq!(format_sequence(&[7], ", "), "7");
+}
+
+#[test]
+fn format_sequence_should_join_with_separator() {
+    assert_eq!(format_sequence(&[1, 2, 3], ", "), "1, 2, 3");
+    assert_eq!(format_sequence(&[1, 2, 3], "\n"), "1\n2\n3");
+}
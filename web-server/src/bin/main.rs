@@ -50,54 +50,347 @@
 
     The ThreadPool will go out of scope at the end of main,
     and the drop implementation will run
+
+    LIMITING THE REQUEST BODY SIZE
+
+    A client can claim any Content-Length it likes,
+    so anything that reads the body based on that header could be made to allocate huge amounts of memory
+
+    The limit is set in main and passed along to handle_connection for every connection
+
+    Before doing anything else the handler looks for a Content-Length header in the bytes it already read,
+    and if the declared body is larger than the limit it responds with 413 Payload Too Large
+    and closes the connection without ever reading the body
+
+    The bytes it already read are a single read of at most REQUEST_BUFFER_SIZE (1024) bytes,
+    so only a Content-Length header that arrives within that window is seen
+    A request whose request line and headers run past 1024 bytes, or which arrives over several reads,
+    has the rest of its headers cut off and is dispatched as if it had no Content-Length at all
+    That is fine here as no handler reads a body yet, but one that does would have to check the length again
+
+    REGISTERING ROUTES
+
+    The pages the server knows about are registered on a Router in main
+    instead of being hardcoded in handle_connection, see the router module in the library
+
+    The router is built once and shared with every connection through an Arc,
+    cloning the Arc for each job just bumps the reference count
+
+    handle_connection parses the request, checks the body size, and hands the request to the router,
+    a request that can't be parsed at all gets a 400 Bad Request
+
+    TIMING OUT SLOW CLIENTS
+
+    A client can open a connection and then send its request a byte at a time, or not at all,
+    and every such connection ties up one of the few workers in the pool (a Slowloris attack)
+
+    set_read_timeout makes a read that waits longer than the timeout return an error,
+    WouldBlock or TimedOut depending on the platform, instead of blocking forever
+
+    When that happens the handler just returns, dropping the stream closes the connection
+    and the worker is free to pick up the next job
+
+    Like the body size limit the timeout is set in main and passed along to serve_connection,
+    which sets it on the TcpStream and then hands the stream to handle_connection
+
+    handle_connection itself takes anything that implements Read and Write, like send_response,
+    so a test can feed it a request from memory and look at the response it writes
+
+    ERROR PAGES
+
+    The 404 and 500 pages come from an ErrorPages in the library, see the error_pages module,
+    with the paths taken from the NOT_FOUND_PAGE and SERVER_ERROR_PAGE environment variables
+    e.g. NOT_FOUND_PAGE=pages/missing.html cargo run
+
+    The handlers that need the pages are closures which hold on to an Arc of the ErrorPages,
+    and handle_index sends the 500 page when index.html can't be read instead of panicking
+
+    LIMITING CONCURRENT CONNECTIONS
+
+    At most MAX_CONNECTIONS connections are handled or waiting for a worker at any one time,
+    see the semaphore module in the library
+
+    Each accepted stream first has to get a permit from the semaphore,
+    the permit is moved into the job along with the stream and is given back when the job finishes
+
+    When there is no permit left the connection gets a 503 Service Unavailable straight away
+    instead of joining the queue for the pool
+
+    WRITING THE RESPONSE
+
+    A client can hang up before the response is written, writing then fails with a broken pipe
+    or a connection reset, and unwrapping that would panic and take the worker thread down with it
+
+    A client going away is nothing unusual, so send_response just prints the error and returns,
+    it takes anything that implements Write so a failing writer can stand in for the stream in a test
+
+    write_all is used rather than write as write can return after writing only part of the response
+
+    METRICS
+
+    GET /metrics shows how busy the pool is and how many connections have been served,
+    see the metrics module in the library
+
+    The ServerMetrics is built from pool.stats() and shared through an Arc like the router,
+    the route holds one clone and each job another to count its connection once it is done
 ***/
 
 use std::io::prelude::*;
 use std::net::TcpListener;
 use std::net::TcpStream;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use web_server::error_pages::ErrorPages;
+use web_server::metrics::ServerMetrics;
+use web_server::router::{Request, Response, Router};
+use web_server::semaphore::Semaphore;
 use web_server::ThreadPool;
+use std::env;
 use std::fs;
 
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+const REQUEST_BUFFER_SIZE: usize = 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_CONNECTIONS: usize = 16;
+
 fn main() {
     let listener = TcpListener::bind("127.0.0.1:7878").unwrap();
     let pool = ThreadPool::new(4);
+    let max_body_size = MAX_BODY_SIZE;
+    let read_timeout = READ_TIMEOUT;
+    let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+    let metrics = Arc::new(ServerMetrics::new(pool.stats()));
+
+    let error_pages = Arc::new(ErrorPages::new(
+        env::var("NOT_FOUND_PAGE").unwrap_or_else(|_| "404.html".to_string()),
+        env::var("SERVER_ERROR_PAGE").unwrap_or_else(|_| "500.html".to_string()),
+    ));
+
+    let mut router = Router::new();
+
+    let pages = Arc::clone(&error_pages);
+    router.get("/", move |request| handle_index(request, &pages));
+
+    let pages = Arc::clone(&error_pages);
+    router.get("/sleep", move |request| handle_sleep(request, &pages));
+
+    let handler = Arc::clone(&metrics);
+    router.get("/metrics", move |request| handler.handle(request));
+
+    let pages = Arc::clone(&error_pages);
+    router.not_found(move |_request| pages.not_found());
+
+    let router = Arc::new(router);
 
     for stream in listener.incoming().take(2) {
         let stream = stream.unwrap();
 
-        pool.execute(|| {
-            handle_connection(stream);
+        let permit = match connections.try_acquire() {
+            Some(permit) => permit,
+            None => {
+                reject_connection(stream);
+                continue;
+            }
+        };
+
+        let router = Arc::clone(&router);
+        let metrics = Arc::clone(&metrics);
+
+        pool.execute(move || {
+            serve_connection(stream, &router, max_body_size, read_timeout);
+            metrics.connection_served();
+            drop(permit);
         })
     }
 
     println!("Shutting down.");
 }
 
-fn handle_connection(mut stream: TcpStream) {
-    let mut buffer = [0; 1024];
+fn serve_connection(
+    mut stream: TcpStream,
+    router: &Router,
+    max_body_size: usize,
+    read_timeout: Duration,
+) {
+    if let Err(e) = stream.set_read_timeout(Some(read_timeout)) {
+        println!("Closing connection, could not set read timeout: {}", e);
+        return;
+    }
 
-    stream.read(&mut buffer).unwrap();
+    handle_connection(&mut stream, router, max_body_size);
+}
 
-    let get = b"GET / HTTP/1.1\r\n";
-    let sleep = b"GET /sleep HTTP/1.1\r\n";
+fn handle_connection<S: Read + Write>(stream: &mut S, router: &Router, max_body_size: usize) {
+    let mut buffer = [0; REQUEST_BUFFER_SIZE];
 
-    let (status_line, filename) = if buffer.starts_with(get) {
-        ("HTTP/1.1 200 OK\r\n\r\n", "index.html")
-    } else if  buffer.starts_with(sleep) {
-        thread::sleep(Duration::from_secs(5));
-        ("HTTP/1.1 200 OK\r\n\r\n", "index.html")
-    } else {
-        ("HTTP/1.1 404 NOT FOUND\r\n\r\n", "404.html")
+    if let Err(e) = stream.read(&mut buffer) {
+        println!("Closing connection after failed read: {}", e);
+        return;
+    }
+
+    let response = match Request::parse(&buffer) {
+        Some(request) => {
+            let length = request
+                .header("content-length")
+                .and_then(|value| value.parse::<usize>().ok());
+
+            match length {
+                Some(length) if length > max_body_size => {
+                    Response::new("HTTP/1.1 413 PAYLOAD TOO LARGE\r\nConnection: close", String::new())
+                }
+                _ => router.dispatch(&request),
+            }
+        }
+        None => Response::new("HTTP/1.1 400 BAD REQUEST", String::new()),
     };
 
-    let contents = fs::read_to_string(filename).unwrap();
+    send_response(stream, response);
+}
+
+fn send_response<W: Write>(stream: &mut W, response: Response) {
+    if let Err(e) = stream.write_all(&response.into_bytes()) {
+        println!("Failed to write response: {}", e);
+        return;
+    }
+
+    if let Err(e) = stream.flush() {
+        println!("Failed to flush response: {}", e);
+    }
+}
+
+fn reject_connection(mut stream: TcpStream) {
+    let response = Response::new("HTTP/1.1 503 SERVICE UNAVAILABLE\r\nConnection: close", String::new());
+
+    if let Err(e) = stream.write_all(&response.into_bytes()) {
+        println!("Failed to reject connection: {}", e);
+    }
+}
+
+fn handle_index(_request: &Request, error_pages: &ErrorPages) -> Response {
+    match fs::read_to_string("index.html") {
+        Ok(contents) => Response::new("HTTP/1.1 200 OK", contents),
+        Err(e) => {
+            println!("Failed to read index.html: {}", e);
+            error_pages.server_error()
+        }
+    }
+}
+
+fn handle_sleep(request: &Request, error_pages: &ErrorPages) -> Response {
+    thread::sleep(Duration::from_secs(5));
+    handle_index(request, error_pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    struct BrokenPipe;
+
+    impl Write for BrokenPipe {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "client went away"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "client went away"))
+        }
+    }
+
+    struct FakeStream {
+        input: io::Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl FakeStream {
+        fn new(request: &str) -> FakeStream {
+            FakeStream {
+                input: io::Cursor::new(request.as_bytes().to_vec()),
+                output: Vec::new(),
+            }
+        }
+    }
 
-    let response = format!(
-        "{}{}",
-        status_line,
-        contents
-    );
+    impl Read for FakeStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for FakeStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
 
-    stream.write(response.as_bytes()).unwrap();
-    stream.flush().unwrap();
-}
\ No newline at end of file
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn router() -> Router {
+        let mut router = Router::new();
+        router.route("POST", "/upload", |_| Response::new("HTTP/1.1 200 OK", "uploaded".to_string()));
+        router
+    }
+
+    fn respond(request: &str, max_body_size: usize) -> String {
+        let mut stream = FakeStream::new(request);
+        handle_connection(&mut stream, &router(), max_body_size);
+
+        String::from_utf8(stream.output).unwrap()
+    }
+
+    #[test]
+    fn handle_connection_rejects_a_body_over_the_limit() {
+        let response = respond("POST /upload HTTP/1.1\r\nContent-Length: 2048\r\n\r\n", 1024);
+        assert!(response.starts_with("HTTP/1.1 413 PAYLOAD TOO LARGE\r\nConnection: close"));
+
+        let response = respond("POST /upload HTTP/1.1\r\nContent-Length: 1024\r\n\r\n", 1024);
+        assert_eq!(response, "HTTP/1.1 200 OK\r\n\r\nuploaded");
+
+        let response = respond("POST /upload HTTP/1.1\r\nContent-Length: lots\r\n\r\n", 1024);
+        assert_eq!(response, "HTTP/1.1 200 OK\r\n\r\nuploaded");
+    }
+
+    #[test]
+    fn handle_connection_rejects_a_malformed_request() {
+        assert!(respond("NONSENSE\r\n\r\n", 1024).starts_with("HTTP/1.1 400 BAD REQUEST"));
+    }
+
+    #[test]
+    fn serve_connection_gives_up_on_a_silent_client() {
+        use std::time::Instant;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        let timeout = Duration::from_millis(200);
+        let started = Instant::now();
+        serve_connection(stream, &router(), 1024, timeout);
+        let elapsed = started.elapsed();
+
+        assert!(elapsed >= timeout, "returned after {:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(5), "returned after {:?}", elapsed);
+
+        let mut received = Vec::new();
+        client.read_to_end(&mut received).unwrap();
+        assert!(received.is_empty());
+    }
+
+    #[test]
+    fn send_response_survives_a_broken_pipe() {
+        send_response(&mut BrokenPipe, Response::new("HTTP/1.1 200 OK", "hello".to_string()));
+    }
+
+    #[test]
+    fn send_response_writes_the_whole_response() {
+        let mut written = Vec::new();
+        send_response(&mut written, Response::new("HTTP/1.1 200 OK", "hello".to_string()));
+
+        assert!(written.starts_with(b"HTTP/1.1 200 OK"));
+        assert!(written.ends_with(b"hello"));
+    }
+}
@@ -0,0 +1,85 @@
+/***
+ *
+ *
+    ERROR PAGES
+
+    The server used to read 404.html with fs::read_to_string(...).unwrap(),
+    so if the file was missing or unreadable the worker thread panicked instead of answering
+
+    ErrorPages holds the paths of the page to send for a 404 and for a 500,
+    which main takes from the NOT_FOUND_PAGE and SERVER_ERROR_PAGE environment variables,
+    falling back to 404.html and 500.html in the current directory
+
+    The file is read each time a page is needed, so a page can be edited without restarting the server
+
+    When a page can't be read we use unwrap_or_else to send a small built in body instead,
+    DEFAULT_404 or DEFAULT_500, so an error page going missing can never take down a worker
+
+    The 500 page is for when the server can't produce the page it was asked for,
+    e.g. index.html going missing
+
+***/
+
+use crate::router::Response;
+use std::fs;
+use std::path::PathBuf;
+
+pub const DEFAULT_404: &str = "<!DOCTYPE html>\n<html lang=\"en\">\n  <head>\n    <meta charset=\"utf-8\">\n    <title>Not Found</title>\n  </head>\n  <body>\n    <h1>404 Not Found</h1>\n  </body>\n</html>\n";
+
+pub const DEFAULT_500: &str = "<!DOCTYPE html>\n<html lang=\"en\">\n  <head>\n    <meta charset=\"utf-8\">\n    <title>Internal Server Error</title>\n  </head>\n  <body>\n    <h1>500 Internal Server Error</h1>\n  </body>\n</html>\n";
+
+pub struct ErrorPages {
+    pub not_found: PathBuf,
+    pub server_error: PathBuf,
+}
+
+impl ErrorPages {
+    pub fn new<P: Into<PathBuf>, Q: Into<PathBuf>>(not_found: P, server_error: Q) -> ErrorPages {
+        ErrorPages {
+            not_found: not_found.into(),
+            server_error: server_error.into(),
+        }
+    }
+
+    pub fn not_found(&self) -> Response {
+        let body = fs::read_to_string(&self.not_found).unwrap_or_else(|_| DEFAULT_404.to_string());
+
+        Response::new("HTTP/1.1 404 NOT FOUND", body)
+    }
+
+    pub fn server_error(&self) -> Response {
+        let body = fs::read_to_string(&self.server_error).unwrap_or_else(|_| DEFAULT_500.to_string());
+
+        Response::new("HTTP/1.1 500 INTERNAL SERVER ERROR", body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_pages_fall_back_to_the_defaults() {
+        let pages = ErrorPages::new("does-not-exist/404.html", "does-not-exist/500.html");
+
+        let not_found = pages.not_found();
+        assert_eq!(not_found.status_line, "HTTP/1.1 404 NOT FOUND");
+        assert_eq!(not_found.body, DEFAULT_404);
+
+        let server_error = pages.server_error();
+        assert_eq!(server_error.status_line, "HTTP/1.1 500 INTERNAL SERVER ERROR");
+        assert_eq!(server_error.body, DEFAULT_500);
+    }
+
+    #[test]
+    fn existing_page_is_served() {
+        let path = std::env::temp_dir().join(format!("web-server-404-{}.html", std::process::id()));
+        fs::write(&path, "<h1>lost</h1>").unwrap();
+
+        let pages = ErrorPages::new(&path, "does-not-exist/500.html");
+        let body = pages.not_found().body;
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(body, "<h1>lost</h1>");
+    }
+}
@@ -213,10 +213,55 @@
         Ultimately left waiting on the first worker to shut down,
         but it never would because the second thread picked up the terminate message
 
+    RESIZING THE POOL
+
+    resize changes the number of workers while the pool is running, e.g. to follow the load on the server
+
+    The pool keeps its own Arc of the receiver so that workers added later share the same queue of jobs,
+    and next_id so that every worker gets a new id even after some have gone away
+
+    Growing just spawns the extra workers the same way new does
+
+    Shrinking sends one Terminate message for each worker that should go,
+    but as explained above there is no telling which workers will pick them up,
+    a busy worker only sees its Terminate once it has finished its job
+
+    So every worker is given the sending side of a second channel and sends its id down it as it stops,
+    resize then waits for as many ids as Terminate messages it sent,
+    takes those workers out of the vector, and joins their threads
+
+    This means resize blocks until that many workers have finished what they were doing,
+    and the jobs already queued ahead of the Terminate messages still run
+
+    A pool of zero workers would never run anything, so resizing to zero panics like new does
+
+    COUNTING JOBS
+
+    PoolStats keeps three numbers about the pool: how many threads it has,
+    how many jobs are waiting in the channel, and how many are being run right now
+
+    They are AtomicUsize so that execute and every worker can update them without taking a lock,
+    and the pool hands out an Arc of them with stats() so something else, e.g. the /metrics page,
+    can read them while the pool goes on working
+
+    execute adds one to queued before sending the job,
+    and a worker moves it from queued to active when it takes the job off the channel
+    and takes it off active once the job returns
+
+    The numbers are each read on their own so they are a snapshot that can be slightly off,
+    e.g. a job between the two updates in the worker is in neither count for a moment,
+    which is fine for watching the server but not for making decisions that have to be exact
+
     
 ***/
 
+pub mod error_pages;
+pub mod metrics;
+pub mod router;
+pub mod semaphore;
+
 use std::thread;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 
@@ -230,6 +275,18 @@ enum Message {
 pub struct ThreadPool {
     workers: Vec<Worker>,
     sender: mpsc::Sender<Message>,
+    receiver: Arc<Mutex<mpsc::Receiver<Message>>>,
+    stopped_sender: mpsc::Sender<usize>,
+    stopped: mpsc::Receiver<usize>,
+    next_id: usize,
+    stats: Arc<PoolStats>,
+}
+
+#[derive(Default)]
+pub struct PoolStats {
+    threads: AtomicUsize,
+    queued: AtomicUsize,
+    active: AtomicUsize,
 }
 
 struct Worker {
@@ -245,7 +302,6 @@ impl ThreadPool {
     /// # Panics
     /// 
     /// The `new` function will panic if the size is zero
-     
     pub fn new(size: usize) -> ThreadPool {
         assert!(size > 0);
 
@@ -253,15 +309,84 @@ impl ThreadPool {
 
         let receiver = Arc::new(Mutex::new(receiver));
 
+        let (stopped_sender, stopped) = mpsc::channel();
+
+        let stats = Arc::new(PoolStats::default());
+        stats.threads.store(size, Ordering::Relaxed);
+
         let mut workers = Vec::with_capacity(size);
 
         for id in 0..size {
-            workers.push(Worker::new(id, Arc::clone(&receiver)));
+            workers.push(Worker::new(id, Arc::clone(&receiver), stopped_sender.clone(), Arc::clone(&stats)));
         }
 
         ThreadPool {
             workers,
-            sender
+            sender,
+            receiver,
+            stopped_sender,
+            stopped,
+            next_id: size,
+            stats,
+        }
+    }
+
+    /// The number of threads in the pool
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// The counts of threads, queued jobs, and running jobs,
+    /// kept up to date as the pool works
+    pub fn stats(&self) -> Arc<PoolStats> {
+        Arc::clone(&self.stats)
+    }
+
+    /// Change the number of threads in the pool
+    /// 
+    /// Shrinking waits for the workers that are told to stop
+    /// to finish the job they are running, if any
+    /// 
+    /// # Panics
+    /// 
+    /// The `resize` function will panic if the new size is zero
+    pub fn resize(&mut self, new_size: usize) {
+        assert!(new_size > 0);
+
+        let size = self.workers.len();
+
+        for _ in size..new_size {
+            let worker = Worker::new(
+                self.next_id,
+                Arc::clone(&self.receiver),
+                self.stopped_sender.clone(),
+                Arc::clone(&self.stats)
+            );
+            self.workers.push(worker);
+            self.next_id += 1;
+        }
+
+        self.stats.threads.store(new_size, Ordering::Relaxed);
+
+        if new_size >= size {
+            return;
+        }
+
+        for _ in new_size..size {
+            self.sender.send(Message::Terminate).unwrap();
+        }
+
+        for _ in new_size..size {
+            let id = self.stopped.recv().unwrap();
+
+            if let Some(idx) = self.workers.iter().position(|w| w.id == id) {
+                let mut worker = self.workers.remove(idx);
+                println!("Removed worker {}", worker.id);
+
+                if let Some(thread) = worker.thread.take() {
+                    thread.join().unwrap();
+                }
+            }
         }
     }
 
@@ -271,6 +396,7 @@ impl ThreadPool {
     {
         let job = Box::new(f);
 
+        self.stats.queued.fetch_add(1, Ordering::Relaxed);
         self.sender.send(Message::NewJob(job)).unwrap();
     }
 }
@@ -295,18 +421,44 @@ impl Drop for ThreadPool {
     }
 }
 
+impl PoolStats {
+    /// The number of threads in the pool
+    pub fn threads(&self) -> usize {
+        self.threads.load(Ordering::Relaxed)
+    }
+
+    /// The number of jobs waiting for a free thread
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// The number of jobs being run right now
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+}
+
 impl Worker {
-    fn new(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Message>>>) -> Worker {
+    fn new(
+        id: usize,
+        receiver: Arc<Mutex<mpsc::Receiver<Message>>>,
+        stopped: mpsc::Sender<usize>,
+        stats: Arc<PoolStats>
+    ) -> Worker {
         let thread = thread::spawn(move || loop {
             let message = receiver.lock().unwrap().recv().unwrap();
 
             match message {
                 Message::NewJob(job) => {
                     println!("Worker {} got a job; executing.", id);
+                    stats.queued.fetch_sub(1, Ordering::Relaxed);
+                    stats.active.fetch_add(1, Ordering::Relaxed);
                     job();
+                    stats.active.fetch_sub(1, Ordering::Relaxed);
                 }
                 Message::Terminate => {
                     println!("Worker {} was told to terminate.", id);
+                    let _ = stopped.send(id);
                     break;
                 }
             }
@@ -317,4 +469,78 @@ impl Worker {
             thread: Some(thread)
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn blocking_jobs(pool: &ThreadPool, count: usize) -> (mpsc::Receiver<()>, Vec<mpsc::Sender<()>>) {
+        let (started_sender, started) = mpsc::channel();
+        let mut releases = Vec::new();
+
+        for _ in 0..count {
+            let started_sender = started_sender.clone();
+            let (release, wait) = mpsc::channel::<()>();
+            releases.push(release);
+
+            pool.execute(move || {
+                started_sender.send(()).unwrap();
+                let _ = wait.recv();
+            });
+        }
+
+        (started, releases)
+    }
+
+    #[test]
+    fn growing_runs_more_jobs_at_once() {
+        let mut pool = ThreadPool::new(1);
+        pool.resize(3);
+        assert_eq!(pool.size(), 3);
+
+        let (started, releases) = blocking_jobs(&pool, 3);
+
+        for _ in 0..3 {
+            assert!(started.recv_timeout(Duration::from_secs(2)).is_ok());
+        }
+
+        drop(releases);
+    }
+
+    #[test]
+    fn shrinking_stops_workers() {
+        let mut pool = ThreadPool::new(3);
+        pool.resize(1);
+        assert_eq!(pool.size(), 1);
+        assert!(pool.workers.iter().all(|w| w.thread.is_some()));
+
+        let (started, releases) = blocking_jobs(&pool, 2);
+
+        assert!(started.recv_timeout(Duration::from_secs(2)).is_ok());
+        assert!(started.recv_timeout(Duration::from_millis(200)).is_err());
+
+        drop(releases);
+        assert!(started.recv_timeout(Duration::from_secs(2)).is_ok());
+    }
+
+    #[test]
+    fn resizing_keeps_worker_ids_unique() {
+        let mut pool = ThreadPool::new(2);
+        pool.resize(1);
+        pool.resize(3);
+
+        let mut ids: Vec<usize> = pool.workers.iter().map(|w| w.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 3);
+    }
+
+    #[test]
+    #[should_panic]
+    fn resizing_to_zero_panics() {
+        let mut pool = ThreadPool::new(2);
+        pool.resize(0);
+    }
+}
@@ -0,0 +1,122 @@
+/***
+ *
+ *
+    METRICS
+
+    Whether the pool is keeping up can't be seen from the outside,
+    a slow page looks the same whether a worker is slow or the request sat in the queue
+
+    GET /metrics answers with a few plain text lines, one number per line:
+
+        pool_threads 4
+        pool_active_jobs 1
+        pool_queued_jobs 0
+        connections_served_total 27
+
+    The pool numbers come from the PoolStats of the ThreadPool, see ThreadPool::stats,
+    and ServerMetrics only adds the count of connections served on top of them
+
+    COUNTING CONNECTIONS
+
+    main calls connection_served at the end of each job, once handle_connection is done with the stream,
+    so a connection is counted whether it got a page, an error, or timed out
+
+    Connections rejected with a 503 never reach the pool and aren't counted
+
+    The count is an AtomicUsize like the pool numbers,
+    so ServerMetrics can be shared through an Arc between the router and every job without a lock
+
+    The page is rendered from the current values each time it is requested,
+    and the request for /metrics itself is counted only once it has been answered
+
+***/
+
+use crate::router::{Request, Response};
+use crate::PoolStats;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+pub struct ServerMetrics {
+    pool: Arc<PoolStats>,
+    connections: AtomicUsize,
+}
+
+impl ServerMetrics {
+    pub fn new(pool: Arc<PoolStats>) -> ServerMetrics {
+        ServerMetrics {
+            pool,
+            connections: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn connection_served(&self) {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn connections_served(&self) -> usize {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    pub fn render(&self) -> String {
+        format!(
+            "pool_threads {}\npool_active_jobs {}\npool_queued_jobs {}\nconnections_served_total {}\n",
+            self.pool.threads(),
+            self.pool.active(),
+            self.pool.queued(),
+            self.connections_served(),
+        )
+    }
+
+    pub fn handle(&self, _request: &Request) -> Response {
+        Response::new("HTTP/1.1 200 OK\r\nContent-Type: text/plain", self.render())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::Router;
+    use crate::ThreadPool;
+    use std::sync::mpsc;
+
+    #[test]
+    fn metrics_route_reports_the_pool_and_connections() {
+        let pool = ThreadPool::new(1);
+        let metrics = Arc::new(ServerMetrics::new(pool.stats()));
+
+        let mut router = Router::new();
+        let handler = Arc::clone(&metrics);
+        router.get("/metrics", move |request| handler.handle(request));
+
+        let (started_sender, started) = mpsc::channel();
+        let mut releases = Vec::new();
+
+        for _ in 0..2 {
+            let started_sender = started_sender.clone();
+            let (release, wait) = mpsc::channel::<()>();
+            releases.push(release);
+
+            pool.execute(move || {
+                started_sender.send(()).unwrap();
+                let _ = wait.recv();
+            });
+        }
+
+        started.recv().unwrap();
+        metrics.connection_served();
+        metrics.connection_served();
+
+        let request = Request::parse(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let response = router.dispatch(&request);
+
+        assert!(response.status_line.starts_with("HTTP/1.1 200 OK"));
+        assert_eq!(
+            response.body,
+            "pool_threads 1\npool_active_jobs 1\npool_queued_jobs 1\nconnections_served_total 2\n"
+        );
+
+        for release in releases {
+            release.send(()).unwrap();
+        }
+    }
+}
@@ -0,0 +1,216 @@
+/***
+ *
+ *
+    ROUTING REQUESTS
+
+    The server started out checking the raw bytes of the request against a couple of hardcoded request lines,
+    which means every new page is another branch in handle_connection
+
+    Instead main registers a handler for each method and path it wants to serve,
+    and handle_connection only has to parse the request and ask the router for a response
+
+    THE REQUEST
+
+    Request::parse takes the bytes read from the stream and pulls out the request line and the headers
+
+    The request line looks like GET /about HTTP/1.1 so splitting it on whitespace gives the method and the path
+
+    Each header line up to the blank line that ends the headers is split on the first colon,
+    anything that is not a valid request just gives back None
+
+    Header names are case insensitive so header compares them with eq_ignore_ascii_case
+
+    THE RESPONSE
+
+    A Response is just the status line and the body,
+    into_bytes glues them together in the format that gets written back to the stream
+
+    THE ROUTER
+
+    The handlers are stored as trait objects, Box<dyn Fn(&Request) -> Response>,
+    so that any function or closure with the right signature can be registered
+
+    The router is shared by all of the threads in the pool so the handlers also have to be Send + Sync,
+    and main wraps the whole router in an Arc
+
+    Routes are kept in a HashMap keyed by the method and the path
+
+    dispatch looks up the handler for the request and calls it,
+    falling back to the not found handler when nothing matches,
+    and if no not found handler was given, to an empty 404 response
+
+***/
+
+use std::collections::HashMap;
+
+type Handler = Box<dyn Fn(&Request) -> Response + Send + Sync + 'static>;
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+}
+
+pub struct Response {
+    pub status_line: String,
+    pub body: String,
+}
+
+#[derive(Default)]
+pub struct Router {
+    routes: HashMap<(String, String), Handler>,
+    not_found: Option<Handler>,
+}
+
+impl Request {
+    pub fn parse(buffer: &[u8]) -> Option<Request> {
+        let request = String::from_utf8_lossy(buffer);
+        let mut lines = request.split("\r\n");
+
+        let mut request_line = lines.next()?.split_whitespace();
+        let method = request_line.next()?.to_owned();
+        let path = request_line.next()?.to_owned();
+
+        let headers = lines
+            .take_while(|line| !line.is_empty())
+            .filter_map(|line| {
+                let mut parts = line.splitn(2, ':');
+                let name = parts.next()?.trim().to_owned();
+                let value = parts.next()?.trim().to_owned();
+
+                Some((name, value))
+            })
+            .collect();
+
+        Some(Request {
+            method,
+            path,
+            headers,
+        })
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl Response {
+    pub fn new(status_line: &str, body: String) -> Response {
+        Response {
+            status_line: status_line.to_owned(),
+            body,
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        format!("{}\r\n\r\n{}", self.status_line, self.body).into_bytes()
+    }
+}
+
+impl Router {
+    pub fn new() -> Router {
+        Router::default()
+    }
+
+    pub fn route<F>(&mut self, method: &str, path: &str, handler: F)
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.routes
+            .insert((method.to_owned(), path.to_owned()), Box::new(handler));
+    }
+
+    pub fn get<F>(&mut self, path: &str, handler: F)
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.route("GET", path, handler);
+    }
+
+    pub fn not_found<F>(&mut self, handler: F)
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.not_found = Some(Box::new(handler));
+    }
+
+    pub fn dispatch(&self, request: &Request) -> Response {
+        let key = (request.method.clone(), request.path.clone());
+
+        match self.routes.get(&key).or(self.not_found.as_ref()) {
+            Some(handler) => handler(request),
+            None => Response::new("HTTP/1.1 404 NOT FOUND", String::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn router() -> Router {
+        let mut router = Router::new();
+        router.get("/", |_| Response::new("HTTP/1.1 200 OK", "home".to_owned()));
+        router.route("POST", "/messages", |request| {
+            let body = request.header("content-type").unwrap_or("none").to_owned();
+            Response::new("HTTP/1.1 201 CREATED", body)
+        });
+        router
+    }
+
+    fn request(raw: &str) -> Request {
+        Request::parse(raw.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn dispatch_calls_the_handler_for_the_method_and_path() {
+        let router = router();
+
+        let response = router.dispatch(&request("GET / HTTP/1.1\r\n\r\n"));
+        assert_eq!(response.status_line, "HTTP/1.1 200 OK");
+        assert_eq!(response.body, "home");
+
+        let response = router.dispatch(&request("POST /messages HTTP/1.1\r\nContent-Type: text/plain\r\n\r\n"));
+        assert_eq!(response.status_line, "HTTP/1.1 201 CREATED");
+        assert_eq!(response.body, "text/plain");
+    }
+
+    #[test]
+    fn dispatch_does_not_match_another_method() {
+        let router = router();
+
+        let response = router.dispatch(&request("POST / HTTP/1.1\r\n\r\n"));
+        assert_eq!(response.status_line, "HTTP/1.1 404 NOT FOUND");
+        assert!(response.body.is_empty());
+    }
+
+    #[test]
+    fn dispatch_falls_back_to_the_not_found_handler() {
+        let mut router = router();
+        router.not_found(|request| Response::new("HTTP/1.1 404 NOT FOUND", format!("no {}", request.path)));
+
+        let response = router.dispatch(&request("GET /missing HTTP/1.1\r\n\r\n"));
+        assert_eq!(response.status_line, "HTTP/1.1 404 NOT FOUND");
+        assert_eq!(response.body, "no /missing");
+
+        let response = router.dispatch(&request("DELETE /messages HTTP/1.1\r\n\r\n"));
+        assert_eq!(response.body, "no /messages");
+    }
+
+    #[test]
+    fn parse_rejects_a_malformed_request_line() {
+        assert!(Request::parse(b"GET\r\nHost: localhost\r\n\r\n").is_none());
+        assert!(Request::parse(b"\r\n\r\n").is_none());
+        assert!(Request::parse(b"").is_none());
+
+        let parsed = request("GET /about HTTP/1.1\r\nHost: localhost\r\nbroken header\r\nX-Id: 7\r\n\r\n");
+        assert_eq!(parsed.method, "GET");
+        assert_eq!(parsed.path, "/about");
+        assert_eq!(parsed.header("host"), Some("localhost"));
+        assert_eq!(parsed.header("x-id"), Some("7"));
+        assert_eq!(parsed.headers.len(), 2);
+    }
+}
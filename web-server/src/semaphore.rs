@@ -0,0 +1,146 @@
+/***
+ *
+ *
+    LIMITING CONCURRENT CONNECTIONS
+
+    The pool only has a fixed number of threads, but the channel in front of it has no limit,
+    so every connection that is accepted while all the workers are busy just waits in the queue
+    holding on to its socket, and a flood of connections can pile up without end
+
+    A counting semaphore puts a cap on how many connections are being handled or waiting at once,
+    independently of how many threads the pool has
+
+    THE SEMAPHORE
+
+    The semaphore is a count of permits behind a Mutex along with a Condvar
+
+    try_acquire takes a permit if there is one left and gives back None straight away otherwise,
+    while acquire waits on the Condvar until another connection hands its permit back
+
+    Condvar::wait releases the lock while waiting and takes it again before returning,
+    and it is called in a loop as a thread can be woken up without a permit actually being free
+
+    Both methods take self: &Arc<Self> so the Permit they give back can keep its own Arc to the semaphore
+
+    RELEASING THE PERMIT
+
+    A Permit gives its permit back when it is dropped, the same way a MutexGuard unlocks its Mutex,
+    so a connection can't forget to release it, even if the handler returns early
+
+    main takes a permit for each connection before handing it to the pool and moves it into the job,
+    so it is dropped once handle_connection is done with the stream
+
+    AT CAPACITY
+
+    main uses try_acquire rather than acquire and answers with a 503 Service Unavailable when no permit is left,
+    waiting would only move the queue from the channel to the listen backlog of the operating system
+
+    The 503 is written from the main thread and the connection is closed without reading the request
+***/
+
+use std::sync::{Arc, Condvar, Mutex};
+
+pub struct Semaphore {
+    permits: Mutex<usize>,
+    released: Condvar,
+}
+
+pub struct Permit {
+    semaphore: Arc<Semaphore>,
+}
+
+impl Semaphore {
+    pub fn new(permits: usize) -> Semaphore {
+        Semaphore {
+            permits: Mutex::new(permits),
+            released: Condvar::new(),
+        }
+    }
+
+    pub fn try_acquire(self: &Arc<Self>) -> Option<Permit> {
+        let mut permits = self.permits.lock().unwrap();
+
+        if *permits == 0 {
+            return None;
+        }
+
+        *permits -= 1;
+
+        Some(Permit {
+            semaphore: Arc::clone(self),
+        })
+    }
+
+    pub fn acquire(self: &Arc<Self>) -> Permit {
+        let mut permits = self.permits.lock().unwrap();
+
+        while *permits == 0 {
+            permits = self.released.wait(permits).unwrap();
+        }
+
+        *permits -= 1;
+
+        Permit {
+            semaphore: Arc::clone(self),
+        }
+    }
+
+    pub fn available(&self) -> usize {
+        *self.permits.lock().unwrap()
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut permits = self.semaphore.permits.lock().unwrap();
+        *permits += 1;
+
+        self.semaphore.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn permits_are_counted_and_given_back_on_drop() {
+        let semaphore = Arc::new(Semaphore::new(2));
+
+        let first = semaphore.try_acquire().unwrap();
+        let second = semaphore.try_acquire().unwrap();
+        assert_eq!(semaphore.available(), 0);
+        assert!(semaphore.try_acquire().is_none());
+
+        drop(first);
+        assert_eq!(semaphore.available(), 1);
+
+        let third = semaphore.try_acquire().unwrap();
+        assert!(semaphore.try_acquire().is_none());
+
+        drop(second);
+        drop(third);
+        assert_eq!(semaphore.available(), 2);
+    }
+
+    #[test]
+    fn acquire_waits_for_a_permit() {
+        let semaphore = Arc::new(Semaphore::new(1));
+        let held = semaphore.acquire();
+
+        let waiting = Arc::clone(&semaphore);
+        let handle = thread::spawn(move || {
+            let _permit = waiting.acquire();
+        });
+
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(semaphore.available(), 0);
+
+        drop(held);
+        handle.join().unwrap();
+
+        assert_eq!(semaphore.available(), 1);
+    }
+}